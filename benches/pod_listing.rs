@@ -0,0 +1,112 @@
+//! Compare listing 10k pods as full objects, as image projections and as metadata
+//!
+//! Run with `cargo bench --bench pod_listing`. Every run pages through the same
+//! fixture served by an in-process API server, 500 pods at a time, and reports
+//! the bytes that crossed the wire next to the time taken. Projections only save
+//! client-side memory and parsing; metadata lists also shrink the payload.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::{create_realistic_pods, FakeApiServer};
+use k8s_openapi::api::core::v1::Pod;
+use kelper::{process_pod, ImageQuery};
+use kube::api::{ListParams, ObjectList};
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::{Api, Client, Config};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+const PODS: usize = 10_000;
+const CHUNK_SIZE: u32 = 500;
+const ITERATIONS: u32 = 5;
+
+/// Page through a listing, collecting every item
+async fn list_all<T, F, Fut>(mut list: F) -> Vec<T>
+where
+    T: Clone,
+    F: FnMut(ListParams) -> Fut,
+    Fut: std::future::Future<Output = kube::Result<ObjectList<T>>>,
+{
+    let mut items = Vec::new();
+    let mut params = ListParams::default().limit(CHUNK_SIZE);
+    loop {
+        let list = list(params.clone()).await.unwrap();
+        let token = list.metadata.continue_.clone().filter(|t| !t.is_empty());
+        items.extend(list.items);
+        match token {
+            Some(token) => params = params.continue_token(&token),
+            None => break,
+        }
+    }
+    items
+}
+
+/// List every pod as a full object, as Kelper did before projections
+async fn list_full_pods(api: &Api<Pod>) -> usize {
+    let pods = list_all(|params| async move { api.list(&params).await }).await;
+    pods.iter().flat_map(process_pod).count()
+}
+
+/// List the metadata of every pod, as owner and label checks do
+async fn list_pod_metadata(api: &Api<Pod>) -> usize {
+    list_all(|params| async move { api.list_metadata(&params).await })
+        .await
+        .len()
+}
+
+/// Time `ITERATIONS` runs of `run` and report the wire payload of each run
+///
+/// `run` returns the number of images or objects it read.
+async fn measure<F, Fut>(name: &str, server: &FakeApiServer, mut run: F)
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = usize>,
+{
+    let mut total = Duration::ZERO;
+    let mut items = 0;
+    server.bytes_sent.store(0, Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        let started = Instant::now();
+        items = run().await;
+        total += started.elapsed();
+    }
+    let bytes = server.bytes_sent.load(Ordering::Relaxed) / ITERATIONS as usize;
+    println!(
+        "{:<28} {:>8.1} ms/iter {:>10.1} KiB/iter {:>8} items",
+        name,
+        total.as_secs_f64() * 1000.0 / f64::from(ITERATIONS),
+        bytes as f64 / 1024.0,
+        items
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    let server = FakeApiServer::start_with(vec![("pods", create_realistic_pods(PODS))]).await;
+    println!("Listing {} pods, {} per page", PODS, CHUNK_SIZE);
+
+    server.gzip.store(false, Ordering::Relaxed);
+    let kubeconfig = Kubeconfig::read_from(&server.kubeconfig).unwrap();
+    let config = Config::from_custom_kubeconfig(kubeconfig, &KubeConfigOptions::default())
+        .await
+        .unwrap();
+    let pods: Api<Pod> = Api::all(Client::try_from(config).unwrap());
+    measure("full pods, uncompressed", &server, || list_full_pods(&pods)).await;
+    measure("pod metadata, uncompressed", &server, || {
+        list_pod_metadata(&pods)
+    })
+    .await;
+
+    server.gzip.store(true, Ordering::Relaxed);
+    let client = server.client(CHUNK_SIZE).await;
+    let query = ImageQuery {
+        all_namespaces: true,
+        ..Default::default()
+    };
+    measure("projected pods, gzip", &server, || async {
+        client.query_pod_images(&query).await.unwrap().len()
+    })
+    .await;
+    measure("pod metadata, gzip", &server, || list_pod_metadata(&pods)).await;
+}
@@ -1,5 +1,8 @@
 use crate::cli::formats::LogFormat;
 use crate::cli::Commands;
+use crate::image::{RegistryConfig, RegistryResolver};
+use crate::k8s::{ClientOptions, DEFAULT_CHUNK_SIZE};
+use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
 
@@ -16,6 +19,43 @@ pub struct Args {
     #[arg(long = "kubeconfig", global = true)]
     pub kubeconfig: Option<PathBuf>,
 
+    /// Name of the kubeconfig context to use (default: current-context)
+    #[arg(long = "context", global = true)]
+    pub context: Option<String>,
+
+    /// Name of the kubeconfig cluster to use (default: the cluster of the context)
+    #[arg(long = "cluster", global = true)]
+    pub cluster: Option<String>,
+
+    /// Name of the kubeconfig user to use (default: the user of the context)
+    #[arg(long = "user", global = true)]
+    pub user: Option<String>,
+
+    /// Username to impersonate for the operation
+    #[arg(long = "as", global = true)]
+    pub impersonate: Option<String>,
+
+    /// Return large lists in chunks of this many objects rather than all at once (0 disables chunking)
+    #[arg(long = "chunk-size", global = true, default_value_t = DEFAULT_CHUNK_SIZE)]
+    pub chunk_size: u32,
+
+    /// Normalize Docker Hub references so that nginx, library/nginx and
+    /// index.docker.io/library/nginx are reported as docker.io/nginx (use --normalize=false to disable)
+    #[arg(
+        long = "normalize",
+        global = true,
+        default_value_t = true,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set
+    )]
+    pub normalize: bool,
+
+    /// Path to a YAML file declaring registry aliases and mirrors (default: $KELPER_REGISTRY_CONFIG)
+    #[arg(long = "registry-config", global = true)]
+    pub registry_config: Option<PathBuf>,
+
     /// Enable verbose logging. Use multiple v's for increased verbosity:
     /// -v: WARN level
     /// -vv: INFO level
@@ -49,4 +89,55 @@ impl Args {
             .clone()
             .or_else(|| std::env::var("KUBECONFIG").ok().map(PathBuf::from))
     }
+
+    /// Build the Kubernetes client options from the command line arguments
+    ///
+    /// A kubeconfig given on the subcommand takes precedence over the global `--kubeconfig`.
+    /// When neither is set, the path is left empty so that `KUBECONFIG` (including multiple
+    /// paths) and `~/.kube/config` are resolved by the client itself.
+    ///
+    /// # Returns
+    ///
+    /// * `ClientOptions` - The options used to create the Kubernetes client
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            kubeconfig: self
+                .command
+                .get_kubeconfig_path()
+                .or_else(|| self.kubeconfig.clone()),
+            context: self.context.clone(),
+            cluster: self.cluster.clone(),
+            user: self.user.clone(),
+            impersonate: self.impersonate.clone(),
+            chunk_size: self.chunk_size,
+        }
+    }
+
+    /// Get the registry config path, respecting the command line argument or falling back to environment variable
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathBuf>` - The path to the registry config file if specified via command line or environment variable
+    pub fn get_registry_config_path(&self) -> Option<PathBuf> {
+        self.registry_config.clone().or_else(|| {
+            std::env::var("KELPER_REGISTRY_CONFIG")
+                .ok()
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+        })
+    }
+
+    /// Build the registry resolver used to report image registries and repositories
+    ///
+    /// # Returns
+    ///
+    /// * `Result<RegistryResolver>` - The resolver configured from the command line arguments,
+    ///   or an error if the registry config file cannot be loaded
+    pub fn registry_resolver(&self) -> Result<RegistryResolver> {
+        let config = match self.get_registry_config_path() {
+            Some(path) => RegistryConfig::from_file(&path)?,
+            None => RegistryConfig::default(),
+        };
+        Ok(RegistryResolver::from_config(config, self.normalize))
+    }
 }
@@ -1,9 +1,17 @@
 use crate::cli::formats::OutputFormat;
-use clap::Subcommand;
+use crate::image::ProviderKind;
+use crate::k8s::{
+    ContainerTypeFilter, GroupBy, ImageQuery, LabelRequirement, LabelTarget, MetadataResource,
+    RegistrySource, DEFAULT_CLUSTER_CONCURRENCY, DEFAULT_RESTART_THRESHOLD,
+};
+use crate::utils::Pattern;
+use clap::{Args, Subcommand};
+use regex::Regex;
 use std::path::PathBuf;
 
 /// CLI command structure for Kelper
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)] // parsed once per invocation
 pub enum Commands {
     /// Get information about Kubernetes resources
     Get {
@@ -11,10 +19,119 @@ pub enum Commands {
         #[command(subcommand)]
         resource: GetImages,
     },
+
+    /// Check Kubernetes resources against a policy, exiting non-zero on violations
+    Check {
+        /// The policy to check
+        #[command(subcommand)]
+        policy: CheckPolicy,
+    },
+
+    /// Audit Kubernetes resources and report risky configurations
+    Audit {
+        /// The report to produce
+        #[command(subcommand)]
+        report: AuditReport,
+    },
+
+    /// Save image inventories and compare them between points in time or clusters
+    Snapshot {
+        /// The snapshot action to perform
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+}
+
+impl Commands {
+    /// Get the kubeconfig path given on the subcommand, if any
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathBuf>` - The path to the kubeconfig file if specified on the subcommand
+    pub fn get_kubeconfig_path(&self) -> Option<PathBuf> {
+        match self {
+            Commands::Get { resource } => resource.get_kubeconfig_path(),
+            Commands::Check { policy } => policy.get_kubeconfig_path(),
+            Commands::Audit { report } => report.get_kubeconfig_path(),
+            Commands::Snapshot { action } => action.get_kubeconfig_path(),
+        }
+    }
+
+    /// Check if this command needs a connection to a Kubernetes cluster
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - False for commands that do not use the current context, such as snapshot diff
+    pub fn requires_cluster(&self) -> bool {
+        // Diffing against a cluster connects to the --against-cluster context on its own
+        !matches!(
+            self,
+            Commands::Snapshot {
+                action: SnapshotAction::Diff { .. }
+            }
+        )
+    }
+
+    /// Get the kubeconfig contexts this command fans out to, if it queries several clusters
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&ContextSelection>` - The selected contexts when `--contexts` or `--all-contexts` is given
+    pub fn context_selection(&self) -> Option<&ContextSelection> {
+        match self {
+            Commands::Get { resource } => resource.context_selection(),
+            _ => None,
+        }
+    }
+}
+
+/// Kubeconfig contexts a command is run against concurrently
+#[derive(Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContextSelection {
+    /// Query these kubeconfig contexts concurrently instead of the current one, e.g. prod-eu,prod-us
+    #[arg(
+        long = "contexts",
+        value_delimiter = ',',
+        conflicts_with_all = ["all_contexts", "context"]
+    )]
+    pub contexts: Vec<String>,
+
+    /// Query every context of the kubeconfig concurrently
+    #[arg(long = "all-contexts", conflicts_with = "context")]
+    pub all_contexts: bool,
+
+    /// Maximum number of clusters queried at the same time with --contexts or --all-contexts
+    #[arg(
+        long = "max-concurrency",
+        default_value_t = DEFAULT_CLUSTER_CONCURRENCY,
+        value_parser = parse_concurrency
+    )]
+    pub max_concurrency: usize,
+}
+
+impl ContextSelection {
+    /// Check if several clusters were selected
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if `--contexts` or `--all-contexts` is given
+    pub fn is_fan_out(&self) -> bool {
+        self.all_contexts || !self.contexts.is_empty()
+    }
+}
+
+/// Parse a concurrency limit, which must allow at least one cluster at a time
+fn parse_concurrency(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(limit) => Ok(limit),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
 /// Resource types that can be queried in the Kubernetes cluster
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)] // parsed once per invocation
 pub enum GetImages {
     /// List pod images and their registries
     Images {
@@ -31,22 +148,70 @@ pub enum GetImages {
         #[arg(short = 'N', long = "node", conflicts_with = "all_namespaces")]
         node: Option<String>,
 
-        /// Filter pods by pod name
+        /// Filter pods by pod name, either exact or a glob such as 'api-*'
         #[arg(short, long)]
-        pod: Option<String>,
+        pod: Option<Pattern>,
+
+        /// Filter pods by a regular expression on the pod name
+        #[arg(long = "pod-regex", value_parser = Regex::new)]
+        pod_regex: Option<Regex>,
+
+        /// Label selector to filter pods on, e.g. app=payments,tier=backend
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Field selector passed through to the API server, e.g. status.phase=Running
+        #[arg(long = "field-selector")]
+        field_selector: Option<String>,
 
-        /// Filter pods by container image registry
+        /// Filter by image name, either exact or a glob such as 'linkerd/*'
+        #[arg(long = "image")]
+        image: Option<Pattern>,
+
+        /// Filter by image tag, either exact or a glob such as 'v1.*'
+        #[arg(long = "tag")]
+        tag: Option<Pattern>,
+
+        /// Only show images from this registry; repeatable, globs such as '*.dkr.ecr.*.amazonaws.com' allowed
         #[arg(short = 'R', long = "registry")]
-        registry: Option<String>,
+        registry: Vec<Pattern>,
+
+        /// Hide images from this registry; repeatable, globs allowed
+        #[arg(long = "exclude-registry")]
+        exclude_registry: Vec<Pattern>,
+
+        /// Only show images hosted by this provider (ecr, artifact-registry, acr, ghcr, ...); repeatable
+        #[arg(long = "provider")]
+        provider: Vec<ProviderKind>,
+
+        /// Only show containers of this type (init, regular, ephemeral or all)
+        #[arg(long = "container-type", default_value = "all")]
+        container_type: ContainerTypeFilter,
 
         /// Query pods across all namespaces
         #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
         all_namespaces: bool,
 
-        /// Output format (default: normal, wide: shows additional columns)
+        /// Summarize the images per image, registry, namespace or node instead of listing them
+        #[arg(long = "group-by")]
+        group_by: Option<GroupBy>,
+
+        /// List the current images, then keep watching and print images as they are added, removed or changed
+        #[arg(
+            short = 'w',
+            long = "watch",
+            conflicts_with_all = ["group_by", "contexts", "all_contexts"]
+        )]
+        watch: bool,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
         #[arg(short = 'o', long = "output", default_value = "normal")]
         output: OutputFormat,
 
+        /// Kubeconfig contexts to query concurrently
+        #[command(flatten)]
+        clusters: ContextSelection,
+
         /// Path to kubeconfig file (default: ~/.kube/config)
         #[arg(long = "kubeconfig")]
         kubeconfig: Option<PathBuf>,
@@ -67,7 +232,219 @@ pub enum GetImages {
         #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
         all_namespaces: bool,
 
-        /// Output format (default: normal, wide: shows additional columns)
+        /// Where to read images from: running pods, workload templates, or all of them
+        #[arg(long = "source", default_value = "workloads")]
+        source: RegistrySource,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Kubeconfig contexts to query concurrently
+        #[command(flatten)]
+        clusters: ContextSelection,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+
+    /// Compare the image each container's spec asks for with the image it is running
+    Drift {
+        /// Kubernetes namespace to query (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Filter pods by pod name, either exact or a glob such as 'api-*'
+        #[arg(short, long)]
+        pod: Option<Pattern>,
+
+        /// Label selector to filter pods on, e.g. app=payments,tier=backend
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Only show containers of this type (init, regular, ephemeral or all)
+        #[arg(long = "container-type", default_value = "all")]
+        container_type: ContainerTypeFilter,
+
+        /// Only show containers whose running image disagrees with the spec or whose tag was re-pushed
+        #[arg(long = "drifted")]
+        drifted: bool,
+
+        /// Query pods across all namespaces
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+
+    /// List the labels of pods, namespaces or nodes, one row per key
+    Labels {
+        /// The kind of objects to read: pods, namespaces or nodes
+        #[arg(default_value = "pods")]
+        resource: MetadataResource,
+
+        /// Kubernetes namespace of the pods, or the namespace to read (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Filter pods by node name, or the node to read
+        #[arg(short = 'N', long = "node", conflicts_with = "all_namespaces")]
+        node: Option<String>,
+
+        /// Filter pods by pod name, either exact or a glob such as 'api-*'
+        #[arg(short, long)]
+        pod: Option<Pattern>,
+
+        /// Label selector to filter objects on, e.g. app=payments,tier=backend
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Only show labels with this key; repeatable, globs such as 'app.kubernetes.io/*' allowed
+        #[arg(short = 'k', long = "key")]
+        key: Vec<Pattern>,
+
+        /// Query pods across all namespaces, or read every namespace
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+
+    /// List the annotations of pods, namespaces or nodes, one row per key
+    Annotations {
+        /// The kind of objects to read: pods, namespaces or nodes
+        #[arg(default_value = "pods")]
+        resource: MetadataResource,
+
+        /// Kubernetes namespace of the pods, or the namespace to read (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Filter pods by node name, or the node to read
+        #[arg(short = 'N', long = "node", conflicts_with = "all_namespaces")]
+        node: Option<String>,
+
+        /// Filter pods by pod name, either exact or a glob such as 'api-*'
+        #[arg(short, long)]
+        pod: Option<Pattern>,
+
+        /// Label selector to filter objects on, e.g. app=payments,tier=backend
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Only show annotations with this key; repeatable, globs such as 'app.kubernetes.io/*' allowed
+        #[arg(short = 'k', long = "key")]
+        key: Vec<Pattern>,
+
+        /// Query pods across all namespaces, or read every namespace
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+
+    /// Show the probes, readiness, restarts and last termination of every container
+    Health {
+        /// Kubernetes namespace to query (defaults to "default", ignored when --node is specified)
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Filter pods by node name
+        #[arg(short = 'N', long = "node", conflicts_with = "all_namespaces")]
+        node: Option<String>,
+
+        /// Filter pods by pod name, either exact or a glob such as 'api-*'
+        #[arg(short, long)]
+        pod: Option<Pattern>,
+
+        /// Filter pods by a regular expression on the pod name
+        #[arg(long = "pod-regex", value_parser = Regex::new)]
+        pod_regex: Option<Regex>,
+
+        /// Label selector to filter pods on, e.g. app=payments,tier=backend
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Field selector passed through to the API server, e.g. status.phase=Running
+        #[arg(long = "field-selector")]
+        field_selector: Option<String>,
+
+        /// Filter by image name, either exact or a glob such as 'linkerd/*'
+        #[arg(long = "image")]
+        image: Option<Pattern>,
+
+        /// Filter by image tag, either exact or a glob such as 'v1.*'
+        #[arg(long = "tag")]
+        tag: Option<Pattern>,
+
+        /// Only show images from this registry; repeatable, globs such as '*.dkr.ecr.*.amazonaws.com' allowed
+        #[arg(short = 'R', long = "registry")]
+        registry: Vec<Pattern>,
+
+        /// Hide images from this registry; repeatable, globs allowed
+        #[arg(long = "exclude-registry")]
+        exclude_registry: Vec<Pattern>,
+
+        /// Only show images hosted by this provider (ecr, artifact-registry, acr, ghcr, ...); repeatable
+        #[arg(long = "provider")]
+        provider: Vec<ProviderKind>,
+
+        /// Only show containers of this type (init, regular, ephemeral or all)
+        #[arg(long = "container-type", default_value = "all")]
+        container_type: ContainerTypeFilter,
+
+        /// Query pods across all namespaces
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Number of restarts from which a container is flagged as restarting too often (0 disables the check)
+        #[arg(long = "restart-threshold", default_value_t = DEFAULT_RESTART_THRESHOLD)]
+        restart_threshold: i32,
+
+        /// Only show containers with no probes, too many restarts or an OOM kill
+        #[arg(long = "unhealthy")]
+        unhealthy: bool,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
         #[arg(short = 'o', long = "output", default_value = "normal")]
         output: OutputFormat,
 
@@ -85,9 +462,12 @@ impl GetImages {
     /// * `Option<PathBuf>` - The path to the kubeconfig file if specified
     pub fn get_kubeconfig_path(&self) -> Option<PathBuf> {
         match self {
-            GetImages::Images { kubeconfig, .. } | GetImages::Registries { kubeconfig, .. } => {
-                kubeconfig.clone()
-            }
+            GetImages::Images { kubeconfig, .. }
+            | GetImages::Registries { kubeconfig, .. }
+            | GetImages::Drift { kubeconfig, .. }
+            | GetImages::Labels { kubeconfig, .. }
+            | GetImages::Annotations { kubeconfig, .. }
+            | GetImages::Health { kubeconfig, .. } => kubeconfig.clone(),
         }
     }
 
@@ -98,9 +478,12 @@ impl GetImages {
     /// * `&str` - The namespace to query
     pub fn get_namespace(&self) -> &str {
         match self {
-            GetImages::Images { namespace, .. } | GetImages::Registries { namespace, .. } => {
-                namespace
-            }
+            GetImages::Images { namespace, .. }
+            | GetImages::Registries { namespace, .. }
+            | GetImages::Drift { namespace, .. }
+            | GetImages::Labels { namespace, .. }
+            | GetImages::Annotations { namespace, .. }
+            | GetImages::Health { namespace, .. } => namespace,
         }
     }
 
@@ -112,7 +495,393 @@ impl GetImages {
     pub fn is_all_namespaces(&self) -> bool {
         match self {
             GetImages::Images { all_namespaces, .. }
-            | GetImages::Registries { all_namespaces, .. } => *all_namespaces,
+            | GetImages::Registries { all_namespaces, .. }
+            | GetImages::Drift { all_namespaces, .. }
+            | GetImages::Labels { all_namespaces, .. }
+            | GetImages::Annotations { all_namespaces, .. }
+            | GetImages::Health { all_namespaces, .. } => *all_namespaces,
+        }
+    }
+
+    /// Get the kubeconfig contexts this command fans out to, if it queries several clusters
+    ///
+    /// # Returns
+    ///
+    /// * `Option<&ContextSelection>` - The selected contexts when `--contexts` or `--all-contexts` is given
+    pub fn context_selection(&self) -> Option<&ContextSelection> {
+        match self {
+            GetImages::Images { clusters, .. } | GetImages::Registries { clusters, .. } => {
+                Some(clusters).filter(|clusters| clusters.is_fan_out())
+            }
+            GetImages::Drift { .. }
+            | GetImages::Labels { .. }
+            | GetImages::Annotations { .. }
+            | GetImages::Health { .. } => None,
+        }
+    }
+
+    /// Build the query selecting the pods and images this command reports on
+    ///
+    /// # Returns
+    ///
+    /// * `ImageQuery` - The namespace scope and every pod and image filter given on the command line
+    pub fn image_query(&self) -> ImageQuery {
+        match self {
+            GetImages::Images {
+                namespace,
+                node,
+                pod,
+                pod_regex,
+                selector,
+                field_selector,
+                image,
+                tag,
+                registry,
+                exclude_registry,
+                provider,
+                container_type,
+                all_namespaces,
+                ..
+            }
+            | GetImages::Health {
+                namespace,
+                node,
+                pod,
+                pod_regex,
+                selector,
+                field_selector,
+                image,
+                tag,
+                registry,
+                exclude_registry,
+                provider,
+                container_type,
+                all_namespaces,
+                ..
+            } => ImageQuery {
+                namespace: namespace.clone(),
+                all_namespaces: *all_namespaces,
+                node: node.clone(),
+                pod: pod.clone(),
+                pod_regex: pod_regex.clone(),
+                label_selector: selector.clone(),
+                field_selector: field_selector.clone(),
+                image: image.clone(),
+                tag: tag.clone(),
+                registries: registry.clone(),
+                exclude_registries: exclude_registry.clone(),
+                providers: provider.clone(),
+                container_type: *container_type,
+            },
+            GetImages::Registries {
+                namespace,
+                all_namespaces,
+                ..
+            } => ImageQuery {
+                namespace: namespace.clone(),
+                all_namespaces: *all_namespaces,
+                ..Default::default()
+            },
+            GetImages::Drift {
+                namespace,
+                pod,
+                selector,
+                container_type,
+                all_namespaces,
+                ..
+            } => ImageQuery {
+                namespace: namespace.clone(),
+                all_namespaces: *all_namespaces,
+                pod: pod.clone(),
+                label_selector: selector.clone(),
+                container_type: *container_type,
+                ..Default::default()
+            },
+            GetImages::Labels {
+                namespace,
+                node,
+                pod,
+                selector,
+                all_namespaces,
+                ..
+            }
+            | GetImages::Annotations {
+                namespace,
+                node,
+                pod,
+                selector,
+                all_namespaces,
+                ..
+            } => ImageQuery {
+                namespace: namespace.clone(),
+                all_namespaces: *all_namespaces,
+                node: node.clone(),
+                pod: pod.clone(),
+                label_selector: selector.clone(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Policies that can be checked against the Kubernetes cluster
+#[derive(Subcommand, Debug)]
+pub enum CheckPolicy {
+    /// Report every container pulling from a registry outside the allow-list
+    Registries {
+        /// Kubernetes namespace to check (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Check pods across all namespaces
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Label selector to limit the checked pods, e.g. app=payments
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Allowed registry or registry path such as 'ghcr.io/ourorg'; repeatable, globs allowed
+        #[arg(long = "allow", required_unless_present = "policy")]
+        allow: Vec<Pattern>,
+
+        /// Path to a YAML policy file with an 'allow' list, combined with any --allow entries
+        #[arg(long = "policy")]
+        policy: Option<PathBuf>,
+
+        /// Output format for violations: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+}
+
+impl CheckPolicy {
+    /// Get the kubeconfig path for this command
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathBuf>` - The path to the kubeconfig file if specified
+    pub fn get_kubeconfig_path(&self) -> Option<PathBuf> {
+        match self {
+            CheckPolicy::Registries { kubeconfig, .. } => kubeconfig.clone(),
+        }
+    }
+}
+
+/// Audit reports that can be produced for the Kubernetes cluster
+#[derive(Subcommand, Debug)]
+pub enum AuditReport {
+    /// List containers running `latest`, untagged or undigested images
+    Tags {
+        /// Kubernetes namespace to audit (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Audit pods across all namespaces
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Label selector to limit the audited pods, e.g. app=payments
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+
+    /// List every tag and digest running for images that run more than one version
+    Skew {
+        /// Kubernetes namespace to audit (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Audit pods across all namespaces
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Label selector to limit the audited pods, e.g. app=payments
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Only audit images whose name matches this exact name or glob, e.g. 'linkerd/*'
+        #[arg(long = "image")]
+        image: Option<Pattern>,
+
+        /// Also list images running a single tag and digest
+        #[arg(long = "all")]
+        all: bool,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+
+    /// List pods, workloads and namespaces missing required labels or annotations, exiting non-zero if any
+    Labels {
+        /// Kubernetes namespace to audit (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Audit objects across all namespaces
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Label selector to limit the audited objects, e.g. app=payments
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// Required label as KEY, or KEY=REGEX to also check its whole value; repeatable
+        #[arg(long = "require", value_parser = LabelRequirement::label, required_unless_present = "require_annotation")]
+        require: Vec<LabelRequirement>,
+
+        /// Required annotation as KEY, or KEY=REGEX to also check its whole value; repeatable
+        #[arg(long = "require-annotation", value_parser = LabelRequirement::annotation)]
+        require_annotation: Vec<LabelRequirement>,
+
+        /// Kinds of objects to audit: pods, workloads and/or namespaces
+        #[arg(
+            long = "kind",
+            value_delimiter = ',',
+            default_values = ["pods", "workloads", "namespaces"]
+        )]
+        kind: Vec<LabelTarget>,
+
+        /// Output format for violations: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+}
+
+impl AuditReport {
+    /// Get the kubeconfig path for this command
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathBuf>` - The path to the kubeconfig file if specified
+    pub fn get_kubeconfig_path(&self) -> Option<PathBuf> {
+        match self {
+            AuditReport::Tags { kubeconfig, .. }
+            | AuditReport::Skew { kubeconfig, .. }
+            | AuditReport::Labels { kubeconfig, .. } => kubeconfig.clone(),
+        }
+    }
+}
+
+/// Actions on snapshots of the cluster's image inventory
+#[derive(Subcommand, Debug)]
+pub enum SnapshotAction {
+    /// Save every pod image with the cluster name and a timestamp as JSON
+    Save {
+        /// Kubernetes namespace to snapshot (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Snapshot pods across all namespaces
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Label selector to limit the snapshotted pods, e.g. app=payments
+        #[arg(short = 'l', long = "selector")]
+        selector: Option<String>,
+
+        /// File to write the snapshot to (default: standard output)
+        #[arg(short = 'o', long = "output")]
+        output: Option<PathBuf>,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+
+    /// Report images added, removed or changed per workload between two snapshots
+    Diff {
+        /// Snapshot to compare from
+        before: PathBuf,
+
+        /// Snapshot to compare to
+        #[arg(required_unless_present = "against_cluster")]
+        after: Option<PathBuf>,
+
+        /// Compare to a live snapshot of this kubeconfig context instead of a second file
+        #[arg(long = "against-cluster", conflicts_with = "after")]
+        against_cluster: Option<String>,
+
+        /// Kubernetes namespace of the live snapshot (defaults to "default")
+        #[arg(
+            short,
+            long,
+            default_value = "default",
+            conflicts_with = "all_namespaces"
+        )]
+        namespace: String,
+
+        /// Take the live snapshot across all namespaces
+        #[arg(short = 'A', long = "all-namespaces", conflicts_with = "namespace")]
+        all_namespaces: bool,
+
+        /// Output format: normal, wide, json, yaml, csv, tsv, custom-columns=<spec> or jsonpath=<template>
+        #[arg(short = 'o', long = "output", default_value = "normal")]
+        output: OutputFormat,
+
+        /// Path to kubeconfig file (default: ~/.kube/config)
+        #[arg(long = "kubeconfig")]
+        kubeconfig: Option<PathBuf>,
+    },
+}
+
+impl SnapshotAction {
+    /// Get the kubeconfig path for this command
+    ///
+    /// # Returns
+    ///
+    /// * `Option<PathBuf>` - The path to the kubeconfig file if specified
+    pub fn get_kubeconfig_path(&self) -> Option<PathBuf> {
+        match self {
+            SnapshotAction::Save { kubeconfig, .. } | SnapshotAction::Diff { kubeconfig, .. } => {
+                kubeconfig.clone()
+            }
         }
     }
 }
@@ -1,5 +1,7 @@
+use crate::utils::{parse_custom_columns, ColumnSpec, JsonPathTemplate};
 use clap::ValueEnum;
 use std::fmt;
+use std::str::FromStr;
 
 /// Logging format options for Kelper
 #[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
@@ -20,12 +22,24 @@ impl fmt::Display for LogFormat {
 }
 
 /// Output format options for displaying Kubernetes resource data
-#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OutputFormat {
     /// Standard output format with essential columns
     Normal,
     /// Extended output format with additional columns
     Wide,
+    /// JSON array with every field of each row
+    Json,
+    /// YAML sequence with every field of each row
+    Yaml,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+    /// Table with user-defined columns (`custom-columns=HEADER:.field,...`)
+    CustomColumns(Vec<ColumnSpec>),
+    /// Kubectl-style jsonpath template evaluated against `{"items": [...]}`
+    JsonPath(JsonPathTemplate),
 }
 
 impl fmt::Display for OutputFormat {
@@ -33,18 +47,93 @@ impl fmt::Display for OutputFormat {
         match self {
             OutputFormat::Normal => write!(f, "normal"),
             OutputFormat::Wide => write!(f, "wide"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Yaml => write!(f, "yaml"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Tsv => write!(f, "tsv"),
+            OutputFormat::CustomColumns(columns) => {
+                let columns: Vec<String> = columns.iter().map(ToString::to_string).collect();
+                write!(f, "custom-columns={}", columns.join(","))
+            }
+            OutputFormat::JsonPath(template) => write!(f, "jsonpath={}", template),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(spec) = s.strip_prefix("custom-columns=") {
+            return parse_custom_columns(spec).map(OutputFormat::CustomColumns);
+        }
+
+        if let Some(template) = s.strip_prefix("jsonpath=") {
+            return template.parse().map(OutputFormat::JsonPath);
+        }
+
+        match s {
+            "normal" => Ok(OutputFormat::Normal),
+            "wide" => Ok(OutputFormat::Wide),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            other => Err(format!(
+                "invalid output format '{}' (expected one of normal, wide, json, yaml, csv, tsv, \
+                 custom-columns=<spec>, jsonpath=<template>)",
+                other
+            )),
         }
     }
 }
 
 impl OutputFormat {
+    /// Check if this format is rendered as a human readable table
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True for the normal, wide and custom-columns formats
+    pub fn is_table(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Normal | OutputFormat::Wide | OutputFormat::CustomColumns(_)
+        )
+    }
+
+    /// Check if rows in this format can be printed page by page as they are listed
+    ///
+    /// Jsonpath templates may range over or index into every row at once, so
+    /// they are only evaluated once every page has been read.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True for every format but jsonpath
+    pub fn is_streamable(&self) -> bool {
+        !matches!(self, OutputFormat::JsonPath(_))
+    }
+
+    /// Check if this format is intended for machine consumption
+    ///
+    /// Machine-readable formats always carry every field, including the wide-only ones.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True for the json, yaml, csv and tsv formats
+    pub fn is_machine_readable(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Json | OutputFormat::Yaml | OutputFormat::Csv | OutputFormat::Tsv
+        )
+    }
+
     /// Check if this format includes registry information
     ///
     /// # Returns
     ///
     /// * `bool` - True if the format includes registry information
     pub fn includes_registry(&self) -> bool {
-        matches!(self, OutputFormat::Wide)
+        matches!(self, OutputFormat::Wide) || self.is_machine_readable()
     }
 
     /// Check if this format includes digest information
@@ -53,7 +142,34 @@ impl OutputFormat {
     ///
     /// * `bool` - True if the format includes digest information
     pub fn includes_digest(&self) -> bool {
-        matches!(self, OutputFormat::Wide)
+        matches!(self, OutputFormat::Wide) || self.is_machine_readable()
+    }
+
+    /// Check if this format includes the container type (init, regular or ephemeral)
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the format includes container type information
+    pub fn includes_container_type(&self) -> bool {
+        matches!(self, OutputFormat::Wide) || self.is_machine_readable()
+    }
+
+    /// Check if this format includes the upstream registry of mirrored images
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the format includes upstream registry information
+    pub fn includes_upstream(&self) -> bool {
+        matches!(self, OutputFormat::Wide) || self.is_machine_readable()
+    }
+
+    /// Check if this format includes the provider, account and region of the registry
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the format includes registry provider information
+    pub fn includes_provider(&self) -> bool {
+        matches!(self, OutputFormat::Wide) || self.is_machine_readable()
     }
 
     /// Check if this format includes node information
@@ -62,6 +178,6 @@ impl OutputFormat {
     ///
     /// * `bool` - True if the format includes node information
     pub fn includes_node(&self) -> bool {
-        matches!(self, OutputFormat::Wide)
+        matches!(self, OutputFormat::Wide) || self.is_machine_readable()
     }
 }
@@ -3,5 +3,7 @@ mod commands;
 mod formats;
 
 pub use args::Args;
-pub use commands::{Commands, GetImages};
+pub use commands::{
+    AuditReport, CheckPolicy, Commands, ContextSelection, GetImages, SnapshotAction,
+};
 pub use formats::{LogFormat, OutputFormat};
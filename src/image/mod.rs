@@ -0,0 +1,8 @@
+mod provider;
+mod reference;
+mod resolver;
+
+pub use provider::{ProviderKind, RegistryProvider};
+pub use reference::{Digest, ImageReference, ImageReferenceError};
+pub(crate) use reference::{DEFAULT_REGISTRY, DOCKER_HUB_ALIASES};
+pub use resolver::{MirrorRule, RegistryConfig, RegistryResolver};
@@ -0,0 +1,190 @@
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+
+/// `<account>.dkr.ecr[-fips].<region>.amazonaws.com[.cn]`
+static ECR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{12})\.dkr\.ecr(?:-fips)?\.([a-z0-9-]+)\.amazonaws\.com(?:\.cn)?$").unwrap()
+});
+
+/// `<location>-docker.pkg.dev`
+static ARTIFACT_REGISTRY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([a-z0-9-]+)-docker\.pkg\.dev$").unwrap());
+
+/// `gcr.io` or `<multi-region>.gcr.io`
+static GCR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(?:([a-z]+)\.)?gcr\.io$").unwrap());
+
+/// `<registry>.azurecr.io`
+static ACR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^([a-z0-9]+)\.azurecr\.(?:io|cn|us)$").unwrap());
+
+/// The service hosting a container registry
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ValueEnum,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProviderKind {
+    /// Amazon Elastic Container Registry (private)
+    Ecr,
+    /// Amazon ECR Public Gallery
+    EcrPublic,
+    /// Google Container Registry
+    Gcr,
+    /// Google Artifact Registry
+    ArtifactRegistry,
+    /// Azure Container Registry
+    Acr,
+    /// GitHub Container Registry
+    Ghcr,
+    /// Red Hat Quay.io
+    Quay,
+    /// Docker Hub
+    DockerHub,
+    /// GitLab Container Registry
+    Gitlab,
+    /// Microsoft Container Registry
+    Mcr,
+    /// The Kubernetes project registry
+    Kubernetes,
+    /// A registry on localhost, a private IP address or a host without a domain
+    Private,
+    /// Any other registry
+    #[default]
+    SelfHosted,
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ProviderKind::Ecr => "ecr",
+            ProviderKind::EcrPublic => "ecr-public",
+            ProviderKind::Gcr => "gcr",
+            ProviderKind::ArtifactRegistry => "artifact-registry",
+            ProviderKind::Acr => "acr",
+            ProviderKind::Ghcr => "ghcr",
+            ProviderKind::Quay => "quay",
+            ProviderKind::DockerHub => "docker-hub",
+            ProviderKind::Gitlab => "gitlab",
+            ProviderKind::Mcr => "mcr",
+            ProviderKind::Kubernetes => "kubernetes",
+            ProviderKind::Private => "private",
+            ProviderKind::SelfHosted => "self-hosted",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The provider of a registry together with the account and region it belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct RegistryProvider {
+    /// The service hosting the registry
+    pub kind: ProviderKind,
+    /// AWS account, GCP project, Azure registry name or GitHub/Quay/Docker Hub namespace
+    pub account: Option<String>,
+    /// AWS region or GCP location
+    pub region: Option<String>,
+}
+
+impl RegistryProvider {
+    /// Classify the registry an image is pulled from
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - Registry host, optionally with a port
+    /// * `repository` - Repository path within the registry, used for the project or namespace
+    ///
+    /// # Returns
+    ///
+    /// * `RegistryProvider` - The provider, account and region of the registry
+    pub fn classify(registry: &str, repository: &str) -> Self {
+        let host = strip_port(registry).to_ascii_lowercase();
+        let namespace = repository
+            .split_once('/')
+            .map(|(namespace, _)| namespace.to_string());
+
+        let provider = |kind, account: Option<String>, region: Option<String>| Self {
+            kind,
+            account,
+            region,
+        };
+
+        if let Some(captures) = ECR.captures(&host) {
+            return provider(
+                ProviderKind::Ecr,
+                Some(captures[1].to_string()),
+                Some(captures[2].to_string()),
+            );
+        }
+        if let Some(captures) = ARTIFACT_REGISTRY.captures(&host) {
+            return provider(
+                ProviderKind::ArtifactRegistry,
+                namespace,
+                Some(captures[1].to_string()),
+            );
+        }
+        if host == "k8s.gcr.io" || host == "registry.k8s.io" {
+            return provider(ProviderKind::Kubernetes, None, None);
+        }
+        if let Some(captures) = GCR.captures(&host) {
+            let region = captures.get(1).map(|region| region.as_str().to_string());
+            return provider(ProviderKind::Gcr, namespace, region);
+        }
+        if let Some(captures) = ACR.captures(&host) {
+            return provider(ProviderKind::Acr, Some(captures[1].to_string()), None);
+        }
+
+        match host.as_str() {
+            "public.ecr.aws" => provider(ProviderKind::EcrPublic, namespace, None),
+            "ghcr.io" | "docker.pkg.github.com" => provider(ProviderKind::Ghcr, namespace, None),
+            "quay.io" => provider(ProviderKind::Quay, namespace, None),
+            "docker.io"
+            | "index.docker.io"
+            | "registry-1.docker.io"
+            | "registry.hub.docker.com" => {
+                let namespace = namespace.unwrap_or_else(|| "library".to_string());
+                provider(ProviderKind::DockerHub, Some(namespace), None)
+            }
+            "registry.gitlab.com" => provider(ProviderKind::Gitlab, namespace, None),
+            "mcr.microsoft.com" => provider(ProviderKind::Mcr, None, None),
+            _ if is_private_host(&host) => provider(ProviderKind::Private, None, None),
+            _ => provider(ProviderKind::SelfHosted, None, None),
+        }
+    }
+}
+
+/// Remove the port from a registry host, keeping IPv6 brackets intact
+fn strip_port(registry: &str) -> &str {
+    if let Some(end) = registry.find(']') {
+        return &registry[..=end];
+    }
+    registry.split(':').next().unwrap_or(registry)
+}
+
+/// Check if a host is localhost, a private or loopback IP address, or has no domain
+fn is_private_host(host: &str) -> bool {
+    if host == "localhost" || !host.contains('.') && !host.starts_with('[') {
+        return true;
+    }
+
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(IpAddr::V4(ip)) => {
+            ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified()
+        }
+        Ok(IpAddr::V6(ip)) => ip.is_loopback() || (ip.segments()[0] & 0xfe00) == 0xfc00,
+        Err(_) => false,
+    }
+}
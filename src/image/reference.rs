@@ -0,0 +1,389 @@
+use regex::Regex;
+use serde::Serialize;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::LazyLock;
+use thiserror::Error;
+
+/// Registry used for references that do not name one
+pub const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Tag used by the container runtime for references that do not name one
+pub const DEFAULT_TAG: &str = "latest";
+
+/// Host names that all refer to the Docker Hub registry
+pub const DOCKER_HUB_ALIASES: [&str; 4] = [
+    "docker.io",
+    "index.docker.io",
+    "registry-1.docker.io",
+    "registry.hub.docker.com",
+];
+
+/// Maximum length of `registry/repository` allowed by the reference grammar
+const NAME_TOTAL_LENGTH_MAX: usize = 255;
+
+/// A repository path component, e.g. `library` or `my_app-2`
+static PATH_COMPONENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*$").unwrap());
+
+/// A registry host with optional port, e.g. `ghcr.io`, `localhost:5000` or `[::1]:5000`
+static DOMAIN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:\[[0-9A-Fa-f:.]+\]|[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?)*)(?::[0-9]+)?$",
+    )
+    .unwrap()
+});
+
+/// A tag, e.g. `1.25` or `v1.10.0-rc.1`
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[\w][\w.-]{0,127}$").unwrap());
+
+/// A digest algorithm, e.g. `sha256` or `sha512`
+static ALGORITHM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[a-z0-9]+(?:[+._-][a-z0-9]+)*$").unwrap());
+
+/// Digest algorithms registered with the OCI image spec and the length of their hex encoding
+const REGISTERED_ALGORITHMS: [(&str, usize); 2] = [("sha256", 64), ("sha512", 128)];
+
+/// The encoded part of a digest using an unregistered algorithm
+static ENCODED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[a-zA-Z0-9=_-]+$").unwrap());
+
+/// Errors that can occur when parsing an image reference
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ImageReferenceError {
+    /// The reference is an empty string
+    #[error("image reference is empty")]
+    Empty,
+    /// The registry host is not a valid host name, IP address or port
+    #[error("invalid registry '{0}'")]
+    InvalidRegistry(String),
+    /// The repository path does not follow the reference grammar
+    #[error("invalid repository '{0}'")]
+    InvalidRepository(String),
+    /// `registry/repository` is longer than 255 characters
+    #[error("repository name '{0}' is longer than 255 characters")]
+    NameTooLong(String),
+    /// The tag does not follow the reference grammar
+    #[error("invalid tag '{0}'")]
+    InvalidTag(String),
+    /// The digest is not `algorithm:encoded`, or a `sha256`/`sha512` digest is not lowercase hex of the right length
+    #[error("invalid digest '{0}'")]
+    InvalidDigest(String),
+}
+
+/// A content digest such as `sha256:496429c2...`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct Digest {
+    /// Hash algorithm, e.g. `sha256`
+    pub algorithm: String,
+    /// Encoded hash value
+    pub hex: String,
+}
+
+impl FromStr for Digest {
+    type Err = ImageReferenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ImageReferenceError::InvalidDigest(s.to_string());
+        let (algorithm, hex) = s.split_once(':').ok_or_else(invalid)?;
+        if !ALGORITHM.is_match(algorithm) || !ENCODED.is_match(hex) {
+            return Err(invalid());
+        }
+
+        // Registered algorithms have a fixed length, e.g. 64 hex characters for sha256
+        let registered = REGISTERED_ALGORITHMS
+            .iter()
+            .find(|(name, _)| *name == algorithm);
+        if let Some((_, length)) = registered {
+            let is_hex = hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'));
+            if hex.len() != *length || !is_hex {
+                return Err(invalid());
+            }
+        }
+
+        Ok(Self {
+            algorithm: algorithm.to_string(),
+            hex: hex.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.algorithm.is_empty() {
+            // Only produced by `ImageReference::lossy` for digests without an algorithm
+            write!(f, "{}", self.hex)
+        } else {
+            write!(f, "{}:{}", self.algorithm, self.hex)
+        }
+    }
+}
+
+/// A container image reference: `[registry/]repository[:tag][@digest]`
+///
+/// References are parsed following the docker/distribution reference grammar,
+/// and `Display` writes them back as they were given, so a registry is only
+/// printed when the original reference named one. Registry hosts are
+/// case-insensitive and always lowercased.
+///
+/// Equality and hashing ignore whether the registry was written: `nginx` and
+/// `docker.io/nginx` compare equal even though they display differently.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageReference {
+    /// Registry host, `docker.io` when the reference does not name one
+    pub registry: String,
+    /// Repository path within the registry, e.g. `nginx` or `linkerd/proxy`
+    pub repository: String,
+    /// Tag, if the reference names one
+    pub tag: Option<String>,
+    /// Digest, if the reference is pinned to one
+    pub digest: Option<Digest>,
+    /// Whether the image is an official Docker Hub library image, e.g. `nginx`
+    pub is_official_library: bool,
+    /// Whether the registry was written in the reference
+    #[serde(skip)]
+    registry_explicit: bool,
+}
+
+impl ImageReference {
+    /// Split an image reference into its parts without validating them
+    ///
+    /// Unlike [`FromStr`], this never fails: anything that is not a valid
+    /// reference keeps its structure as far as it can be recognized, and an
+    /// empty string becomes an empty Docker Hub repository.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The container image reference
+    ///
+    /// # Returns
+    ///
+    /// * `ImageReference` - The best-effort reference
+    pub fn lossy(image: &str) -> Self {
+        let (name, digest) = match image.split_once('@') {
+            Some((name, digest)) => (name, Some(digest)),
+            None => (image, None),
+        };
+
+        // A colon after the last slash separates the tag; earlier ones belong to a port
+        let last_slash_index = name.rfind('/').unwrap_or(0);
+        let (name, tag) = match name.rfind(':') {
+            Some(tag_index) if tag_index > last_slash_index => {
+                (&name[..tag_index], Some(&name[tag_index + 1..]))
+            }
+            _ => (name, None),
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((domain, path)) if is_domain(domain) => (Some(domain), path),
+            _ => (None, name),
+        };
+
+        let registry_explicit = registry.is_some();
+        let registry = registry.map_or(DEFAULT_REGISTRY.to_string(), str::to_ascii_lowercase);
+        Self {
+            is_official_library: is_official_library(&registry, repository),
+            registry,
+            repository: repository.to_string(),
+            tag: tag.map(String::from),
+            digest: digest.map(|digest| match digest.split_once(':') {
+                Some((algorithm, hex)) => Digest {
+                    algorithm: algorithm.to_string(),
+                    hex: hex.to_string(),
+                },
+                None => Digest {
+                    algorithm: String::new(),
+                    hex: digest.to_string(),
+                },
+            }),
+            registry_explicit,
+        }
+    }
+
+    /// Replace the registry, e.g. with the canonical name of an alias
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - The new registry host
+    ///
+    /// # Returns
+    ///
+    /// * `ImageReference` - The reference in the new registry
+    pub fn with_registry(self, registry: &str) -> Self {
+        let registry = registry.to_ascii_lowercase();
+        Self {
+            is_official_library: is_official_library(&registry, &self.repository),
+            registry,
+            ..self
+        }
+    }
+
+    /// Normalize Docker Hub references so that every way of writing them compares equal
+    ///
+    /// Docker Hub aliases such as `index.docker.io` become `docker.io`, and official
+    /// library images use their familiar name, so `nginx`, `library/nginx`,
+    /// `docker.io/library/nginx` and `index.docker.io/library/nginx` all become
+    /// `docker.io/nginx`. References to other registries are returned unchanged.
+    ///
+    /// # Returns
+    ///
+    /// * `ImageReference` - The normalized reference
+    pub fn normalized(&self) -> Self {
+        if !DOCKER_HUB_ALIASES.contains(&self.registry.as_str()) {
+            return self.clone();
+        }
+
+        let repository = self
+            .repository
+            .strip_prefix("library/")
+            .filter(|name| !name.contains('/'))
+            .unwrap_or(&self.repository);
+
+        Self {
+            registry: DEFAULT_REGISTRY.to_string(),
+            repository: repository.to_string(),
+            tag: self.tag.clone(),
+            digest: self.digest.clone(),
+            is_official_library: is_official_library(DEFAULT_REGISTRY, repository),
+            registry_explicit: true,
+        }
+    }
+
+    /// Check if the reference names its registry rather than defaulting to Docker Hub
+    pub fn has_explicit_registry(&self) -> bool {
+        self.registry_explicit
+    }
+
+    /// Get the tag, or `latest` when the reference does not name one
+    pub fn tag_or_default(&self) -> &str {
+        self.tag.as_deref().unwrap_or(DEFAULT_TAG)
+    }
+
+    /// Get the name as written, i.e. the reference without its tag and digest
+    ///
+    /// # Returns
+    ///
+    /// * `String` - `registry/repository` if the registry was written, otherwise `repository`
+    pub fn name(&self) -> String {
+        if self.registry_explicit {
+            format!("{}/{}", self.registry, self.repository)
+        } else {
+            self.repository.clone()
+        }
+    }
+
+    /// Get the version shown for the image: the tag, followed by `@digest` when pinned
+    ///
+    /// # Returns
+    ///
+    /// * `String` - e.g. `1.25`, `latest` or `1.25@sha256:abc...`
+    pub fn version(&self) -> String {
+        match &self.digest {
+            Some(digest) => format!("{}@{}", self.tag_or_default(), digest),
+            None => self.tag_or_default().to_string(),
+        }
+    }
+}
+
+impl FromStr for ImageReference {
+    type Err = ImageReferenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ImageReferenceError::Empty);
+        }
+
+        if let Some((_, digest)) = s.split_once('@') {
+            digest.parse::<Digest>()?;
+        }
+
+        let reference = Self::lossy(s);
+
+        if reference.registry_explicit && !DOMAIN.is_match(&reference.registry) {
+            return Err(ImageReferenceError::InvalidRegistry(
+                reference.registry.clone(),
+            ));
+        }
+
+        if reference.repository.is_empty()
+            || !reference
+                .repository
+                .split('/')
+                .all(|component| PATH_COMPONENT.is_match(component))
+        {
+            return Err(ImageReferenceError::InvalidRepository(
+                reference.repository.clone(),
+            ));
+        }
+
+        let name = reference.name();
+        if name.len() > NAME_TOTAL_LENGTH_MAX {
+            return Err(ImageReferenceError::NameTooLong(name));
+        }
+
+        if let Some(tag) = reference.tag.as_deref().filter(|tag| !TAG.is_match(tag)) {
+            return Err(ImageReferenceError::InvalidTag(tag.to_string()));
+        }
+
+        Ok(reference)
+    }
+}
+
+impl PartialEq for ImageReference {
+    fn eq(&self, other: &Self) -> bool {
+        self.registry == other.registry
+            && self.repository == other.repository
+            && self.tag == other.tag
+            && self.digest == other.digest
+    }
+}
+
+impl Eq for ImageReference {}
+
+impl Hash for ImageReference {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.registry.hash(state);
+        self.repository.hash(state);
+        self.tag.hash(state);
+        self.digest.hash(state);
+    }
+}
+
+impl fmt::Display for ImageReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{}", tag)?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check if a repository is an official Docker Hub library image
+///
+/// # Arguments
+///
+/// * `registry` - The lowercased registry host
+/// * `repository` - The repository path within the registry
+///
+/// # Returns
+///
+/// * `bool` - True for Docker Hub repositories without a namespace or in `library/`
+fn is_official_library(registry: &str, repository: &str) -> bool {
+    DOCKER_HUB_ALIASES.contains(&registry)
+        && (!repository.contains('/') || repository.starts_with("library/"))
+}
+
+/// Check if the first path component of a reference is a registry host
+///
+/// Following the reference grammar, it is a registry when it contains a `.`,
+/// a `:` (port) or an uppercase letter, or is exactly `localhost`; otherwise it
+/// is a Docker Hub namespace such as `library` or `linkerd`.
+fn is_domain(component: &str) -> bool {
+    component.contains(['.', ':'])
+        || component == "localhost"
+        || component.chars().any(|c| c.is_ascii_uppercase())
+}
@@ -0,0 +1,161 @@
+use crate::image::reference::{DEFAULT_REGISTRY, DOCKER_HUB_ALIASES};
+use crate::image::ImageReference;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// A registry path that proxies another registry, e.g. `mirror.corp/dockerhub` for `docker.io`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MirrorRule {
+    /// Registry and optional path prefix images are pulled through
+    pub prefix: String,
+    /// Registry the mirror proxies
+    pub upstream: String,
+}
+
+/// Registry aliases and mirrors, loaded from a YAML file
+///
+/// ```yaml
+/// aliases:
+///   registry.corp: [registry.corp.internal, registry-eu.corp]
+/// mirrors:
+///   - prefix: mirror.corp/dockerhub
+///     upstream: docker.io
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct RegistryConfig {
+    /// Canonical registry name mapped to the other host names it is reached by
+    #[serde(default)]
+    pub aliases: BTreeMap<String, Vec<String>>,
+    /// Registry paths that proxy an upstream registry
+    #[serde(default)]
+    pub mirrors: Vec<MirrorRule>,
+}
+
+impl RegistryConfig {
+    /// Load a registry configuration from a YAML (or JSON) file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the configuration file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<RegistryConfig>` - The parsed configuration or an error
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read registry config {}", path.display()))?;
+        serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse registry config {}", path.display()))
+    }
+}
+
+/// Resolves container image references into the registry and repository used for reporting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryResolver {
+    /// Whether Docker Hub aliases and library prefixes are normalized (see [`ImageReference::normalized`])
+    pub normalize: bool,
+    /// Alias host name mapped to its canonical registry name
+    pub aliases: BTreeMap<String, String>,
+    /// Registry paths that proxy an upstream registry, checked in order
+    pub mirrors: Vec<MirrorRule>,
+}
+
+impl Default for RegistryResolver {
+    fn default() -> Self {
+        Self {
+            normalize: true,
+            aliases: BTreeMap::new(),
+            mirrors: Vec::new(),
+        }
+    }
+}
+
+impl RegistryResolver {
+    /// Create a resolver from a registry configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The aliases and mirrors to apply
+    /// * `normalize` - Whether Docker Hub references are normalized
+    ///
+    /// # Returns
+    ///
+    /// * `RegistryResolver` - The configured resolver
+    pub fn from_config(config: RegistryConfig, normalize: bool) -> Self {
+        let aliases = config
+            .aliases
+            .into_iter()
+            .flat_map(|(canonical, aliases)| {
+                aliases
+                    .into_iter()
+                    .map(move |alias| (alias.to_ascii_lowercase(), canonical.clone()))
+            })
+            .collect();
+
+        Self {
+            normalize,
+            aliases,
+            mirrors: config.mirrors,
+        }
+    }
+
+    /// Resolve an image reference as written in a pod spec
+    ///
+    /// Configured aliases are always applied; Docker Hub normalization only when enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The container image reference
+    ///
+    /// # Returns
+    ///
+    /// * `ImageReference` - The parsed reference with its registry resolved
+    pub fn resolve(&self, image: &str) -> ImageReference {
+        let mut reference = ImageReference::lossy(image);
+        if let Some(canonical) = self.aliases.get(&reference.registry) {
+            reference = reference.with_registry(canonical);
+        }
+
+        if self.normalize {
+            reference.normalized()
+        } else {
+            reference
+        }
+    }
+
+    /// Find the registry an image originally comes from, looking through mirrors
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - A reference returned by [`RegistryResolver::resolve`]
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The upstream registry of the first matching mirror, or the image's own registry
+    pub fn upstream(&self, reference: &ImageReference) -> String {
+        let full_name = format!("{}/{}", reference.registry, reference.repository);
+
+        self.mirrors
+            .iter()
+            .find(|mirror| {
+                let prefix = mirror.prefix.trim_end_matches('/');
+                full_name == prefix || full_name.starts_with(&format!("{}/", prefix))
+            })
+            .map(|mirror| self.resolve_registry(&mirror.upstream))
+            .unwrap_or_else(|| reference.registry.clone())
+    }
+
+    /// Resolve a registry host name through the aliases and, if enabled, Docker Hub normalization
+    fn resolve_registry(&self, registry: &str) -> String {
+        let registry = self.aliases.get(registry).map_or(registry, String::as_str);
+
+        if self.normalize && DOCKER_HUB_ALIASES.contains(&registry) {
+            DEFAULT_REGISTRY.to_string()
+        } else {
+            registry.to_string()
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::k8s::{ContainerType, PodImage, TagSource};
+use serde::Serialize;
+use std::fmt;
+
+/// A reason why a container image reference is mutable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagIssue {
+    /// The image explicitly uses the `latest` tag
+    Latest,
+    /// The image has no tag and implicitly resolves to `latest`
+    Untagged,
+    /// The image is not pinned to a digest
+    Undigested,
+}
+
+impl fmt::Display for TagIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagIssue::Latest => write!(f, "latest"),
+            TagIssue::Untagged => write!(f, "untagged"),
+            TagIssue::Undigested => write!(f, "undigested"),
+        }
+    }
+}
+
+/// A container whose image reference can change without its spec changing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagAuditEntry {
+    /// Kubernetes namespace of the pod
+    pub namespace: String,
+    /// Name of the pod containing the container
+    pub pod_name: String,
+    /// Name of the container
+    pub container_name: String,
+    /// Whether the container is an init, regular or ephemeral container
+    pub container_type: ContainerType,
+    /// Registry where the image is hosted
+    pub registry: String,
+    /// Name of the container image
+    pub image_name: String,
+    /// Version/tag of the container image
+    pub image_version: String,
+    /// Every reason the image reference is mutable
+    pub issues: Vec<TagIssue>,
+}
+
+/// Find the tag issues of a single image
+///
+/// Images pinned by digest are immutable and never have issues, whatever their tag.
+///
+/// # Arguments
+///
+/// * `image` - The image to check
+///
+/// # Returns
+///
+/// * `Vec<TagIssue>` - The issues found, empty if the image is pinned
+pub fn tag_issues(image: &PodImage) -> Vec<TagIssue> {
+    if image.pinned_by_digest {
+        return Vec::new();
+    }
+
+    let mut issues = Vec::new();
+    match image.tag_source {
+        TagSource::Implicit => issues.push(TagIssue::Untagged),
+        TagSource::Explicit if image.image_version == "latest" => issues.push(TagIssue::Latest),
+        TagSource::Explicit => {}
+    }
+    issues.push(TagIssue::Undigested);
+    issues
+}
+
+/// List every container running a `latest`, untagged or undigested image
+///
+/// # Arguments
+///
+/// * `images` - The images to audit
+///
+/// # Returns
+///
+/// * `Vec<TagAuditEntry>` - One entry per container with at least one issue
+pub fn audit_tags(images: &[PodImage]) -> Vec<TagAuditEntry> {
+    images
+        .iter()
+        .filter_map(|image| {
+            let issues = tag_issues(image);
+            if issues.is_empty() {
+                return None;
+            }
+
+            Some(TagAuditEntry {
+                namespace: image.namespace.clone(),
+                pod_name: image.pod_name.clone(),
+                container_name: image.container_name.clone(),
+                container_type: image.container_type,
+                registry: image.registry.clone(),
+                image_name: image.image_name.clone(),
+                image_version: image.image_version.clone(),
+                issues,
+            })
+        })
+        .collect()
+}
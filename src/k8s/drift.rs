@@ -0,0 +1,207 @@
+use crate::image::ImageReference;
+use crate::k8s::{
+    container_status, parse_image_id_digest, pod_workload, spec_containers, ContainerType,
+    ImageQuery, K8sClient,
+};
+use anyhow::Result;
+use k8s_openapi::api::core::v1::Pod;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use tracing::{debug, info, instrument};
+
+/// What a container's spec asked for compared with what is actually running
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImageDrift {
+    /// Kubernetes namespace of the pod
+    pub namespace: String,
+    /// Name of the pod containing the container
+    pub pod_name: String,
+    /// Workload running the pod as `Kind/name`, e.g. `Deployment/web` or `Pod/debug` for bare pods
+    pub owner: String,
+    /// Name of the container
+    pub container_name: String,
+    /// Whether the container is an init, regular or ephemeral container
+    pub container_type: ContainerType,
+    /// Image reference from the pod spec
+    pub spec_image: String,
+    /// Image reference reported in the container status (`status.image`)
+    pub running_image: String,
+    /// Digest resolved by the runtime from the container status `imageID`
+    pub digest: String,
+    /// Whether the running image differs from the image the spec asked for
+    pub image_mismatch: bool,
+    /// Whether replicas running the same tag resolved it to different digests
+    pub tag_repushed: bool,
+}
+
+impl ImageDrift {
+    /// Check if the container shows any kind of drift
+    pub fn has_drift(&self) -> bool {
+        self.image_mismatch || self.tag_repushed
+    }
+}
+
+impl K8sClient {
+    /// Compare spec and running images for the containers of the pods matching a query
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The criteria used to select pods and containers
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<ImageDrift>>` - One row per matching container or an error
+    #[instrument(skip(self), fields(
+        namespace = %query.namespace,
+        all_namespaces = %query.all_namespaces
+    ))]
+    pub async fn get_image_drift(&self, query: &ImageQuery) -> Result<Vec<ImageDrift>> {
+        debug!(?query, "Fetching image drift");
+
+        let mut rows = Vec::new();
+        self.for_each_pod_page(query, |pods| {
+            rows.extend(
+                pods.iter()
+                    .flat_map(pod_drift)
+                    .filter(|row| query.container_type.matches(row.container_type)),
+            );
+            Ok(())
+        })
+        .await?;
+        mark_repushed_tags(&mut rows);
+
+        info!(
+            containers = rows.len(),
+            drifted = rows.iter().filter(|row| row.has_drift()).count(),
+            "Successfully compared spec and running images"
+        );
+        Ok(rows)
+    }
+}
+
+/// Compare spec and running images for every container of a set of pods
+///
+/// Containers are grouped by workload, container name and spec image; a group
+/// whose replicas report more than one digest has had its tag re-pushed.
+/// Images pinned by digest in the spec are never reported as re-pushed.
+///
+/// # Arguments
+///
+/// * `pods` - The pods to inspect
+///
+/// # Returns
+///
+/// * `Vec<ImageDrift>` - One row per container, in pod order
+pub fn detect_drift<'a>(pods: impl IntoIterator<Item = &'a Pod>) -> Vec<ImageDrift> {
+    let mut rows: Vec<ImageDrift> = pods.into_iter().flat_map(pod_drift).collect();
+    mark_repushed_tags(&mut rows);
+    rows
+}
+
+/// Flag the containers whose replicas resolved the same tag to different digests
+fn mark_repushed_tags(rows: &mut [ImageDrift]) {
+    let group_key = |row: &ImageDrift| {
+        (
+            row.namespace.clone(),
+            row.owner.clone(),
+            row.container_name.clone(),
+            canonical_image(&row.spec_image),
+        )
+    };
+
+    let mut digests: HashMap<_, BTreeSet<String>> = HashMap::new();
+    for row in rows
+        .iter()
+        .filter(|row| !row.spec_image.contains('@') && !row.digest.is_empty())
+    {
+        digests
+            .entry(group_key(row))
+            .or_default()
+            .insert(row.digest.clone());
+    }
+
+    for row in rows.iter_mut() {
+        row.tag_repushed = !row.spec_image.contains('@')
+            && digests
+                .get(&group_key(row))
+                .is_some_and(|digests| digests.len() > 1);
+    }
+}
+
+/// Compare spec and running images for every container of a single pod
+///
+/// # Arguments
+///
+/// * `pod` - The pod to inspect
+///
+/// # Returns
+///
+/// * `Vec<ImageDrift>` - One row per container, with `tag_repushed` unset
+pub fn pod_drift(pod: &Pod) -> Vec<ImageDrift> {
+    let Some(spec) = pod.spec.as_ref() else {
+        return Vec::new();
+    };
+
+    let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+    let pod_name = pod.metadata.name.clone().unwrap_or_default();
+    let owner = pod_workload(pod);
+
+    spec_containers(spec)
+        .into_iter()
+        .filter_map(|(container_type, container_name, image)| {
+            let spec_image = image?;
+            let status = container_status(pod, container_name, container_type);
+            let running_image = status.map(|cs| cs.image.clone()).unwrap_or_default();
+            let image_id = status.map(|cs| cs.image_id.as_str()).unwrap_or_default();
+            let digest = parse_image_id_digest(image_id).unwrap_or_default();
+
+            Some(ImageDrift {
+                namespace: namespace.clone(),
+                pod_name: pod_name.clone(),
+                owner: owner.clone(),
+                container_name: container_name.to_string(),
+                container_type,
+                spec_image: spec_image.to_string(),
+                image_mismatch: is_image_mismatch(spec_image, &running_image, image_id),
+                running_image,
+                digest,
+                tag_repushed: false,
+            })
+        })
+        .collect()
+}
+
+/// Check if a running container disagrees with the image its spec asked for
+///
+/// Digest-pinned specs are compared with the repo digest of an `imageID` such as
+/// `nginx@sha256:...`; an `imageID` holding only the image config ID (`sha256:...`)
+/// cannot be compared with it. Otherwise both references are normalized (`nginx`
+/// and `index.docker.io/library/nginx:latest` are the same) and compared by
+/// repository and tag. A container that has not started yet, or whose runtime
+/// only reports an image ID, is not considered drifted.
+fn is_image_mismatch(spec_image: &str, running_image: &str, image_id: &str) -> bool {
+    if let Some(spec_digest) = ImageReference::lossy(spec_image).digest {
+        let repo_digest = image_id
+            .contains('@')
+            .then(|| parse_image_id_digest(image_id))
+            .flatten();
+        return repo_digest.is_some_and(|digest| spec_digest.hex != digest);
+    }
+
+    if running_image.is_empty() || running_image.starts_with("sha256:") {
+        return false;
+    }
+
+    canonical_image(spec_image) != canonical_image(running_image)
+}
+
+/// Normalize an image reference to `registry/repository:tag`, dropping any digest
+fn canonical_image(image: &str) -> String {
+    let reference = ImageReference::lossy(image).normalized();
+    format!(
+        "{}/{}:{}",
+        reference.registry,
+        reference.repository,
+        reference.tag_or_default()
+    )
+}
@@ -0,0 +1,156 @@
+use crate::image::RegistryResolver;
+use crate::k8s::{ClientOptions, K8sClient, K8sError};
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use kube::config::Kubeconfig;
+use serde::Serialize;
+use std::future::Future;
+use std::path::Path;
+use tracing::{debug, info, instrument, warn};
+
+/// Number of clusters queried at the same time unless configured otherwise
+pub const DEFAULT_CLUSTER_CONCURRENCY: usize = 8;
+
+/// A row read from one cluster of a fleet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClusterRecord<T> {
+    /// Kubeconfig context of the cluster the row was read from
+    pub cluster: String,
+    /// The row itself, serialized alongside the cluster
+    #[serde(flatten)]
+    pub record: T,
+}
+
+/// A cluster of a fleet that could not be queried
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClusterFailure {
+    /// Kubeconfig context of the cluster
+    pub cluster: String,
+    /// Why connecting to or querying the cluster failed
+    pub error: String,
+}
+
+/// The rows of every cluster that answered a query, and the clusters that did not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FleetResult<T> {
+    /// Rows of the clusters that answered, in the order the contexts were given
+    pub records: Vec<ClusterRecord<T>>,
+    /// Clusters that could not be queried, in the order the contexts were given
+    pub failures: Vec<ClusterFailure>,
+}
+
+/// List the names of every context in a kubeconfig
+///
+/// # Arguments
+///
+/// * `kubeconfig` - Explicit kubeconfig path, or `None` for `KUBECONFIG` and `~/.kube/config`
+///
+/// # Returns
+///
+/// * `Result<Vec<String>>` - The context names in kubeconfig order, or an error if none can be read
+pub fn kubeconfig_contexts(kubeconfig: Option<&Path>) -> Result<Vec<String>> {
+    let config = match kubeconfig {
+        Some(path) => Kubeconfig::read_from(path),
+        None => Kubeconfig::read(),
+    }
+    .map_err(|e| K8sError::ConfigError(format!("Failed to read kubeconfig: {}", e)))?;
+
+    let contexts: Vec<String> = config
+        .contexts
+        .into_iter()
+        .map(|context| context.name)
+        .collect();
+    if contexts.is_empty() {
+        return Err(
+            K8sError::ConfigError("The kubeconfig does not define any context".into()).into(),
+        );
+    }
+    Ok(contexts)
+}
+
+/// Run the same query against several kubeconfig contexts concurrently
+///
+/// At most `concurrency` clusters are connected to at the same time. A
+/// cluster that cannot be reached or queried does not fail the others: it is
+/// reported in [`FleetResult::failures`] instead.
+///
+/// # Arguments
+///
+/// * `options` - Kubeconfig path and impersonation shared by every cluster
+/// * `contexts` - The kubeconfig contexts to query
+/// * `resolver` - Registry resolver used by the client of every cluster
+/// * `concurrency` - Maximum number of clusters queried at the same time
+/// * `query` - Reads the rows of a single cluster
+///
+/// # Returns
+///
+/// * `FleetResult<T>` - The rows of every cluster that answered and the clusters that failed
+#[instrument(skip(options, resolver, query), fields(clusters = contexts.len()))]
+pub async fn query_fleet<T, F, Fut>(
+    options: &ClientOptions,
+    contexts: &[String],
+    resolver: &RegistryResolver,
+    concurrency: usize,
+    query: F,
+) -> FleetResult<T>
+where
+    F: Fn(K8sClient) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    debug!(concurrency, "Querying clusters");
+
+    let results: Vec<(String, Result<Vec<T>>)> = stream::iter(contexts)
+        .map(|context| {
+            // The cluster and user of the current context do not apply to another context
+            let options = ClientOptions {
+                context: Some(context.clone()),
+                cluster: None,
+                user: None,
+                ..options.clone()
+            };
+            let query = &query;
+            async move {
+                let result = match K8sClient::from_options(&options).await {
+                    Ok(client) => query(client.with_resolver(resolver.clone())).await,
+                    Err(e) => Err(e),
+                };
+                (context.clone(), result)
+            }
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+
+    let mut fleet = FleetResult {
+        records: Vec::new(),
+        failures: Vec::new(),
+    };
+    for (cluster, result) in results {
+        match result {
+            Ok(records) => {
+                debug!(cluster = %cluster, count = records.len(), "Queried cluster");
+                fleet
+                    .records
+                    .extend(records.into_iter().map(|record| ClusterRecord {
+                        cluster: cluster.clone(),
+                        record,
+                    }));
+            }
+            Err(e) => {
+                warn!(cluster = %cluster, error = %e, "Failed to query cluster");
+                fleet.failures.push(ClusterFailure {
+                    cluster,
+                    error: format!("{:#}", e),
+                });
+            }
+        }
+    }
+
+    info!(
+        clusters = contexts.len(),
+        failed = fleet.failures.len(),
+        records = fleet.records.len(),
+        "Finished querying clusters"
+    );
+    fleet
+}
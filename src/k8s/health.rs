@@ -0,0 +1,319 @@
+use crate::k8s::{container_status, process_pod_with, ContainerType, ImageQuery, K8sClient};
+use anyhow::Result;
+use k8s_openapi::api::core::v1::{Container, ContainerState, Pod, Probe};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use serde::Serialize;
+use std::fmt;
+use tracing::{debug, info, instrument};
+
+/// Number of restarts from which a container is reported as restarting too often
+pub const DEFAULT_RESTART_THRESHOLD: i32 = 5;
+
+/// Termination reason reported by the kubelet when a container exceeded its memory limit
+const OOM_KILLED: &str = "OOMKilled";
+
+/// A reason why a container deserves attention
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HealthIssue {
+    /// The container has no liveness, readiness or startup probe
+    NoProbes,
+    /// The container restarted at least as often as the restart threshold
+    Restarts,
+    /// The container was last terminated for exceeding its memory limit
+    OomKilled,
+}
+
+impl fmt::Display for HealthIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthIssue::NoProbes => write!(f, "no-probes"),
+            HealthIssue::Restarts => write!(f, "restarts"),
+            HealthIssue::OomKilled => write!(f, "oom-killed"),
+        }
+    }
+}
+
+/// The probes and runtime status of a single container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ContainerHealth {
+    /// Name of the pod containing the container
+    pub pod_name: String,
+    /// Kubernetes namespace of the pod
+    pub namespace: String,
+    /// Node the pod is scheduled on
+    pub node_name: String,
+    /// Name of the container
+    pub container_name: String,
+    /// Whether the container is an init, regular or ephemeral container
+    pub container_type: ContainerType,
+    /// Liveness probe, e.g. `http-get http://:8080/healthz`, empty when none is set
+    pub liveness: String,
+    /// Readiness probe, empty when none is set
+    pub readiness: String,
+    /// Startup probe, empty when none is set
+    pub startup: String,
+    /// Whether the container passes its readiness probe
+    pub ready: bool,
+    /// Number of times the container has been restarted
+    pub restart_count: i32,
+    /// Current state: `Running`, the reason the container is waiting or terminated, or empty if not created
+    pub state: String,
+    /// Reason of the container's last termination, e.g. `Error` or `OOMKilled`
+    pub last_termination_reason: String,
+    /// Exit code of the container's last termination
+    pub last_exit_code: Option<i32>,
+    /// Whether the container was killed for exceeding its memory limit
+    pub oom_killed: bool,
+    /// Every reason the container deserves attention
+    pub issues: Vec<HealthIssue>,
+}
+
+impl ContainerHealth {
+    /// Check if the container has any health issue
+    pub fn is_unhealthy(&self) -> bool {
+        !self.issues.is_empty()
+    }
+}
+
+impl K8sClient {
+    /// Read the probes and status of the containers of the pods matching a query
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The criteria used to select pods and containers
+    /// * `restart_threshold` - Number of restarts from which a container is reported
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<ContainerHealth>>` - One row per matching container or an error
+    #[instrument(skip(self), fields(
+        namespace = %query.namespace,
+        all_namespaces = %query.all_namespaces
+    ))]
+    pub async fn get_container_health(
+        &self,
+        query: &ImageQuery,
+        restart_threshold: i32,
+    ) -> Result<Vec<ContainerHealth>> {
+        debug!(?query, "Fetching container health");
+
+        let mut rows = Vec::new();
+        self.for_each_pod_page(query, |pods| {
+            for pod in &pods {
+                // Image filters apply to the image each container runs
+                let images = process_pod_with(pod, &self.resolver);
+                rows.extend(
+                    pod_health(pod, restart_threshold)
+                        .into_iter()
+                        .filter(|row| {
+                            images.iter().any(|image| {
+                                image.container_name == row.container_name
+                                    && image.container_type == row.container_type
+                                    && query.matches_image(image)
+                            })
+                        }),
+                );
+            }
+            Ok(())
+        })
+        .await?;
+
+        info!(
+            containers = rows.len(),
+            unhealthy = rows.iter().filter(|row| row.is_unhealthy()).count(),
+            "Successfully read container health"
+        );
+        Ok(rows)
+    }
+}
+
+/// Read the probes and status of every container of a pod
+///
+/// Only regular containers and sidecar init containers can have probes, so
+/// other init containers and ephemeral containers are never reported for
+/// missing them.
+///
+/// # Arguments
+///
+/// * `pod` - The pod to inspect
+/// * `restart_threshold` - Number of restarts from which a container is reported
+///
+/// # Returns
+///
+/// * `Vec<ContainerHealth>` - One row per container: init, then regular, then ephemeral
+pub fn pod_health(pod: &Pod, restart_threshold: i32) -> Vec<ContainerHealth> {
+    let Some(spec) = &pod.spec else {
+        return Vec::new();
+    };
+
+    let init = spec.init_containers.iter().flatten().map(|container| {
+        let sidecar = container.restart_policy.as_deref() == Some("Always");
+        (ContainerType::Init, container, sidecar)
+    });
+    let regular = spec
+        .containers
+        .iter()
+        .map(|container| (ContainerType::Regular, container, true));
+
+    let mut rows: Vec<ContainerHealth> = init
+        .chain(regular)
+        .map(|(container_type, container, expects_probes)| {
+            container_health(
+                pod,
+                container_type,
+                container,
+                expects_probes,
+                restart_threshold,
+            )
+        })
+        .collect();
+
+    // Ephemeral containers cannot declare probes
+    rows.extend(spec.ephemeral_containers.iter().flatten().map(|container| {
+        let container = Container {
+            name: container.name.clone(),
+            ..Default::default()
+        };
+        container_health(
+            pod,
+            ContainerType::Ephemeral,
+            &container,
+            false,
+            restart_threshold,
+        )
+    }));
+
+    rows
+}
+
+/// Read the probes and status of a single container
+fn container_health(
+    pod: &Pod,
+    container_type: ContainerType,
+    container: &Container,
+    expects_probes: bool,
+    restart_threshold: i32,
+) -> ContainerHealth {
+    let status = container_status(pod, &container.name, container_type);
+    let last_terminated = status
+        .and_then(|status| status.last_state.as_ref())
+        .and_then(|state| state.terminated.as_ref());
+    let terminated = status
+        .and_then(|status| status.state.as_ref())
+        .and_then(|state| state.terminated.as_ref());
+
+    let liveness = container.liveness_probe.as_ref().map(describe_probe);
+    let readiness = container.readiness_probe.as_ref().map(describe_probe);
+    let startup = container.startup_probe.as_ref().map(describe_probe);
+    let restart_count = status
+        .map(|status| status.restart_count)
+        .unwrap_or_default();
+    let oom_killed = [last_terminated, terminated]
+        .into_iter()
+        .flatten()
+        .any(|terminated| terminated.reason.as_deref() == Some(OOM_KILLED));
+
+    let mut issues = Vec::new();
+    if expects_probes && liveness.is_none() && readiness.is_none() && startup.is_none() {
+        issues.push(HealthIssue::NoProbes);
+    }
+    if restart_threshold > 0 && restart_count >= restart_threshold {
+        issues.push(HealthIssue::Restarts);
+    }
+    if oom_killed {
+        issues.push(HealthIssue::OomKilled);
+    }
+
+    ContainerHealth {
+        pod_name: pod.metadata.name.clone().unwrap_or_default(),
+        namespace: pod.metadata.namespace.clone().unwrap_or_default(),
+        node_name: pod
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.node_name.clone())
+            .unwrap_or_default(),
+        container_name: container.name.clone(),
+        container_type,
+        liveness: liveness.unwrap_or_default(),
+        readiness: readiness.unwrap_or_default(),
+        startup: startup.unwrap_or_default(),
+        ready: status.is_some_and(|status| status.ready),
+        restart_count,
+        state: status
+            .and_then(|status| status.state.as_ref())
+            .map(describe_state)
+            .unwrap_or_default(),
+        last_termination_reason: last_terminated
+            .and_then(|terminated| terminated.reason.clone())
+            .unwrap_or_default(),
+        last_exit_code: last_terminated.map(|terminated| terminated.exit_code),
+        oom_killed,
+        issues,
+    }
+}
+
+/// Describe a probe the way `kubectl describe` does, without its timings
+///
+/// # Arguments
+///
+/// * `probe` - The probe to describe
+///
+/// # Returns
+///
+/// * `String` - e.g. `http-get http://:8080/healthz`, `tcp-socket :5432`, `grpc :9090` or `exec [cat /tmp/ready]`
+pub fn describe_probe(probe: &Probe) -> String {
+    let port = |port: &IntOrString| match port {
+        IntOrString::Int(port) => port.to_string(),
+        IntOrString::String(name) => name.clone(),
+    };
+
+    if let Some(http) = &probe.http_get {
+        let scheme = http.scheme.as_deref().unwrap_or("HTTP").to_lowercase();
+        let host = http.host.as_deref().unwrap_or_default();
+        let path = http.path.as_deref().unwrap_or("/");
+        format!(
+            "http-get {}://{}:{}{}",
+            scheme,
+            host,
+            port(&http.port),
+            path
+        )
+    } else if let Some(tcp) = &probe.tcp_socket {
+        let host = tcp.host.as_deref().unwrap_or_default();
+        format!("tcp-socket {}:{}", host, port(&tcp.port))
+    } else if let Some(grpc) = &probe.grpc {
+        format!("grpc :{}", grpc.port)
+    } else if let Some(exec) = &probe.exec {
+        format!(
+            "exec [{}]",
+            exec.command
+                .iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ")
+        )
+    } else {
+        "unknown".to_string()
+    }
+}
+
+/// Describe a container state like the STATUS column of `kubectl get pods`
+fn describe_state(state: &ContainerState) -> String {
+    if let Some(waiting) = &state.waiting {
+        waiting
+            .reason
+            .clone()
+            .unwrap_or_else(|| "Waiting".to_string())
+    } else if let Some(terminated) = &state.terminated {
+        terminated
+            .reason
+            .clone()
+            .unwrap_or_else(|| "Terminated".to_string())
+    } else if state.running.is_some() {
+        "Running".to_string()
+    } else {
+        String::new()
+    }
+}
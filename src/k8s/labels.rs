@@ -0,0 +1,368 @@
+use crate::k8s::registries::is_owned_by;
+use crate::k8s::{K8sClient, K8sError, MetadataField};
+use anyhow::Result;
+use clap::ValueEnum;
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{Namespace, Pod};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::{ListParams, PartialObjectMeta};
+use kube::{Api, Resource};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use tracing::{debug, info, instrument};
+
+/// Kinds of objects whose labels and annotations can be audited
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+pub enum LabelTarget {
+    /// Running pods
+    Pods,
+    /// Deployments, StatefulSets, DaemonSets, CronJobs, and ReplicaSets and Jobs without a controller
+    Workloads,
+    /// The audited namespace, or every namespace with --all-namespaces
+    Namespaces,
+}
+
+impl fmt::Display for LabelTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelTarget::Pods => write!(f, "pods"),
+            LabelTarget::Workloads => write!(f, "workloads"),
+            LabelTarget::Namespaces => write!(f, "namespaces"),
+        }
+    }
+}
+
+/// A label or annotation every audited object must carry
+#[derive(Debug, Clone)]
+pub struct LabelRequirement {
+    /// Whether the key is a label or an annotation
+    pub field: MetadataField,
+    /// The required key
+    pub key: String,
+    /// Regular expression the whole value must match, any value is accepted when `None`
+    pub value: Option<Regex>,
+}
+
+impl LabelRequirement {
+    /// Parse a required label written as `KEY` or `KEY=REGEX`
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The requirement, e.g. `team` or `team=[a-z-]+`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<LabelRequirement, String>` - The requirement, or why it is invalid
+    pub fn label(spec: &str) -> Result<Self, String> {
+        Self::parse(MetadataField::Labels, spec)
+    }
+
+    /// Parse a required annotation written as `KEY` or `KEY=REGEX`
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The requirement, e.g. `owner` or `owner=.+@example\.com`
+    ///
+    /// # Returns
+    ///
+    /// * `Result<LabelRequirement, String>` - The requirement, or why it is invalid
+    pub fn annotation(spec: &str) -> Result<Self, String> {
+        Self::parse(MetadataField::Annotations, spec)
+    }
+
+    /// Parse a requirement on labels or annotations
+    ///
+    /// The regular expression must match the whole value, so `env=prod` rejects `preprod`.
+    fn parse(field: MetadataField, spec: &str) -> Result<Self, String> {
+        // Keys cannot contain '=', so the first one separates the key from the regex
+        let (key, value) = match spec.split_once('=') {
+            Some((key, pattern)) => {
+                let regex = Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| e.to_string())?;
+                (key, Some(regex))
+            }
+            None => (spec, None),
+        };
+        if key.is_empty() {
+            return Err("the key must not be empty".to_string());
+        }
+
+        Ok(Self {
+            field,
+            key: key.to_string(),
+            value,
+        })
+    }
+
+    /// Get the regular expression as written, without the anchors added around it
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The regular expression, empty when any value is accepted
+    pub fn expected(&self) -> &str {
+        let Some(regex) = &self.value else {
+            return "";
+        };
+        regex
+            .as_str()
+            .strip_prefix("^(?:")
+            .and_then(|pattern| pattern.strip_suffix(")$"))
+            .unwrap_or(regex.as_str())
+    }
+}
+
+/// Why an object does not comply with a requirement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelIssue {
+    /// The required key is not set
+    Missing,
+    /// The key is set but its value does not match the required regular expression
+    Mismatch,
+}
+
+impl fmt::Display for LabelIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelIssue::Missing => write!(f, "missing"),
+            LabelIssue::Mismatch => write!(f, "mismatch"),
+        }
+    }
+}
+
+/// A required label or annotation an object is missing or sets to a wrong value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LabelViolation {
+    /// Kind of the object, e.g. `Pod` or `Deployment`
+    pub kind: String,
+    /// Namespace of the object, empty for namespaces
+    pub namespace: String,
+    /// Name of the object
+    pub name: String,
+    /// Whether the key is a label or an annotation
+    pub field: MetadataField,
+    /// The required key
+    pub key: String,
+    /// The value the object carries, empty when the key is missing
+    pub value: String,
+    /// Whether the key is missing or its value does not match
+    pub issue: LabelIssue,
+    /// Regular expression the value must match, empty when any value is accepted
+    pub expected: String,
+}
+
+/// The outcome of auditing the labels and annotations of a set of objects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelAudit {
+    /// Number of objects audited
+    pub objects: usize,
+    /// Every requirement an audited object does not meet
+    pub violations: Vec<LabelViolation>,
+}
+
+impl K8sClient {
+    /// Audit the labels and annotations of pods, workloads and namespaces against requirements
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to audit
+    /// * `all_namespaces` - Whether to audit all namespaces
+    /// * `label_selector` - Only audit objects matching this label selector
+    /// * `targets` - The kinds of objects to audit
+    /// * `requirements` - The labels and annotations every audited object must carry
+    ///
+    /// # Returns
+    ///
+    /// * `Result<LabelAudit>` - The number of audited objects and their violations, or an error
+    #[instrument(skip(self, requirements), fields(
+        namespace = %namespace,
+        all_namespaces = %all_namespaces
+    ))]
+    pub async fn audit_labels(
+        &self,
+        namespace: &str,
+        all_namespaces: bool,
+        label_selector: Option<&str>,
+        targets: &[LabelTarget],
+        requirements: &[LabelRequirement],
+    ) -> Result<LabelAudit> {
+        debug!(?targets, ?requirements, "Auditing labels");
+
+        if !all_namespaces && !self.namespace_exists(namespace).await? {
+            let resource = format!("Namespace {} not found", namespace);
+            return Err(K8sError::ResourceNotFound(resource).into());
+        }
+
+        let mut list_params = ListParams::default();
+        if let Some(labels) = label_selector {
+            list_params = list_params.labels(labels);
+        }
+
+        let mut auditor = Auditor {
+            namespace,
+            all_namespaces,
+            list_params,
+            requirements,
+            audit: LabelAudit {
+                objects: 0,
+                violations: Vec::new(),
+            },
+        };
+        if targets.contains(&LabelTarget::Workloads) {
+            self.audit_kind::<Deployment>(&mut auditor, None).await?;
+            self.audit_kind::<StatefulSet>(&mut auditor, None).await?;
+            self.audit_kind::<DaemonSet>(&mut auditor, None).await?;
+            // ReplicaSets and Jobs created by a controller inherit its labels
+            self.audit_kind::<ReplicaSet>(&mut auditor, Some("Deployment"))
+                .await?;
+            self.audit_kind::<Job>(&mut auditor, Some("CronJob"))
+                .await?;
+            self.audit_kind::<CronJob>(&mut auditor, None).await?;
+        }
+
+        if targets.contains(&LabelTarget::Pods) {
+            self.audit_kind::<Pod>(&mut auditor, None).await?;
+        }
+
+        if targets.contains(&LabelTarget::Namespaces) {
+            let mut list_params = auditor.list_params.clone();
+            if !all_namespaces {
+                list_params = list_params.fields(&format!("metadata.name={}", namespace));
+            }
+            let api: Api<Namespace> = Api::all(self.client.clone());
+            self.list_metadata_pages(&api, &list_params, |page| {
+                auditor.audit_page(page, None);
+                Ok(())
+            })
+            .await?;
+        }
+
+        let audit = auditor.audit;
+        info!(
+            objects = audit.objects,
+            violations = audit.violations.len(),
+            "Successfully audited labels"
+        );
+        Ok(audit)
+    }
+
+    /// Audit every object of a namespaced kind, one page at a time
+    ///
+    /// Only the metadata of the objects is listed.
+    ///
+    /// # Arguments
+    ///
+    /// * `auditor` - Where to list objects from and the audit to add them to
+    /// * `skip_owned_by` - Skip objects owned by a controller of this kind
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success or the first listing error
+    async fn audit_kind<K>(
+        &self,
+        auditor: &mut Auditor<'_>,
+        skip_owned_by: Option<&str>,
+    ) -> Result<()>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+            + Clone
+            + DeserializeOwned
+            + fmt::Debug,
+    {
+        let list_params = auditor.list_params.clone();
+        self.for_each_namespaced_metadata_page::<K>(
+            auditor.namespace,
+            auditor.all_namespaces,
+            &list_params,
+            |page| {
+                auditor.audit_page(page, skip_owned_by);
+                Ok(())
+            },
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// An audit in progress, so listed objects can be checked without being kept
+struct Auditor<'a> {
+    /// The namespace to audit
+    namespace: &'a str,
+    /// Whether to audit all namespaces
+    all_namespaces: bool,
+    /// Selectors applied to every listing
+    list_params: ListParams,
+    /// The labels and annotations every audited object must carry
+    requirements: &'a [LabelRequirement],
+    /// The objects audited so far and their violations
+    audit: LabelAudit,
+}
+
+impl Auditor<'_> {
+    /// Check a page of listed objects, skipping those owned by a controller of a kind
+    fn audit_page<K: Resource<DynamicType = ()>>(
+        &mut self,
+        objects: Vec<PartialObjectMeta<K>>,
+        skip_owned_by: Option<&str>,
+    ) {
+        let kind = K::kind(&());
+        for object in objects {
+            let metadata = &object.metadata;
+            if skip_owned_by.is_some_and(|owner| is_owned_by(metadata, owner)) {
+                continue;
+            }
+            self.audit.objects += 1;
+            self.audit
+                .violations
+                .extend(label_violations(&kind, metadata, self.requirements));
+        }
+    }
+}
+
+/// Check the labels and annotations of a single object against requirements
+///
+/// # Arguments
+///
+/// * `kind` - Kind of the object, e.g. `Deployment`
+/// * `metadata` - The object's metadata
+/// * `requirements` - The labels and annotations the object must carry
+///
+/// # Returns
+///
+/// * `Vec<LabelViolation>` - One violation per requirement the object does not meet, in requirement order
+pub fn label_violations(
+    kind: &str,
+    metadata: &ObjectMeta,
+    requirements: &[LabelRequirement],
+) -> Vec<LabelViolation> {
+    requirements
+        .iter()
+        .filter_map(|requirement| {
+            let map = match requirement.field {
+                MetadataField::Labels => metadata.labels.as_ref(),
+                MetadataField::Annotations => metadata.annotations.as_ref(),
+            };
+            let value = map.and_then(|map| map.get(&requirement.key));
+
+            let issue = match (value, &requirement.value) {
+                (None, _) => LabelIssue::Missing,
+                (Some(value), Some(regex)) if !regex.is_match(value) => LabelIssue::Mismatch,
+                (Some(_), _) => return None,
+            };
+
+            Some(LabelViolation {
+                kind: kind.to_string(),
+                namespace: metadata.namespace.clone().unwrap_or_default(),
+                name: metadata.name.clone().unwrap_or_default(),
+                field: requirement.field,
+                key: requirement.key.clone(),
+                value: value.cloned().unwrap_or_default(),
+                issue,
+                expected: requirement.expected().to_string(),
+            })
+        })
+        .collect()
+}
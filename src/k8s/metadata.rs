@@ -0,0 +1,210 @@
+use crate::k8s::{ImageQuery, K8sClient, K8sError};
+use crate::utils::Pattern;
+use anyhow::Result;
+use clap::ValueEnum;
+use k8s_openapi::api::core::v1::{Namespace, Node};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::api::ListParams;
+use kube::{Api, Resource};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use tracing::{debug, info, instrument};
+
+/// Kinds of objects whose labels and annotations can be listed
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum MetadataResource {
+    /// Pods, selected with the namespace, node, pod and label filters
+    #[default]
+    Pods,
+    /// The namespace given with --namespace, or every namespace with --all-namespaces
+    Namespaces,
+    /// The node given with --node, or every node
+    Nodes,
+}
+
+impl fmt::Display for MetadataResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataResource::Pods => write!(f, "pods"),
+            MetadataResource::Namespaces => write!(f, "namespaces"),
+            MetadataResource::Nodes => write!(f, "nodes"),
+        }
+    }
+}
+
+/// The key-value map of an object's metadata to list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataField {
+    /// `metadata.labels`
+    Labels,
+    /// `metadata.annotations`
+    Annotations,
+}
+
+impl fmt::Display for MetadataField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetadataField::Labels => write!(f, "labels"),
+            MetadataField::Annotations => write!(f, "annotations"),
+        }
+    }
+}
+
+/// A single label or annotation of a pod, namespace or node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MetadataEntry {
+    /// Kind of the object, e.g. `Pod` or `Node`
+    pub kind: String,
+    /// Namespace of the object, empty for namespaces and nodes
+    pub namespace: String,
+    /// Name of the object
+    pub name: String,
+    /// Label or annotation key
+    pub key: String,
+    /// Label or annotation value
+    pub value: String,
+}
+
+impl K8sClient {
+    /// List the labels or annotations of pods, namespaces or nodes
+    ///
+    /// Pods are selected with the namespace, node, pod and label filters of the
+    /// query. Namespaces are limited to the query's namespace unless it spans all
+    /// namespaces, and nodes to the query's node when one is given.
+    ///
+    /// # Arguments
+    ///
+    /// * `resource` - The kind of objects to read
+    /// * `field` - Whether to list labels or annotations
+    /// * `query` - The criteria used to select the objects
+    /// * `keys` - Only include keys matching one of these exact keys or globs; all keys when empty
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<MetadataEntry>>` - One entry per matching key of every object, or an error
+    #[instrument(skip(self, query, keys), fields(resource = %resource, field = %field))]
+    pub async fn get_metadata(
+        &self,
+        resource: MetadataResource,
+        field: MetadataField,
+        query: &ImageQuery,
+        keys: &[Pattern],
+    ) -> Result<Vec<MetadataEntry>> {
+        debug!(?query, ?keys, "Fetching metadata");
+
+        let mut entries = Vec::new();
+        match resource {
+            MetadataResource::Pods => {
+                self.for_each_pod_metadata_page(query, |pods| {
+                    for metadata in &pods {
+                        entries.extend(metadata_entries("Pod", metadata, field, keys));
+                    }
+                    Ok(())
+                })
+                .await?;
+            }
+            MetadataResource::Namespaces => {
+                let name = (!query.all_namespaces).then_some(query.namespace.as_str());
+                let api: Api<Namespace> = Api::all(self.client.clone());
+                self.list_cluster_metadata(&api, name, query, field, keys, &mut entries)
+                    .await?;
+            }
+            MetadataResource::Nodes => {
+                let api: Api<Node> = Api::all(self.client.clone());
+                self.list_cluster_metadata(
+                    &api,
+                    query.node.as_deref(),
+                    query,
+                    field,
+                    keys,
+                    &mut entries,
+                )
+                .await?;
+            }
+        }
+
+        info!(count = entries.len(), "Successfully listed {}", field);
+        Ok(entries)
+    }
+
+    /// Collect the metadata entries of cluster-scoped objects, optionally a single named one
+    ///
+    /// Only the metadata of the objects is listed.
+    async fn list_cluster_metadata<K>(
+        &self,
+        api: &Api<K>,
+        name: Option<&str>,
+        query: &ImageQuery,
+        field: MetadataField,
+        keys: &[Pattern],
+        entries: &mut Vec<MetadataEntry>,
+    ) -> Result<()>
+    where
+        K: Resource<DynamicType = ()> + Clone + DeserializeOwned + fmt::Debug,
+    {
+        let mut list_params = ListParams::default();
+        if let Some(name) = name {
+            list_params = list_params.fields(&format!("metadata.name={}", name));
+        }
+        if let Some(labels) = &query.label_selector {
+            list_params = list_params.labels(labels);
+        }
+
+        let kind = K::kind(&());
+        let listed = self
+            .list_metadata_pages(api, &list_params, |objects| {
+                for object in &objects {
+                    entries.extend(metadata_entries(&kind, &object.metadata, field, keys));
+                }
+                Ok(())
+            })
+            .await?;
+
+        if listed == 0 {
+            let resource = match name {
+                Some(name) => format!("{} {}", kind, name),
+                None => format!("{} objects", kind),
+            };
+            return Err(K8sError::ResourceNotFound(resource).into());
+        }
+        Ok(())
+    }
+}
+
+/// List the labels or annotations of a single object
+///
+/// # Arguments
+///
+/// * `kind` - Kind of the object, e.g. `Pod`
+/// * `metadata` - The object's metadata
+/// * `field` - Whether to list labels or annotations
+/// * `keys` - Only include keys matching one of these exact keys or globs; all keys when empty
+///
+/// # Returns
+///
+/// * `Vec<MetadataEntry>` - One entry per matching key, sorted by key
+pub fn metadata_entries(
+    kind: &str,
+    metadata: &ObjectMeta,
+    field: MetadataField,
+    keys: &[Pattern],
+) -> Vec<MetadataEntry> {
+    let map = match field {
+        MetadataField::Labels => metadata.labels.as_ref(),
+        MetadataField::Annotations => metadata.annotations.as_ref(),
+    };
+
+    map.into_iter()
+        .flatten()
+        .filter(|(key, _)| keys.is_empty() || keys.iter().any(|pattern| pattern.matches(key)))
+        .map(|(key, value)| MetadataEntry {
+            kind: kind.to_string(),
+            namespace: metadata.namespace.clone().unwrap_or_default(),
+            name: metadata.name.clone().unwrap_or_default(),
+            key: key.clone(),
+            value: value.clone(),
+        })
+        .collect()
+}
@@ -1,13 +1,64 @@
-use crate::utils::{strip_registry, KNOWN_REGISTRIES};
+use crate::image::{ImageReference, ProviderKind, RegistryProvider, RegistryResolver};
+use crate::utils::Pattern;
 use anyhow::{Context, Result};
-use k8s_openapi::api::apps::v1::Deployment;
-use k8s_openapi::api::core::v1::Pod;
-use kube::{api::ListParams, Api, Client};
+use k8s_openapi::api::core::v1::{ContainerStatus, Pod, PodSpec};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::{ListParams, ObjectList, PartialObjectMeta};
+use kube::config::{KubeConfigOptions, Kubeconfig};
+use kube::{Api, Client, Config, Resource};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
 use thiserror::Error;
 use tracing::{debug, error, info, instrument};
 
+mod audit;
+mod drift;
+mod fleet;
+mod health;
+mod labels;
+mod metadata;
+mod policy;
+mod projection;
+mod query;
+mod registries;
+mod skew;
+mod snapshot;
+mod summary;
+mod watch;
+
+pub use audit::{audit_tags, tag_issues, TagAuditEntry, TagIssue};
+pub use drift::{detect_drift, pod_drift, ImageDrift};
+pub use fleet::{
+    kubeconfig_contexts, query_fleet, ClusterFailure, ClusterRecord, FleetResult,
+    DEFAULT_CLUSTER_CONCURRENCY,
+};
+pub use health::{
+    describe_probe, pod_health, ContainerHealth, HealthIssue, DEFAULT_RESTART_THRESHOLD,
+};
+pub use labels::{
+    label_violations, LabelAudit, LabelIssue, LabelRequirement, LabelTarget, LabelViolation,
+};
+pub use metadata::{metadata_entries, MetadataEntry, MetadataField, MetadataResource};
+pub use policy::RegistryPolicy;
+use projection::Projected;
+pub use query::{ContainerTypeFilter, ImageQuery};
+pub use registries::{
+    summarize_registry_usage, summarize_registry_usage_with, RegistrySource, RegistryUsage,
+    WorkloadRef,
+};
+pub use skew::{audit_skew, SkewEntry};
+pub use snapshot::{ImageSnapshot, SnapshotChange};
+pub use summary::{summarize_images, GroupBy, ImageSummary};
+pub use watch::{diff_pod_images, ImageChange, ImageEvent};
+
 /// Represents a container image running in a Kubernetes pod
-#[derive(Debug, Clone)]
+///
+/// The field names are the stable keys used by the machine-readable output formats.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PodImage {
     /// Name of the pod containing the image
     pub pod_name: String,
@@ -15,18 +66,89 @@ pub struct PodImage {
     pub node_name: String,
     /// Kubernetes namespace of the pod
     pub namespace: String,
+    /// Workload running the pod as `Kind/name`, e.g. `Deployment/api` or `Pod/debug` for bare pods
+    pub workload: String,
     /// Name of the container using this image
     pub container_name: String,
+    /// Whether the container is an init, regular or ephemeral container
+    pub container_type: ContainerType,
     /// Name of the container image
     pub image_name: String,
-    /// Version/tag of the container image
+    /// Version/tag of the container image (`latest` when the image has no tag)
     pub image_version: String,
+    /// Whether the tag was written in the image reference or defaulted to `latest`
+    pub tag_source: TagSource,
+    /// Whether the image reference pins a digest (`image@sha256:...`)
+    pub pinned_by_digest: bool,
     /// Registry where the image is hosted
     pub registry: String,
+    /// Registry the image originally comes from when pulled through a mirror, otherwise `registry`
+    pub upstream: String,
+    /// Service hosting the registry, e.g. `ecr` or `artifact-registry`
+    pub provider: ProviderKind,
+    /// Cloud account, project or namespace owning the image, if known
+    pub provider_account: String,
+    /// Cloud region or location of the registry, if known
+    pub provider_region: String,
     /// Image digest (if available)
     pub digest: String,
 }
 
+/// The kind of container an image belongs to within a pod
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerType {
+    /// A container listed in `spec.initContainers`
+    Init,
+    /// A container listed in `spec.containers`
+    #[default]
+    Regular,
+    /// A container listed in `spec.ephemeralContainers`
+    Ephemeral,
+}
+
+/// Whether an image's tag was given explicitly or defaulted by the container runtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagSource {
+    /// The image reference names a tag, e.g. `nginx:1.25` or `nginx:latest`
+    #[default]
+    Explicit,
+    /// The image reference has no tag and resolves to `latest`, e.g. `nginx`
+    Implicit,
+}
+
+impl TagSource {
+    /// Get the tag source of a parsed image reference
+    pub fn of(reference: &ImageReference) -> Self {
+        match reference.tag {
+            Some(_) => TagSource::Explicit,
+            None => TagSource::Implicit,
+        }
+    }
+}
+
+impl std::fmt::Display for TagSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TagSource::Explicit => write!(f, "explicit"),
+            TagSource::Implicit => write!(f, "implicit"),
+        }
+    }
+}
+
+impl std::fmt::Display for ContainerType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContainerType::Init => write!(f, "init"),
+            ContainerType::Regular => write!(f, "regular"),
+            ContainerType::Ephemeral => write!(f, "ephemeral"),
+        }
+    }
+}
+
 /// Errors that can occur when interacting with Kubernetes
 #[derive(Debug, Error)]
 pub enum K8sError {
@@ -44,30 +166,112 @@ pub enum K8sError {
     ResourceNotFound(String),
 }
 
+/// Number of objects requested per list call unless configured otherwise, as in kubectl
+pub const DEFAULT_CHUNK_SIZE: u32 = 500;
+
+/// Options controlling how the Kubernetes client connects to a cluster
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientOptions {
+    /// Explicit path to a kubeconfig file (falls back to KUBECONFIG, ~/.kube/config, then in-cluster)
+    pub kubeconfig: Option<PathBuf>,
+    /// Kubeconfig context to use instead of the current context
+    pub context: Option<String>,
+    /// Kubeconfig cluster to use instead of the one referenced by the context
+    pub cluster: Option<String>,
+    /// Kubeconfig user to use instead of the one referenced by the context
+    pub user: Option<String>,
+    /// Username to impersonate for every API request
+    pub impersonate: Option<String>,
+    /// Maximum number of objects returned by each list call, 0 to list everything at once
+    pub chunk_size: u32,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            kubeconfig: None,
+            context: None,
+            cluster: None,
+            user: None,
+            impersonate: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+impl ClientOptions {
+    /// Check if any kubeconfig selection (context, cluster or user) was requested
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if a context, cluster or user override is set
+    pub fn has_kubeconfig_overrides(&self) -> bool {
+        self.context.is_some() || self.cluster.is_some() || self.user.is_some()
+    }
+
+    /// Convert the context, cluster and user overrides into kube's kubeconfig options
+    fn kubeconfig_options(&self) -> KubeConfigOptions {
+        KubeConfigOptions {
+            context: self.context.clone(),
+            cluster: self.cluster.clone(),
+            user: self.user.clone(),
+        }
+    }
+}
+
 /// Client for interacting with Kubernetes clusters
 pub struct K8sClient {
     /// The underlying Kubernetes client
     client: Client,
+    /// Resolves image references into the registries and repositories reported
+    resolver: RegistryResolver,
+    /// Name of the cluster: the kubeconfig context, or the API server URL in-cluster
+    cluster: String,
+    /// Maximum number of objects returned by each list call, 0 to list everything at once
+    chunk_size: u32,
 }
 
 impl K8sClient {
-    /// Create a new Kubernetes client
+    /// Create a new Kubernetes client using the default kubeconfig resolution
     ///
     /// # Returns
     ///
     /// * `Result<Self>` - A new K8sClient instance or an error if initialization fails
     #[instrument(skip_all)]
     pub async fn new() -> Result<Self> {
+        Self::from_options(&ClientOptions::default()).await
+    }
+
+    /// Create a new Kubernetes client from explicit connection options
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Kubeconfig path, context/cluster/user overrides and impersonation settings
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self>` - A new K8sClient instance or an error if initialization fails
+    #[instrument(skip_all, fields(
+        kubeconfig = ?options.kubeconfig,
+        context = ?options.context,
+        cluster = ?options.cluster,
+        user = ?options.user
+    ))]
+    pub async fn from_options(options: &ClientOptions) -> Result<Self> {
         debug!("Initializing Kubernetes client");
 
-        let kubeconfig_path = Self::get_kubeconfig_path()?;
-        debug!(path = %kubeconfig_path, "Using kubeconfig path");
+        let config = Self::build_config(options).await?;
+        debug!(cluster_url = %config.cluster_url, "Resolved cluster configuration");
 
-        let client = Client::try_default()
-            .await
-            .context("Failed to create Kubernetes client")?;
+        let cluster = Self::context_name(options).unwrap_or_else(|| config.cluster_url.to_string());
+        let client = Client::try_from(config).context("Failed to create Kubernetes client")?;
 
-        let k8s_client = Self { client };
+        let k8s_client = Self {
+            client,
+            resolver: RegistryResolver::default(),
+            cluster,
+            chunk_size: options.chunk_size,
+        };
 
         // Verify cluster accessibility
         if !k8s_client.is_accessible().await? {
@@ -80,29 +284,101 @@ impl K8sClient {
         Ok(k8s_client)
     }
 
-    /// Get the path to the kubeconfig file
+    /// Use a custom registry resolver for every image reported by this client
+    ///
+    /// # Arguments
+    ///
+    /// * `resolver` - The resolver deciding how registries and repositories are reported
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The client using the given resolver
+    pub fn with_resolver(mut self, resolver: RegistryResolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Get the registry resolver used for every image reported by this client
+    pub fn resolver(&self) -> &RegistryResolver {
+        &self.resolver
+    }
+
+    /// Get the name of the cluster this client talks to
     ///
     /// # Returns
     ///
-    /// * `Result<String>` - The path to the kubeconfig file or an error if not found
-    fn get_kubeconfig_path() -> Result<String> {
-        if let Ok(path) = std::env::var("KUBECONFIG") {
-            info!("Using kubeconfig from KUBECONFIG environment variable");
-            return Ok(path);
+    /// * `&str` - The kubeconfig context, or the API server URL when running in-cluster
+    pub fn cluster_name(&self) -> &str {
+        &self.cluster
+    }
+
+    /// Find the kubeconfig context selected by the connection options
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Kubeconfig path and context override
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - The explicit context, else the current context of the kubeconfig
+    fn context_name(options: &ClientOptions) -> Option<String> {
+        if let Some(context) = &options.context {
+            return Some(context.clone());
         }
 
-        debug!("KUBECONFIG not set, checking default location");
-        let home_dir = std::env::var("HOME").context("Failed to get HOME directory")?;
-        let default_kubeconfig = format!("{}/.kube/config", home_dir);
+        let kubeconfig = match &options.kubeconfig {
+            Some(path) => Kubeconfig::read_from(path).ok()?,
+            None => Kubeconfig::read().ok()?,
+        };
+        kubeconfig.current_context
+    }
 
-        if !std::path::Path::new(&default_kubeconfig).exists() {
-            return Err(
-                K8sError::ConfigError("No kubeconfig found at default location".into()).into(),
-            );
+    /// Build the kube configuration for the given connection options
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Kubeconfig path, context/cluster/user overrides and impersonation settings
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Config>` - The resolved configuration or an error if it cannot be loaded
+    async fn build_config(options: &ClientOptions) -> Result<Config> {
+        let kubeconfig_options = options.kubeconfig_options();
+
+        let mut config = match &options.kubeconfig {
+            Some(path) => {
+                info!(path = %path.display(), "Using kubeconfig from command line");
+                let kubeconfig = Kubeconfig::read_from(path).map_err(|e| {
+                    K8sError::ConfigError(format!(
+                        "Failed to read kubeconfig {}: {}",
+                        path.display(),
+                        e
+                    ))
+                })?;
+                Config::from_custom_kubeconfig(kubeconfig, &kubeconfig_options)
+                    .await
+                    .map_err(|e| K8sError::ConfigError(e.to_string()))?
+            }
+            None if options.has_kubeconfig_overrides() => {
+                debug!("Using default kubeconfig location with overrides");
+                Config::from_kubeconfig(&kubeconfig_options)
+                    .await
+                    .map_err(|e| K8sError::ConfigError(e.to_string()))?
+            }
+            None => {
+                debug!("Inferring configuration from KUBECONFIG, ~/.kube/config or in-cluster");
+                Config::infer()
+                    .await
+                    .map_err(|e| K8sError::ConfigError(e.to_string()))?
+            }
+        };
+
+        if let Some(impersonate) = &options.impersonate {
+            debug!(user = %impersonate, "Impersonating user");
+            config.auth_info.impersonate = Some(impersonate.clone());
         }
 
-        info!("Using default kubeconfig location");
-        Ok(default_kubeconfig)
+        Ok(config)
     }
 
     /// Check if the Kubernetes cluster is accessible
@@ -151,14 +427,7 @@ impl K8sClient {
     ///
     /// # Returns
     ///
-    /// * `Result<Vec<PodImage>>` - List of matching pod images or an error
-    #[instrument(skip(self), fields(
-        namespace = %namespace,
-        node = ?node_name,
-        pod = ?pod_name,
-        registry = ?registry_filter,
-        all_namespaces = %all_namespaces
-    ))]
+    /// * `Result<Vec<PodImage>>` - List of matching pod images, or an error if no pods match
     pub async fn get_pod_images(
         &self,
         namespace: &str,
@@ -167,90 +436,332 @@ impl K8sClient {
         registry_filter: Option<&str>,
         all_namespaces: bool,
     ) -> Result<Vec<PodImage>> {
+        let query = ImageQuery {
+            namespace: namespace.to_string(),
+            all_namespaces,
+            node: node_name.map(String::from),
+            pod: pod_name
+                .map(str::parse)
+                .transpose()
+                .map_err(anyhow::Error::msg)?,
+            registries: registry_filter
+                .map(str::parse)
+                .transpose()
+                .map_err(anyhow::Error::msg)?
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+
+        let mut images = Vec::new();
+        let listed = self
+            .for_each_pod_page(&query, |pods| {
+                images.extend(self.matching_images(&query, &pods));
+                Ok(())
+            })
+            .await?;
+        if listed == 0 {
+            return Err(no_pods_found(&query));
+        }
+        Ok(images)
+    }
+
+    /// Get pod images matching an image query
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The criteria used to select pods and images
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<PodImage>>` - List of matching pod images or an error
+    #[instrument(skip(self), fields(
+        namespace = %query.namespace,
+        node = ?query.node,
+        pod = ?query.pod,
+        pod_regex = ?query.pod_regex,
+        image = ?query.image,
+        tag = ?query.tag,
+        registries = ?query.registries,
+        exclude_registries = ?query.exclude_registries,
+        label_selector = ?query.label_selector,
+        field_selector = ?query.field_selector,
+        container_type = %query.container_type,
+        all_namespaces = %query.all_namespaces
+    ))]
+    pub async fn query_pod_images(&self, query: &ImageQuery) -> Result<Vec<PodImage>> {
+        debug!(?query, "Fetching pod images");
+
+        let mut all_images = Vec::new();
+        self.stream_pod_images(query, |images| {
+            all_images.extend(images);
+            Ok(())
+        })
+        .await?;
+
+        info!(
+            total_images = all_images.len(),
+            "Successfully retrieved pod images"
+        );
+        Ok(all_images)
+    }
+
+    /// Get pod images matching an image query, one page of pods at a time
+    ///
+    /// Pods are listed in pages of at most the client's chunk size, and the
+    /// matching images of each page are handed to `on_images` before the next
+    /// page is requested, so large clusters never have to fit in memory at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The criteria used to select pods and images
+    /// * `on_images` - Called with the matching images of every page that has any
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The number of matching images, or the first listing or callback error
+    pub async fn stream_pod_images(
+        &self,
+        query: &ImageQuery,
+        mut on_images: impl FnMut(Vec<PodImage>) -> Result<()>,
+    ) -> Result<usize> {
+        let mut total = 0;
+        self.for_each_pod_page(query, |pods| {
+            let images = self.matching_images(query, &pods);
+            total += images.len();
+            if images.is_empty() {
+                return Ok(());
+            }
+            on_images(images)
+        })
+        .await?;
+
+        Ok(total)
+    }
+
+    /// Get the images of a page of pods that match the image-level criteria of a query
+    fn matching_images(&self, query: &ImageQuery, pods: &[Pod]) -> Vec<PodImage> {
+        let mut images: Vec<PodImage> = pods
+            .iter()
+            .flat_map(|pod| process_pod_with(pod, &self.resolver))
+            .collect();
+
+        let before_count = images.len();
+        images.retain(|image| query.matches_image(image));
         debug!(
-            namespace = %namespace,
-            node = ?node_name,
-            pod = ?pod_name,
-            registry = ?registry_filter,
-            all_namespaces = %all_namespaces,
-            "Fetching pod images"
+            before = before_count,
+            after = images.len(),
+            "Filtered images by image, tag, registry and container type"
         );
+        images
+    }
+
+    /// List the pods matching the pod-level criteria of an image query, page by page
+    ///
+    /// A namespace without pods is not an error: reports, checks and snapshots
+    /// of it are simply empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The criteria used to select pods
+    /// * `on_page` - Called with the matching pods of every page as it arrives
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The number of listed pods, or an error if the namespace
+    ///   does not exist or listing the pods fails
+    async fn for_each_pod_page(
+        &self,
+        query: &ImageQuery,
+        mut on_page: impl FnMut(Vec<Pod>) -> Result<()>,
+    ) -> Result<usize> {
+        let namespace = query.namespace.as_str();
+        let node_name = query.node.as_deref();
+        let all_namespaces = query.all_namespaces;
 
         if !all_namespaces && !self.namespace_exists(namespace).await? {
             let resource = format!("Namespace {} not found", namespace);
             return Err(K8sError::ResourceNotFound(resource).into());
         }
 
-        let list_params = Self::build_list_params(node_name, pod_name);
-        let pods = self.get_pods_api(namespace, all_namespaces, node_name)?;
+        let list_params = query.list_params();
+        let pods: Api<Projected<Pod>> = self.get_pods_api(namespace, all_namespaces, node_name)?;
 
-        let pods_list = pods
-            .list(&list_params)
-            .await
-            .context("Failed to list pods")?;
+        let listed = self
+            .list_pages(&pods, &list_params, |page| {
+                on_page(
+                    page.into_iter()
+                        .map(Projected::into_pod)
+                        .filter(|pod| query.matches_pod(pod))
+                        .collect(),
+                )
+            })
+            .await?;
 
-        debug!("Found {} pods", pods_list.items.len());
+        debug!("Found {} pods", listed);
+        Ok(listed)
+    }
+
+    /// List the metadata of the pods matching the pod-level criteria of an image query, page by page
+    ///
+    /// The node filter is applied by the API server as a field selector, so only
+    /// the metadata of the pods has to be listed.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The criteria used to select pods
+    /// * `on_page` - Called with the metadata of the matching pods of every page as it arrives
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The number of listed pods, or an error if the namespace
+    ///   does not exist or listing the pods fails
+    pub(crate) async fn for_each_pod_metadata_page(
+        &self,
+        query: &ImageQuery,
+        mut on_page: impl FnMut(Vec<ObjectMeta>) -> Result<()>,
+    ) -> Result<usize> {
+        let namespace = query.namespace.as_str();
+        let all_namespaces = query.all_namespaces;
 
-        if pods_list.items.is_empty() {
-            let resource = match (node_name, pod_name) {
-                (Some(node), Some(pod)) => format!("pod {} on node {}", pod, node),
-                (Some(node), None) => format!("pods on node {}", node),
-                (None, Some(pod)) => format!("pod {}", pod),
-                (None, None) => format!("pods in namespace {}", namespace),
-            };
+        if !all_namespaces && !self.namespace_exists(namespace).await? {
+            let resource = format!("Namespace {} not found", namespace);
             return Err(K8sError::ResourceNotFound(resource).into());
         }
 
-        let mut all_images = Vec::new();
-        for pod in pods_list {
-            if !Self::should_process_pod(&pod, all_namespaces, node_name, pod_name) {
-                continue;
-            }
+        let list_params = query.list_params();
+        let pods: Api<Pod> = self.get_pods_api(namespace, all_namespaces, query.node.as_deref())?;
 
-            let pod_images = process_pod(&pod);
-            debug!(images = pod_images.len(), "Processed pod images");
-            all_images.extend(pod_images);
-        }
+        let listed = self
+            .list_metadata_pages(&pods, &list_params, |page| {
+                on_page(
+                    page.into_iter()
+                        .map(|pod| pod.metadata)
+                        .filter(|metadata| {
+                            query.matches_pod_name(metadata.name.as_deref().unwrap_or_default())
+                        })
+                        .collect(),
+                )
+            })
+            .await?;
 
-        if let Some(registry_filter) = registry_filter {
-            let before_count = all_images.len();
-            all_images.retain(|image| image.registry == registry_filter);
-            debug!(
-                before = before_count,
-                after = all_images.len(),
-                "Filtered images by registry"
-            );
-        }
+        debug!("Found {} pods", listed);
+        Ok(listed)
+    }
 
-        info!(
-            total_images = all_images.len(),
-            "Successfully retrieved pod images"
-        );
-        Ok(all_images)
+    /// List objects in pages of at most the client's chunk size
+    ///
+    /// Each page is handed to `on_page` before the next one is requested,
+    /// following the `continue` token returned by the API server.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - The API to list objects from
+    /// * `list_params` - Selectors applied to every page
+    /// * `on_page` - Called with the objects of every page
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The number of objects listed, or the first listing or callback error
+    pub(crate) async fn list_pages<K>(
+        &self,
+        api: &Api<K>,
+        list_params: &ListParams,
+        on_page: impl FnMut(Vec<K>) -> Result<()>,
+    ) -> Result<usize>
+    where
+        K: Resource<DynamicType = ()> + Clone + DeserializeOwned + fmt::Debug,
+    {
+        self.paginate::<K, _, _, _>(
+            list_params,
+            |list_params| async move { api.list(&list_params).await },
+            on_page,
+        )
+        .await
     }
 
-    /// Build list parameters for pod queries
-    fn build_list_params(node_name: Option<&str>, pod_name: Option<&str>) -> ListParams {
-        let mut field_selectors = Vec::new();
+    /// List only the metadata of objects, in pages of at most the client's chunk size
+    ///
+    /// The API server leaves out the spec and status of every object, so this
+    /// is much lighter than [`K8sClient::list_pages`] when only names, labels,
+    /// annotations or owners are read.
+    ///
+    /// # Arguments
+    ///
+    /// * `api` - The API to list objects from
+    /// * `list_params` - Selectors applied to every page
+    /// * `on_page` - Called with the metadata of the objects of every page
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The number of objects listed, or the first listing or callback error
+    pub(crate) async fn list_metadata_pages<K>(
+        &self,
+        api: &Api<K>,
+        list_params: &ListParams,
+        on_page: impl FnMut(Vec<PartialObjectMeta<K>>) -> Result<()>,
+    ) -> Result<usize>
+    where
+        K: Resource<DynamicType = ()> + Clone + DeserializeOwned + fmt::Debug,
+    {
+        self.paginate::<K, _, _, _>(
+            list_params,
+            |list_params| async move { api.list_metadata(&list_params).await },
+            on_page,
+        )
+        .await
+    }
 
-        if let Some(node) = node_name {
-            field_selectors.push(format!("spec.nodeName={}", node));
+    /// Request pages of a listing until the API server returns no `continue` token
+    async fn paginate<K, T, F, Fut>(
+        &self,
+        list_params: &ListParams,
+        mut list: F,
+        mut on_page: impl FnMut(Vec<T>) -> Result<()>,
+    ) -> Result<usize>
+    where
+        K: Resource<DynamicType = ()>,
+        T: Clone,
+        F: FnMut(ListParams) -> Fut,
+        Fut: Future<Output = kube::Result<ObjectList<T>>>,
+    {
+        let mut list_params = list_params.clone();
+        if self.chunk_size > 0 {
+            list_params = list_params.limit(self.chunk_size);
         }
 
-        if let Some(name) = pod_name {
-            field_selectors.push(format!("metadata.name={}", name));
-        }
+        let mut listed = 0;
+        loop {
+            let list = list(list_params.clone())
+                .await
+                .with_context(|| format!("Failed to list {}", K::plural(&())))?;
+            listed += list.items.len();
+            debug!(
+                kind = %K::kind(&()),
+                count = list.items.len(),
+                remaining = ?list.metadata.remaining_item_count,
+                "Listed page"
+            );
 
-        ListParams::default().fields(&field_selectors.join(","))
+            let continue_token = list.metadata.continue_.filter(|token| !token.is_empty());
+            on_page(list.items)?;
+            match continue_token {
+                Some(token) => list_params = list_params.continue_token(&token),
+                None => return Ok(listed),
+            }
+        }
     }
 
-    /// Get the pods API for the specified namespace
-    fn get_pods_api(
+    /// Get the pods API for the specified namespace, for full or projected pods
+    fn get_pods_api<K>(
         &self,
         namespace: &str,
         all_namespaces: bool,
         _node_name: Option<&str>,
-    ) -> Result<Api<Pod>> {
+    ) -> Result<Api<K>>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>,
+    {
         let api = if all_namespaces {
             Api::all(self.client.clone())
         } else {
@@ -259,29 +770,7 @@ impl K8sClient {
         Ok(api)
     }
 
-    /// Check if a pod should be processed based on filters
-    fn should_process_pod(
-        pod: &Pod,
-        _all_namespaces: bool,
-        node_name: Option<&str>,
-        pod_name: Option<&str>,
-    ) -> bool {
-        if let Some(name) = pod_name {
-            if pod.metadata.name.as_deref() != Some(name) {
-                return false;
-            }
-        }
-
-        if let Some(node) = node_name {
-            if pod.spec.as_ref().and_then(|s| s.node_name.as_deref()) != Some(node) {
-                return false;
-            }
-        }
-
-        true
-    }
-
-    /// Get unique container image registries used in the cluster
+    /// Get unique container image registries used by workloads in the cluster
     ///
     /// # Arguments
     ///
@@ -291,66 +780,15 @@ impl K8sClient {
     /// # Returns
     ///
     /// * `Result<Vec<String>>` - List of unique registries or an error
-    #[instrument(skip(self), fields(
-        namespace = %namespace,
-        all_namespaces = %all_namespaces
-    ))]
     pub async fn get_unique_registries(
         &self,
         namespace: &str,
         all_namespaces: bool,
     ) -> Result<Vec<String>> {
-        debug!(
-            namespace = %namespace,
-            all_namespaces = %all_namespaces,
-            "Fetching unique registries from deployments"
-        );
-
-        if !all_namespaces && !self.namespace_exists(namespace).await? {
-            let resource = format!("Namespace {} not found", namespace);
-            return Err(K8sError::ResourceNotFound(resource).into());
-        }
-
-        let deployments_api: Api<Deployment> = if all_namespaces {
-            Api::all(self.client.clone())
-        } else {
-            Api::namespaced(self.client.clone(), namespace)
-        };
-
-        let deployments = deployments_api
-            .list(&Default::default())
-            .await
-            .context("Failed to list deployments")?;
-
-        debug!("Found {} deployments", deployments.items.len());
-
-        if deployments.items.is_empty() {
-            let resource = format!("deployments in namespace {}", namespace);
-            return Err(K8sError::ResourceNotFound(resource).into());
-        }
-
-        let mut registries = std::collections::HashSet::new();
-        for deploy in deployments {
-            if let Some(spec) = deploy.spec {
-                if let Some(pod_spec) = spec.template.spec {
-                    for container in pod_spec.containers {
-                        if let Some(image) = container.image {
-                            let registry = extract_registry(&image);
-                            registries.insert(registry);
-                        }
-                    }
-                }
-            }
-        }
-
-        let mut registries_vec: Vec<String> = registries.into_iter().collect();
-        registries_vec.sort();
-
-        info!(
-            total_registries = registries_vec.len(),
-            "Successfully retrieved unique registries from deployments"
-        );
-        Ok(registries_vec)
+        let usage = self
+            .get_registry_usage(namespace, all_namespaces, RegistrySource::Workloads)
+            .await?;
+        Ok(usage.into_iter().map(|usage| usage.registry).collect())
     }
 
     /// Check if a namespace exists
@@ -395,68 +833,34 @@ impl K8sClient {
 ///
 /// # Returns
 ///
-/// * `String` - The registry name
+/// * `String` - The registry name, `docker.io` when the reference does not name one
 pub fn extract_registry(image: &str) -> String {
-    // Split the image string by '/'
-    let parts: Vec<&str> = image.split('/').collect();
-
-    // If there's only one part (e.g., "ubuntu" or "nginx"), it's a Docker Hub official image
-    if parts.len() == 1 {
-        return "docker.io".to_string();
-    }
-
-    // If there are two parts without dots or colons in the first part (e.g., "library/ubuntu"),
-    // it's likely a Docker Hub image with namespace
-    if parts.len() == 2 && !parts[0].contains('.') && !parts[0].contains(':') {
-        return "docker.io".to_string();
-    }
-
-    // Get the potential registry (first part)
-    let potential_registry = parts[0];
-
-    // Check for localhost variants (with or without port)
-    if potential_registry == "localhost"
-        || potential_registry.starts_with("localhost:")
-        || potential_registry.starts_with("127.0.0.1")
-        || potential_registry.starts_with("0.0.0.0")
-        || potential_registry.starts_with("[::1]")
-    {
-        return potential_registry.to_string();
-    }
-
-    // Check for IPv4 address (with or without port)
-    let ip_parts: Vec<&str> = potential_registry.split(':').collect();
-    let ip = ip_parts[0];
-    if ip.split('.').filter(|&p| !p.is_empty()).count() == 4
-        && ip.split('.').all(|p| p.parse::<u8>().is_ok())
-    {
-        return potential_registry.to_string();
-    }
-
-    // Check for IPv6 address (with or without port)
-    if potential_registry.starts_with('[') && potential_registry.contains(']') {
-        return potential_registry.to_string();
-    }
+    ImageReference::lossy(image).registry
+}
 
-    // Check for known public registries
-    let known_registries = KNOWN_REGISTRIES;
-    for registry in &known_registries {
-        if potential_registry == *registry || potential_registry.ends_with(*registry) {
-            return potential_registry.to_string();
-        }
+/// Build the error reported when no pods match the pod-level criteria of a query
+fn no_pods_found(query: &ImageQuery) -> anyhow::Error {
+    let pod_name = query.pod.as_ref().map(Pattern::as_str);
+    let mut resource = match (query.node.as_deref(), pod_name) {
+        (Some(node), Some(pod)) => format!("pod {} on node {}", pod, node),
+        (Some(node), None) => format!("pods on node {}", node),
+        (None, Some(pod)) => format!("pod {}", pod),
+        (None, None) => format!("pods in namespace {}", query.namespace),
+    };
+    if let Some(labels) = &query.label_selector {
+        resource.push_str(&format!(" with labels {}", labels));
     }
-
-    // For any domain with dots (e.g., "my-registry.example.com") or with port (e.g., "registry:5000")
-    if potential_registry.contains('.') || potential_registry.contains(':') {
-        return potential_registry.to_string();
+    if let Some(fields) = &query.field_selector {
+        resource.push_str(&format!(" with fields {}", fields));
     }
-
-    // Default to Docker Hub if none of the above matches
-    "docker.io".to_string()
+    K8sError::ResourceNotFound(resource).into()
 }
 
 /// Split a container image reference into name and version
 ///
+/// The name keeps the registry as written; the version is the tag (`latest`
+/// when missing) followed by `@digest` when the image is pinned.
+///
 /// # Arguments
 ///
 /// * `image` - The container image reference
@@ -465,56 +869,69 @@ pub fn extract_registry(image: &str) -> String {
 ///
 /// * `(String, String)` - Tuple of (image name, image version)
 pub fn split_image(image: &str) -> (String, String) {
-    // First check for a digest (SHA)
-    if let Some(digest_index) = image.find('@') {
-        // We have a digest, get the part before the digest
-        let image_with_tag = &image[..digest_index];
-        let digest = &image[digest_index..]; // includes the @ symbol
-
-        // Find the last colon which separates the image name from the tag
-        if let Some(tag_index) = image_with_tag.rfind(':') {
-            // Check if this colon is part of a port number in the registry
-            // Look for slashes to determine if this is likely a registry port
-            let last_slash_index = image_with_tag.rfind('/').unwrap_or(0);
-
-            if tag_index > last_slash_index {
-                // This colon is after the last slash, so it's a tag separator
-                let name = &image_with_tag[..tag_index];
-                let tag = &image_with_tag[tag_index + 1..];
-                (name.to_string(), format!("{}@{}", tag, &digest[1..]))
-            } else {
-                // This colon is part of the registry address, no tag specified
-                (
-                    image_with_tag.to_string(),
-                    format!("latest@{}", &digest[1..]),
-                )
-            }
-        } else {
-            // No tag present, use "latest" with the digest
-            (
-                image_with_tag.to_string(),
-                format!("latest@{}", &digest[1..]),
-            )
-        }
-    } else {
-        // No digest, handle image name and tag
-        // Find the last colon which might separate the image name from the tag
-        if let Some(tag_index) = image.rfind(':') {
-            // Check if this colon is part of a port number in the registry
-            // Look for slashes to determine if this is likely a registry port
-            let last_slash_index = image.rfind('/').unwrap_or(0);
-
-            if tag_index > last_slash_index {
-                // This colon is after the last slash, so it's a tag separator
-                let name = &image[..tag_index];
-                let tag = &image[tag_index + 1..];
-                return (name.to_string(), tag.to_string());
-            }
-        }
+    let reference = ImageReference::lossy(image);
+    (reference.name(), reference.version())
+}
 
-        // No valid tag separator found
-        (image.to_string(), "latest".to_string())
-    }
+/// Determine whether a container image reference names its tag explicitly
+///
+/// # Arguments
+///
+/// * `image` - The container image reference
+///
+/// # Returns
+///
+/// * `TagSource` - `Explicit` if a tag is present, `Implicit` if it defaults to `latest`
+pub fn image_tag_source(image: &str) -> TagSource {
+    TagSource::of(&ImageReference::lossy(image))
+}
+
+/// Find the status of a container within a pod
+///
+/// # Arguments
+///
+/// * `pod` - The pod containing the container
+/// * `container_name` - The name of the container
+/// * `container_type` - The type of the container, selecting the matching status list
+///
+/// # Returns
+///
+/// * `Option<&ContainerStatus>` - The container status if the container has been created
+pub(crate) fn container_status<'a>(
+    pod: &'a Pod,
+    container_name: &str,
+    container_type: ContainerType,
+) -> Option<&'a ContainerStatus> {
+    let status = pod.status.as_ref()?;
+    let statuses = match container_type {
+        ContainerType::Init => status.init_container_statuses.as_ref(),
+        ContainerType::Regular => status.container_statuses.as_ref(),
+        ContainerType::Ephemeral => status.ephemeral_container_statuses.as_ref(),
+    }?;
+
+    statuses.iter().find(|cs| cs.name == container_name)
+}
+
+/// Extract the digest from a container status `imageID`
+///
+/// Runtimes report the image ID in several shapes, e.g. `sha256:abc...`,
+/// `docker.io/library/nginx@sha256:abc...` or `docker-pullable://nginx@sha256:abc...`.
+/// The digest is the part after the last `@`, without its algorithm prefix.
+///
+/// # Arguments
+///
+/// * `image_id` - The image ID reported in the container status
+///
+/// # Returns
+///
+/// * `Option<String>` - The digest hex if one is present
+pub fn parse_image_id_digest(image_id: &str) -> Option<String> {
+    let digest = image_id.rsplit('@').next().unwrap_or_default();
+    let hex = digest
+        .split_once(':')
+        .map_or(digest, |(_algorithm, hex)| hex);
+
+    (!hex.is_empty() && !hex.contains('/')).then(|| hex.to_string())
 }
 
 /// Extract the digest of a container from a pod
@@ -523,25 +940,102 @@ pub fn split_image(image: &str) -> (String, String) {
 ///
 /// * `pod` - The pod containing the container
 /// * `container_name` - The name of the container
+/// * `container_type` - The type of the container, selecting the matching status list
 ///
 /// # Returns
 ///
 /// * `Option<String>` - The container digest if available
-fn extract_container_digest(pod: &Pod, container_name: &str) -> Option<String> {
-    pod.status
-        .as_ref()?
-        .container_statuses
-        .as_ref()?
+fn extract_container_digest(
+    pod: &Pod,
+    container_name: &str,
+    container_type: ContainerType,
+) -> Option<String> {
+    parse_image_id_digest(&container_status(pod, container_name, container_type)?.image_id)
+}
+
+/// List every container declared in a pod spec together with its type and image
+///
+/// Init containers come first, followed by regular and then ephemeral containers.
+///
+/// # Arguments
+///
+/// * `spec` - The pod spec to walk
+///
+/// # Returns
+///
+/// * `Vec<(ContainerType, &str, Option<&str>)>` - Container type, name and image of each container
+pub(crate) fn spec_containers(spec: &PodSpec) -> Vec<(ContainerType, &str, Option<&str>)> {
+    let init = spec.init_containers.iter().flatten().map(|container| {
+        (
+            ContainerType::Init,
+            container.name.as_str(),
+            container.image.as_deref(),
+        )
+    });
+    let regular = spec.containers.iter().map(|container| {
+        (
+            ContainerType::Regular,
+            container.name.as_str(),
+            container.image.as_deref(),
+        )
+    });
+    let ephemeral = spec.ephemeral_containers.iter().flatten().map(|container| {
+        (
+            ContainerType::Ephemeral,
+            container.name.as_str(),
+            container.image.as_deref(),
+        )
+    });
+
+    init.chain(regular).chain(ephemeral).collect()
+}
+
+/// Get the workload running a pod as `Kind/name`
+///
+/// Pods of a Deployment are reported under the Deployment rather than the
+/// ReplicaSet of the current rollout, so the workload stays the same across
+/// rollouts. Pods without a controller are their own workload.
+///
+/// # Arguments
+///
+/// * `pod` - The pod to inspect
+///
+/// # Returns
+///
+/// * `String` - The workload, e.g. `Deployment/api`, `StatefulSet/db` or `Pod/debug`
+pub fn pod_workload(pod: &Pod) -> String {
+    let controller = pod
+        .metadata
+        .owner_references
         .iter()
-        .find(|cs| cs.name == container_name)?
-        .image_id
-        .split(':')
-        .nth(1)
-        .map(String::from)
+        .flatten()
+        .find(|owner| owner.controller.unwrap_or_default());
+
+    let Some(owner) = controller else {
+        let name = pod.metadata.name.as_deref().unwrap_or_default();
+        return format!("Pod/{}", name);
+    };
+
+    let template_hash = pod
+        .metadata
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get("pod-template-hash"));
+    if let Some(hash) = template_hash.filter(|_| owner.kind == "ReplicaSet") {
+        if let Some(deployment) = owner.name.strip_suffix(&format!("-{}", hash)) {
+            return format!("Deployment/{}", deployment);
+        }
+    }
+
+    format!("{}/{}", owner.kind, owner.name)
 }
 
 /// Process a pod to extract information about its container images
 ///
+/// Init, regular and ephemeral containers are all included; each image
+/// records the type of container it came from. Docker Hub references are
+/// normalized, see [`process_pod_with`] to report them as written.
+///
 /// # Arguments
 ///
 /// * `pod` - The pod to process
@@ -550,9 +1044,24 @@ fn extract_container_digest(pod: &Pod, container_name: &str) -> Option<String> {
 ///
 /// * `Vec<PodImage>` - List of container images in the pod
 pub fn process_pod(pod: &Pod) -> Vec<PodImage> {
+    process_pod_with(pod, &RegistryResolver::default())
+}
+
+/// Process a pod to extract information about its container images using a registry resolver
+///
+/// # Arguments
+///
+/// * `pod` - The pod to process
+/// * `resolver` - Decides how each image's registry and repository are reported
+///
+/// # Returns
+///
+/// * `Vec<PodImage>` - List of container images in the pod
+pub fn process_pod_with(pod: &Pod, resolver: &RegistryResolver) -> Vec<PodImage> {
     let mut pod_images = Vec::new();
     let pod_name = pod.metadata.name.clone().unwrap_or_default();
     let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+    let workload = pod_workload(pod);
     let node_name = pod
         .spec
         .as_ref()
@@ -560,22 +1069,30 @@ pub fn process_pod(pod: &Pod) -> Vec<PodImage> {
         .unwrap_or_default();
 
     if let Some(spec) = &pod.spec {
-        let containers = &spec.containers;
-        for container in containers {
-            if let Some(image) = &container.image {
-                let registry = extract_registry(image);
-                let (_image_name, image_version) = split_image(image);
-                let image_name = strip_registry(&_image_name, &registry);
-                let digest = extract_container_digest(pod, &container.name).unwrap_or_default();
+        for (container_type, container_name, image) in spec_containers(spec) {
+            if let Some(image) = image {
+                let reference = resolver.resolve(image);
+                let provider =
+                    RegistryProvider::classify(&reference.registry, &reference.repository);
+                let digest = extract_container_digest(pod, container_name, container_type)
+                    .unwrap_or_default();
 
                 pod_images.push(PodImage {
                     pod_name: pod_name.clone(),
                     namespace: namespace.clone(),
-                    container_name: container.name.clone(),
-                    image_name,
-                    image_version,
+                    workload: workload.clone(),
+                    container_name: container_name.to_string(),
+                    container_type,
+                    image_name: reference.repository.clone(),
+                    image_version: reference.version(),
+                    tag_source: TagSource::of(&reference),
+                    pinned_by_digest: reference.digest.is_some(),
                     node_name: node_name.clone(),
-                    registry,
+                    upstream: resolver.upstream(&reference),
+                    provider: provider.kind,
+                    provider_account: provider.account.unwrap_or_default(),
+                    provider_region: provider.region.unwrap_or_default(),
+                    registry: reference.registry,
                     digest,
                 });
             }
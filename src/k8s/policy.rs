@@ -0,0 +1,155 @@
+use crate::image::{ImageReference, DEFAULT_REGISTRY, DOCKER_HUB_ALIASES};
+use crate::k8s::PodImage;
+use crate::utils::Pattern;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// An allow-list of registries that container images may be pulled from
+///
+/// Each entry is either a registry host (`registry.k8s.io`), a registry path
+/// prefix (`ghcr.io/ourorg`) or a glob over either (`*.dkr.ecr.*.amazonaws.com`).
+/// Docker Hub entries are normalized like image references, so `index.docker.io`
+/// allows `docker.io` and `docker.io/library` allows official images such as `nginx`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct RegistryPolicy {
+    /// Registries, registry path prefixes or globs that are allowed
+    #[serde(default)]
+    pub allow: Vec<Pattern>,
+}
+
+impl RegistryPolicy {
+    /// Create a policy from a list of allowed registries
+    ///
+    /// # Arguments
+    ///
+    /// * `allow` - Registries, registry path prefixes or globs that are allowed
+    ///
+    /// # Returns
+    ///
+    /// * `RegistryPolicy` - The policy
+    pub fn new(allow: impl IntoIterator<Item = Pattern>) -> Self {
+        Self {
+            allow: allow.into_iter().collect(),
+        }
+    }
+
+    /// Load a policy from a YAML (or JSON) file
+    ///
+    /// The file contains an `allow` list, e.g.:
+    ///
+    /// ```yaml
+    /// allow:
+    ///   - ghcr.io/ourorg
+    ///   - registry.k8s.io
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the policy file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<RegistryPolicy>` - The parsed policy or an error
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy file {}", path.display()))?;
+        serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse policy file {}", path.display()))
+    }
+
+    /// Add more allowed registries to the policy
+    ///
+    /// # Arguments
+    ///
+    /// * `allow` - Additional registries, registry path prefixes or globs
+    pub fn extend(&mut self, allow: impl IntoIterator<Item = Pattern>) {
+        self.allow.extend(allow);
+    }
+
+    /// Check if the policy has no allowed registries at all
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty()
+    }
+
+    /// Check if an image is pulled from an allowed registry
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The image to check
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if any allow entry matches the image's registry or repository path
+    pub fn allows(&self, image: &PodImage) -> bool {
+        let full_name = format!("{}/{}", image.registry, image.image_name);
+        let reference = ImageReference::lossy(&full_name).normalized();
+        let mut names = vec![
+            format!("{}/{}", reference.registry, reference.repository),
+            full_name,
+        ];
+        // Official images live in the `library` namespace, e.g. docker.io/library/nginx
+        if reference.is_official_library {
+            names.push(format!(
+                "{}/library/{}",
+                reference.registry, reference.repository
+            ));
+        }
+        let registries = [image.registry.as_str(), reference.registry.as_str()];
+
+        self.allow.iter().any(|rule| {
+            if rule.is_glob() {
+                return registries.iter().any(|registry| rule.matches(registry))
+                    || names.iter().any(|name| rule.matches(name));
+            }
+            let rule = normalize_rule(rule.as_str());
+            registries.contains(&rule.as_str())
+                || names
+                    .iter()
+                    .any(|name| *name == rule || name.starts_with(&format!("{}/", rule)))
+        })
+    }
+
+    /// Collect every image that is pulled from a registry outside the allow-list
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - The images to check
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<PodImage>` - The violating images, in their original order
+    pub fn violations(&self, images: &[PodImage]) -> Vec<PodImage> {
+        images
+            .iter()
+            .filter(|image| !self.allows(image))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Normalize an exact allow entry the way image references are normalized
+///
+/// # Arguments
+///
+/// * `rule` - A registry host or registry path prefix
+///
+/// # Returns
+///
+/// * `String` - The entry with Docker Hub aliases replaced by `docker.io` and library
+///   images by their familiar name, e.g. `docker.io/nginx` for `index.docker.io/library/nginx`
+fn normalize_rule(rule: &str) -> String {
+    let rule = rule.trim_end_matches('/');
+    if !rule.contains('/') {
+        let host = rule.to_ascii_lowercase();
+        return if DOCKER_HUB_ALIASES.contains(&host.as_str()) {
+            DEFAULT_REGISTRY.to_string()
+        } else {
+            host
+        };
+    }
+
+    let reference = ImageReference::lossy(rule).normalized();
+    format!("{}/{}", reference.registry, reference.repository)
+}
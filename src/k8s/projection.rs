@@ -0,0 +1,245 @@
+use k8s_openapi::api::core::v1::{
+    Container, ContainerState, ContainerStatus, EphemeralContainer, Pod, PodSpec, PodStatus, Probe,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+use kube::Resource;
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+/// A pod or workload read with only the fields Kelper reports on
+///
+/// This reduces client-side memory, not the payload: objects whose containers
+/// are read still cross the wire in full (gzip-compressed), but reading them into
+/// this reduced shape skips managed fields, env vars, volumes and everything else
+/// Kelper never looks at, and only the reduced objects are kept in memory. Where
+/// only names, labels or owners are read, objects are listed as metadata with
+/// [`K8sClient::list_metadata_pages`](crate::k8s::K8sClient::list_metadata_pages) instead.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct Projected<K> {
+    /// Name, namespace, labels, annotations and owners of the object
+    #[serde(deserialize_with = "deserialize_metadata")]
+    metadata: ObjectMeta,
+    /// Containers of a pod, or the pod template of a workload
+    #[serde(default)]
+    spec: Option<ProjectedSpec>,
+    /// Container statuses of a pod
+    #[serde(default)]
+    status: Option<ProjectedStatus>,
+    /// The kind of object this is a projection of
+    #[serde(skip)]
+    kind: PhantomData<K>,
+}
+
+/// The parts of a pod spec, or of a workload spec wrapping one, that declare containers
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectedSpec {
+    containers: Option<Vec<ProjectedContainer>>,
+    init_containers: Option<Vec<ProjectedContainer>>,
+    ephemeral_containers: Option<Vec<ProjectedContainer>>,
+    node_name: Option<String>,
+    /// Pod template of Deployments, StatefulSets, DaemonSets, ReplicaSets and Jobs
+    template: Option<Box<ProjectedTemplate>>,
+    /// Job template of CronJobs, itself holding a pod template
+    job_template: Option<Box<ProjectedTemplate>>,
+}
+
+/// A pod or job template
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ProjectedTemplate {
+    spec: Option<ProjectedSpec>,
+}
+
+/// The name, image and probes of a container
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectedContainer {
+    name: String,
+    image: Option<String>,
+    liveness_probe: Option<Probe>,
+    readiness_probe: Option<Probe>,
+    startup_probe: Option<Probe>,
+    /// Set to `Always` on init containers running as sidecars
+    restart_policy: Option<String>,
+}
+
+/// The container statuses of a pod
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectedStatus {
+    container_statuses: Option<Vec<ProjectedContainerStatus>>,
+    init_container_statuses: Option<Vec<ProjectedContainerStatus>>,
+    ephemeral_container_statuses: Option<Vec<ProjectedContainerStatus>>,
+}
+
+/// The image a container runs, the digest it resolved to and its current and last state
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectedContainerStatus {
+    name: String,
+    #[serde(default)]
+    image: String,
+    #[serde(default, rename = "imageID")]
+    image_id: String,
+    #[serde(default)]
+    ready: bool,
+    #[serde(default)]
+    restart_count: i32,
+    state: Option<ContainerState>,
+    last_state: Option<ContainerState>,
+}
+
+/// The metadata fields used to identify an object and its workload
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProjectedMetadata {
+    name: Option<String>,
+    namespace: Option<String>,
+    labels: Option<BTreeMap<String, String>>,
+    annotations: Option<BTreeMap<String, String>>,
+    owner_references: Option<Vec<OwnerReference>>,
+}
+
+/// Read only the name, namespace, labels, annotations and owners of an object's metadata
+fn deserialize_metadata<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<ObjectMeta, D::Error> {
+    let metadata = ProjectedMetadata::deserialize(deserializer)?;
+    Ok(ObjectMeta {
+        name: metadata.name,
+        namespace: metadata.namespace,
+        labels: metadata.labels,
+        annotations: metadata.annotations,
+        owner_references: metadata.owner_references,
+        ..Default::default()
+    })
+}
+
+impl<K: Resource<DynamicType = ()>> Resource for Projected<K> {
+    type DynamicType = ();
+    type Scope = K::Scope;
+
+    fn kind(_: &()) -> Cow<'_, str> {
+        K::kind(&())
+    }
+
+    fn group(_: &()) -> Cow<'_, str> {
+        K::group(&())
+    }
+
+    fn version(_: &()) -> Cow<'_, str> {
+        K::version(&())
+    }
+
+    fn plural(_: &()) -> Cow<'_, str> {
+        K::plural(&())
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+}
+
+impl<K> Projected<K> {
+    /// Get the metadata kept for the object
+    pub(crate) fn metadata(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    /// Get the pod spec declared by a workload's pod template, or by a CronJob's job template
+    pub(crate) fn template_spec(&self) -> Option<PodSpec> {
+        let spec = self.spec.as_ref()?;
+        let template = match &spec.job_template {
+            Some(job_template) => job_template.spec.as_ref()?.template.as_ref()?,
+            None => spec.template.as_ref()?,
+        };
+        template.spec.clone().map(PodSpec::from)
+    }
+}
+
+impl Projected<Pod> {
+    /// Convert the projection into a pod holding only the projected fields
+    pub(crate) fn into_pod(self) -> Pod {
+        Pod {
+            metadata: self.metadata,
+            spec: self.spec.map(PodSpec::from),
+            status: self.status.map(PodStatus::from),
+        }
+    }
+}
+
+impl From<ProjectedSpec> for PodSpec {
+    fn from(spec: ProjectedSpec) -> Self {
+        PodSpec {
+            containers: spec
+                .containers
+                .unwrap_or_default()
+                .into_iter()
+                .map(Container::from)
+                .collect(),
+            init_containers: spec
+                .init_containers
+                .map(|containers| containers.into_iter().map(Container::from).collect()),
+            ephemeral_containers: spec.ephemeral_containers.map(|containers| {
+                containers
+                    .into_iter()
+                    .map(|container| EphemeralContainer {
+                        name: container.name,
+                        image: container.image,
+                        ..Default::default()
+                    })
+                    .collect()
+            }),
+            node_name: spec.node_name,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ProjectedContainer> for Container {
+    fn from(container: ProjectedContainer) -> Self {
+        Container {
+            name: container.name,
+            image: container.image,
+            liveness_probe: container.liveness_probe,
+            readiness_probe: container.readiness_probe,
+            startup_probe: container.startup_probe,
+            restart_policy: container.restart_policy,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ProjectedStatus> for PodStatus {
+    fn from(status: ProjectedStatus) -> Self {
+        let statuses = |statuses: Option<Vec<ProjectedContainerStatus>>| {
+            statuses.map(|statuses| {
+                statuses
+                    .into_iter()
+                    .map(|status| ContainerStatus {
+                        name: status.name,
+                        image: status.image,
+                        image_id: status.image_id,
+                        ready: status.ready,
+                        restart_count: status.restart_count,
+                        state: status.state,
+                        last_state: status.last_state,
+                        ..Default::default()
+                    })
+                    .collect()
+            })
+        };
+        PodStatus {
+            container_statuses: statuses(status.container_statuses),
+            init_container_statuses: statuses(status.init_container_statuses),
+            ephemeral_container_statuses: statuses(status.ephemeral_container_statuses),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use crate::image::ProviderKind;
+use crate::k8s::{ContainerType, PodImage};
+use crate::utils::Pattern;
+use clap::ValueEnum;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::ListParams;
+use regex::Regex;
+use std::fmt;
+
+/// Which kinds of containers to include when listing images
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum ContainerTypeFilter {
+    /// Only init containers
+    Init,
+    /// Only regular (app and sidecar) containers
+    Regular,
+    /// Only ephemeral (debug) containers
+    Ephemeral,
+    /// Every container regardless of its type
+    #[default]
+    All,
+}
+
+impl fmt::Display for ContainerTypeFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerTypeFilter::Init => write!(f, "init"),
+            ContainerTypeFilter::Regular => write!(f, "regular"),
+            ContainerTypeFilter::Ephemeral => write!(f, "ephemeral"),
+            ContainerTypeFilter::All => write!(f, "all"),
+        }
+    }
+}
+
+impl ContainerTypeFilter {
+    /// Check if a container of the given type passes this filter
+    ///
+    /// # Arguments
+    ///
+    /// * `container_type` - The type of the container to check
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the container should be included
+    pub fn matches(&self, container_type: ContainerType) -> bool {
+        match self {
+            ContainerTypeFilter::Init => container_type == ContainerType::Init,
+            ContainerTypeFilter::Regular => container_type == ContainerType::Regular,
+            ContainerTypeFilter::Ephemeral => container_type == ContainerType::Ephemeral,
+            ContainerTypeFilter::All => true,
+        }
+    }
+}
+
+/// Criteria used to select pod images from the cluster
+#[derive(Debug, Clone)]
+pub struct ImageQuery {
+    /// The namespace to search in (ignored when `all_namespaces` is set)
+    pub namespace: String,
+    /// Whether to search in all namespaces
+    pub all_namespaces: bool,
+    /// Only include pods scheduled on this node
+    pub node: Option<String>,
+    /// Only include pods whose name matches this exact name or glob (e.g. `api-*`)
+    pub pod: Option<Pattern>,
+    /// Only include pods whose name matches this regular expression
+    pub pod_regex: Option<Regex>,
+    /// Kubernetes label selector, e.g. `app=payments,tier=backend`
+    pub label_selector: Option<String>,
+    /// Raw Kubernetes field selector, combined with the node and pod filters
+    pub field_selector: Option<String>,
+    /// Only include images whose name matches this exact name or glob (e.g. `linkerd/*`)
+    pub image: Option<Pattern>,
+    /// Only include images whose tag matches this exact tag or glob (e.g. `v1.*`)
+    pub tag: Option<Pattern>,
+    /// Only include images pulled from one of these registries (exact names or globs)
+    pub registries: Vec<Pattern>,
+    /// Exclude images pulled from any of these registries (exact names or globs)
+    pub exclude_registries: Vec<Pattern>,
+    /// Only include images hosted by one of these providers
+    pub providers: Vec<ProviderKind>,
+    /// Only include containers of this type
+    pub container_type: ContainerTypeFilter,
+}
+
+impl Default for ImageQuery {
+    fn default() -> Self {
+        Self {
+            namespace: "default".to_string(),
+            all_namespaces: false,
+            node: None,
+            pod: None,
+            pod_regex: None,
+            label_selector: None,
+            field_selector: None,
+            image: None,
+            tag: None,
+            registries: Vec::new(),
+            exclude_registries: Vec::new(),
+            providers: Vec::new(),
+            container_type: ContainerTypeFilter::All,
+        }
+    }
+}
+
+impl ImageQuery {
+    /// Build list parameters for the pod query
+    ///
+    /// The node and pod filters become field selectors and are combined with any
+    /// raw field selector; the label selector is passed through unchanged.
+    ///
+    /// # Returns
+    ///
+    /// * `ListParams` - The parameters to list pods with
+    pub fn list_params(&self) -> ListParams {
+        let mut field_selectors = Vec::new();
+
+        if let Some(node) = &self.node {
+            field_selectors.push(format!("spec.nodeName={}", node));
+        }
+
+        // Globs cannot be expressed as field selectors and are matched client-side
+        if let Some(name) = self.pod.as_ref().filter(|pod| !pod.is_glob()) {
+            field_selectors.push(format!("metadata.name={}", name));
+        }
+
+        if let Some(fields) = &self.field_selector {
+            field_selectors.push(fields.clone());
+        }
+
+        let list_params = ListParams::default().fields(&field_selectors.join(","));
+        match &self.label_selector {
+            Some(labels) => list_params.labels(labels),
+            None => list_params,
+        }
+    }
+
+    /// Check if a pod matches the pod-level criteria
+    ///
+    /// # Arguments
+    ///
+    /// * `pod` - The pod to check
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the pod's images should be considered
+    pub fn matches_pod(&self, pod: &Pod) -> bool {
+        let name = pod.metadata.name.as_deref().unwrap_or_default();
+        if !self.matches_pod_name(name) {
+            return false;
+        }
+
+        if let Some(node) = &self.node {
+            if pod.spec.as_ref().and_then(|s| s.node_name.as_deref()) != Some(node.as_str()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Check if a pod name matches the pod name pattern and regex
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the pod
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the name matches every pod name filter
+    pub fn matches_pod_name(&self, name: &str) -> bool {
+        self.pod
+            .as_ref()
+            .is_none_or(|pattern| pattern.matches(name))
+            && self
+                .pod_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(name))
+    }
+
+    /// Check if an image extracted from a pod matches the image-level criteria
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The image to check
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the image should be included in the results
+    pub fn matches_image(&self, image: &PodImage) -> bool {
+        if !self.container_type.matches(image.container_type) {
+            return false;
+        }
+
+        if let Some(pattern) = &self.image {
+            let full_name = format!("{}/{}", image.registry, image.image_name);
+            if !pattern.matches(&image.image_name) && !pattern.matches(&full_name) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.tag {
+            // Match against the tag alone, without any pinned digest
+            let tag = image.image_version.split('@').next().unwrap_or_default();
+            if !pattern.matches(tag) {
+                return false;
+            }
+        }
+
+        if !self.registries.is_empty()
+            && !self
+                .registries
+                .iter()
+                .any(|pattern| pattern.matches(&image.registry))
+        {
+            return false;
+        }
+
+        if self
+            .exclude_registries
+            .iter()
+            .any(|pattern| pattern.matches(&image.registry))
+        {
+            return false;
+        }
+
+        if !self.providers.is_empty() && !self.providers.contains(&image.provider) {
+            return false;
+        }
+
+        true
+    }
+}
@@ -0,0 +1,553 @@
+use crate::image::{ProviderKind, RegistryProvider, RegistryResolver};
+use crate::k8s::{spec_containers, K8sClient, K8sError, Projected};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use futures::{stream, StreamExt, TryStreamExt};
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::{CronJob, Job};
+use k8s_openapi::api::core::v1::{Pod, PodSpec, ReplicationController};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::{ListParams, PartialObjectMeta};
+use kube::{Api, Resource};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use tracing::{debug, info, instrument};
+
+/// Where to look for container images when building the registry inventory
+#[derive(Debug, Clone, Copy, Default, ValueEnum, PartialEq, Eq)]
+pub enum RegistrySource {
+    /// Running pods, including containers injected by admission webhooks
+    Pods,
+    /// Pod templates of Deployments, StatefulSets, DaemonSets, ReplicaSets, Jobs, CronJobs and ReplicationControllers, plus pods they do not manage
+    #[default]
+    Workloads,
+    /// Both workload templates and running pods
+    All,
+}
+
+impl fmt::Display for RegistrySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistrySource::Pods => write!(f, "pods"),
+            RegistrySource::Workloads => write!(f, "workloads"),
+            RegistrySource::All => write!(f, "all"),
+        }
+    }
+}
+
+/// Identifies a workload (or pod) that declares containers
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WorkloadRef {
+    /// Kubernetes kind, e.g. `Deployment` or `Pod`
+    pub kind: String,
+    /// Namespace of the workload
+    pub namespace: String,
+    /// Name of the workload
+    pub name: String,
+}
+
+impl WorkloadRef {
+    /// Build a workload reference from an object's kind and metadata
+    fn from_meta(kind: &str, meta: &ObjectMeta) -> Self {
+        Self {
+            kind: kind.to_string(),
+            namespace: meta.namespace.clone().unwrap_or_default(),
+            name: meta.name.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// How a single registry is used across workloads
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RegistryUsage {
+    /// Registry host name
+    pub registry: String,
+    /// Service hosting the registry
+    pub provider: ProviderKind,
+    /// Number of distinct workloads with at least one container from this registry
+    pub workloads: usize,
+    /// Number of containers pulling from this registry
+    pub containers: usize,
+    /// Sorted list of workload kinds using this registry
+    pub kinds: Vec<String>,
+    /// Sorted list of registries this registry mirrors, or just the registry itself
+    pub upstreams: Vec<String>,
+}
+
+/// Aggregate the registries used by a set of workloads
+///
+/// # Arguments
+///
+/// * `workloads` - Each workload together with the pod spec it runs
+///
+/// # Returns
+///
+/// * `Vec<RegistryUsage>` - Usage per registry, sorted by registry name
+pub fn summarize_registry_usage<'a>(
+    workloads: impl IntoIterator<Item = (WorkloadRef, &'a PodSpec)>,
+) -> Vec<RegistryUsage> {
+    summarize_registry_usage_with(workloads, &RegistryResolver::default())
+}
+
+/// Aggregate the registries used by a set of workloads using a registry resolver
+///
+/// # Arguments
+///
+/// * `workloads` - Each workload together with the pod spec it runs
+/// * `resolver` - Decides which registry each image is reported under
+///
+/// # Returns
+///
+/// * `Vec<RegistryUsage>` - Usage per registry, sorted by registry name
+pub fn summarize_registry_usage_with<'a>(
+    workloads: impl IntoIterator<Item = (WorkloadRef, &'a PodSpec)>,
+    resolver: &RegistryResolver,
+) -> Vec<RegistryUsage> {
+    let mut tally = RegistryTally::default();
+    for (workload, spec) in workloads {
+        tally.add(&workload, spec, resolver);
+    }
+    tally.into_usage()
+}
+
+/// Registry usage counted so far, so workloads can be dropped once they are added
+#[derive(Default)]
+struct RegistryTally {
+    registries: BTreeMap<String, RegistryAccumulator>,
+}
+
+/// How a single registry is used by the workloads counted so far
+#[derive(Default)]
+struct RegistryAccumulator {
+    workloads: HashSet<WorkloadRef>,
+    containers: usize,
+    kinds: BTreeSet<String>,
+    upstreams: BTreeSet<String>,
+}
+
+impl RegistryTally {
+    /// Count the containers of a workload's pod spec
+    fn add(&mut self, workload: &WorkloadRef, spec: &PodSpec, resolver: &RegistryResolver) {
+        for (_, _, image) in spec_containers(spec) {
+            let Some(image) = image else { continue };
+            let reference = resolver.resolve(image);
+            let upstream = resolver.upstream(&reference);
+            let entry = self.registries.entry(reference.registry).or_default();
+            entry.upstreams.insert(upstream);
+            entry.containers += 1;
+            entry.kinds.insert(workload.kind.clone());
+            entry.workloads.insert(workload.clone());
+        }
+    }
+
+    /// Add the usage counted by another tally
+    fn merge(&mut self, other: RegistryTally) {
+        for (registry, usage) in other.registries {
+            let entry = self.registries.entry(registry).or_default();
+            entry.workloads.extend(usage.workloads);
+            entry.containers += usage.containers;
+            entry.kinds.extend(usage.kinds);
+            entry.upstreams.extend(usage.upstreams);
+        }
+    }
+
+    /// Get the usage per registry, sorted by registry name
+    fn into_usage(self) -> Vec<RegistryUsage> {
+        self.registries
+            .into_iter()
+            .map(|(registry, usage)| RegistryUsage {
+                provider: RegistryProvider::classify(&registry, "").kind,
+                registry,
+                workloads: usage.workloads.len(),
+                containers: usage.containers,
+                kinds: usage.kinds.into_iter().collect(),
+                upstreams: usage.upstreams.into_iter().collect(),
+            })
+            .collect()
+    }
+}
+
+/// Check if an object is owned by a controller of the given kind
+pub(crate) fn is_owned_by(meta: &ObjectMeta, kind: &str) -> bool {
+    meta.owner_references
+        .as_ref()
+        .is_some_and(|owners| owners.iter().any(|owner| owner.kind == kind))
+}
+
+/// Number of objects fetched at the same time after listing their metadata
+const GET_CONCURRENCY: usize = 8;
+
+/// Kinds of controllers whose pod templates are read for the workloads source
+///
+/// Pods of Deployments and CronJobs are owned by their ReplicaSet or Job.
+const TEMPLATE_OWNER_KINDS: [&str; 6] = [
+    "ReplicaSet",
+    "StatefulSet",
+    "DaemonSet",
+    "Job",
+    "CronJob",
+    "ReplicationController",
+];
+
+/// Check if a pod is not covered by the pod template of a workload
+///
+/// Bare pods, static pods mirrored by the kubelet (owned by their `Node`) and
+/// pods of controllers such as operators are workloads of their own.
+fn is_standalone_pod(meta: &ObjectMeta) -> bool {
+    let controller = meta
+        .owner_references
+        .iter()
+        .flatten()
+        .find(|owner| owner.controller.unwrap_or_default());
+    !controller.is_some_and(|owner| TEMPLATE_OWNER_KINDS.contains(&owner.kind.as_str()))
+}
+
+impl K8sClient {
+    /// Get registry usage across workloads and/or pods
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to search in
+    /// * `all_namespaces` - Whether to search in all namespaces
+    /// * `source` - Whether to read workload templates, running pods, or both
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<RegistryUsage>>` - Usage per registry sorted by name, empty when nothing
+    ///   runs in the namespace, or an error
+    #[instrument(skip(self), fields(
+        namespace = %namespace,
+        all_namespaces = %all_namespaces,
+        source = %source
+    ))]
+    pub async fn get_registry_usage(
+        &self,
+        namespace: &str,
+        all_namespaces: bool,
+        source: RegistrySource,
+    ) -> Result<Vec<RegistryUsage>> {
+        debug!("Fetching registry usage");
+
+        if !all_namespaces && !self.namespace_exists(namespace).await? {
+            let resource = format!("Namespace {} not found", namespace);
+            return Err(K8sError::ResourceNotFound(resource).into());
+        }
+
+        let include_workloads = matches!(source, RegistrySource::Workloads | RegistrySource::All);
+        let include_pods = matches!(source, RegistrySource::Pods | RegistrySource::All);
+
+        // Every kind is listed concurrently and counted one page at a time.
+        // ReplicaSets managed by a Deployment and Jobs created by a CronJob are
+        // already covered by the template of their owner.
+        let workloads = include_workloads.then_some((namespace, all_namespaces));
+        let (
+            deployments,
+            stateful_sets,
+            daemon_sets,
+            replica_sets,
+            jobs,
+            cron_jobs,
+            replication_controllers,
+            pods,
+        ) = tokio::try_join!(
+            self.tally_templates::<Deployment>(workloads, None),
+            self.tally_templates::<StatefulSet>(workloads, None),
+            self.tally_templates::<DaemonSet>(workloads, None),
+            self.tally_templates::<ReplicaSet>(workloads, Some("Deployment")),
+            self.tally_templates::<Job>(workloads, Some("CronJob")),
+            self.tally_templates::<CronJob>(workloads, None),
+            self.tally_templates::<ReplicationController>(workloads, None),
+            self.tally_pods(namespace, all_namespaces, include_pods),
+        )?;
+
+        let mut tally = deployments;
+        for other in [
+            stateful_sets,
+            daemon_sets,
+            replica_sets,
+            jobs,
+            cron_jobs,
+            replication_controllers,
+            pods,
+        ] {
+            tally.merge(other);
+        }
+
+        let usage = tally.into_usage();
+        info!(
+            total_registries = usage.len(),
+            "Successfully retrieved registry usage"
+        );
+        Ok(usage)
+    }
+
+    /// Count the registries used by the pod templates of every workload of a kind
+    ///
+    /// When owned workloads are skipped, only their metadata is listed and the
+    /// few workloads without such an owner are then fetched one by one.
+    ///
+    /// # Arguments
+    ///
+    /// * `scope` - The namespace and whether to list all namespaces, or `None` to count nothing
+    /// * `skip_owned_by` - Skip workloads owned by a controller of this kind
+    ///
+    /// # Returns
+    ///
+    /// * `Result<RegistryTally>` - The registries used by the listed workloads, or an error
+    async fn tally_templates<K>(
+        &self,
+        scope: Option<(&str, bool)>,
+        skip_owned_by: Option<&str>,
+    ) -> Result<RegistryTally>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+            + Clone
+            + DeserializeOwned
+            + fmt::Debug,
+    {
+        let mut tally = RegistryTally::default();
+        let Some((namespace, all_namespaces)) = scope else {
+            return Ok(tally);
+        };
+
+        let kind = K::kind(&());
+        if let Some(owner) = skip_owned_by {
+            let unowned = self
+                .list_namespaced_names::<K>(namespace, all_namespaces, |metadata| {
+                    !is_owned_by(metadata, owner)
+                })
+                .await?;
+            debug!(kind = %kind, count = unowned.len(), "Fetching workloads without an owner");
+            for workload in self.get_projected::<K>(unowned).await? {
+                if let Some(spec) = workload.template_spec() {
+                    let workload = WorkloadRef::from_meta(&kind, workload.metadata());
+                    tally.add(&workload, &spec, &self.resolver);
+                }
+            }
+            return Ok(tally);
+        }
+
+        let listed = self
+            .for_each_namespaced_page::<Projected<K>>(
+                namespace,
+                all_namespaces,
+                &ListParams::default(),
+                |page| {
+                    for workload in page {
+                        if let Some(spec) = workload.template_spec() {
+                            let workload = WorkloadRef::from_meta(&kind, workload.metadata());
+                            tally.add(&workload, &spec, &self.resolver);
+                        }
+                    }
+                    Ok(())
+                },
+            )
+            .await?;
+
+        debug!(kind = %kind, count = listed, "Counted workload templates");
+        Ok(tally)
+    }
+
+    /// Count the registries used by running pods
+    ///
+    /// Pods not covered by the template of a workload are always counted, as
+    /// workloads of their own; every pod is counted when pods are a source.
+    /// When only standalone pods are counted, the metadata of every pod is
+    /// listed and just the standalone ones are fetched in full.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to list pods from
+    /// * `all_namespaces` - Whether to list pods from all namespaces
+    /// * `include_pods` - Whether every pod is counted rather than only standalone ones
+    ///
+    /// # Returns
+    ///
+    /// * `Result<RegistryTally>` - The registries used by the counted pods, or an error
+    async fn tally_pods(
+        &self,
+        namespace: &str,
+        all_namespaces: bool,
+        include_pods: bool,
+    ) -> Result<RegistryTally> {
+        let mut tally = RegistryTally::default();
+        let mut add = |pod: Projected<Pod>| {
+            let pod = pod.into_pod();
+            if let Some(spec) = &pod.spec {
+                let workload = WorkloadRef::from_meta("Pod", &pod.metadata);
+                tally.add(&workload, spec, &self.resolver);
+            }
+        };
+
+        if !include_pods {
+            let standalone = self
+                .list_namespaced_names::<Pod>(namespace, all_namespaces, is_standalone_pod)
+                .await?;
+            debug!(count = standalone.len(), "Fetching standalone pods");
+            self.get_projected::<Pod>(standalone)
+                .await?
+                .into_iter()
+                .for_each(add);
+            return Ok(tally);
+        }
+
+        let listed = self
+            .for_each_namespaced_page::<Projected<Pod>>(
+                namespace,
+                all_namespaces,
+                &ListParams::default(),
+                |page| {
+                    page.into_iter().for_each(&mut add);
+                    Ok(())
+                },
+            )
+            .await?;
+
+        debug!(count = listed, "Counted pods");
+        Ok(tally)
+    }
+
+    /// List the namespace and name of the objects of a kind whose metadata matches a filter
+    ///
+    /// Only the metadata of the objects is listed, page by page.
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to list from
+    /// * `all_namespaces` - Whether to list from all namespaces
+    /// * `filter` - Whether an object is kept, given its metadata
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<(String, String)>>` - The namespace and name of every kept object, or an error
+    async fn list_namespaced_names<K>(
+        &self,
+        namespace: &str,
+        all_namespaces: bool,
+        filter: impl Fn(&ObjectMeta) -> bool,
+    ) -> Result<Vec<(String, String)>>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+            + Clone
+            + DeserializeOwned
+            + fmt::Debug,
+    {
+        let mut names = Vec::new();
+        self.for_each_namespaced_metadata_page::<K>(
+            namespace,
+            all_namespaces,
+            &ListParams::default(),
+            |page| {
+                names.extend(
+                    page.into_iter()
+                        .filter(|object| filter(&object.metadata))
+                        .filter_map(|object| {
+                            Some((object.metadata.namespace?, object.metadata.name?))
+                        }),
+                );
+                Ok(())
+            },
+        )
+        .await?;
+        Ok(names)
+    }
+
+    /// Fetch named objects of a kind, a few at a time
+    ///
+    /// Objects deleted since they were listed are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The namespace and name of every object to fetch
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Projected<K>>>` - The objects still present, or the first request error
+    async fn get_projected<K>(&self, names: Vec<(String, String)>) -> Result<Vec<Projected<K>>>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()> + Clone + fmt::Debug,
+    {
+        let objects: Vec<Option<Projected<K>>> = stream::iter(names)
+            .map(|(namespace, name)| async move {
+                let api: Api<Projected<K>> = Api::namespaced(self.client.clone(), &namespace);
+                api.get_opt(&name).await.with_context(|| {
+                    format!("Failed to get {} {}/{}", K::kind(&()), namespace, name)
+                })
+            })
+            .buffered(GET_CONCURRENCY)
+            .try_collect()
+            .await?;
+        Ok(objects.into_iter().flatten().collect())
+    }
+
+    /// List every object of a namespaced kind in one or all namespaces, page by page
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to list from
+    /// * `all_namespaces` - Whether to list from all namespaces
+    /// * `list_params` - Selectors applied to the listing
+    /// * `on_page` - Called with the objects of every page as it arrives
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The number of listed objects, or the first listing or callback error
+    pub(crate) async fn for_each_namespaced_page<K>(
+        &self,
+        namespace: &str,
+        all_namespaces: bool,
+        list_params: &ListParams,
+        on_page: impl FnMut(Vec<K>) -> Result<()>,
+    ) -> Result<usize>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+            + Clone
+            + DeserializeOwned
+            + fmt::Debug,
+    {
+        let api: Api<K> = if all_namespaces {
+            Api::all(self.client.clone())
+        } else {
+            Api::namespaced(self.client.clone(), namespace)
+        };
+
+        self.list_pages(&api, list_params, on_page).await
+    }
+
+    /// List the metadata of every object of a namespaced kind in one or all namespaces, page by page
+    ///
+    /// # Arguments
+    ///
+    /// * `namespace` - The namespace to list from
+    /// * `all_namespaces` - Whether to list from all namespaces
+    /// * `list_params` - Selectors applied to the listing
+    /// * `on_page` - Called with the metadata of the objects of every page as it arrives
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize>` - The number of listed objects, or the first listing or callback error
+    pub(crate) async fn for_each_namespaced_metadata_page<K>(
+        &self,
+        namespace: &str,
+        all_namespaces: bool,
+        list_params: &ListParams,
+        on_page: impl FnMut(Vec<PartialObjectMeta<K>>) -> Result<()>,
+    ) -> Result<usize>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+            + Clone
+            + DeserializeOwned
+            + fmt::Debug,
+    {
+        let api: Api<K> = if all_namespaces {
+            Api::all(self.client.clone())
+        } else {
+            Api::namespaced(self.client.clone(), namespace)
+        };
+
+        self.list_metadata_pages(&api, list_params, on_page).await
+    }
+}
@@ -0,0 +1,114 @@
+use crate::k8s::PodImage;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One tag and digest of an image currently running in the cluster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkewEntry {
+    /// Registry and image name, e.g. `cr.l5d.io/linkerd/proxy`
+    pub image: String,
+    /// Tag running, without any pinned digest
+    pub version: String,
+    /// Digest running, empty when no container of the tag has reported one yet
+    pub digest: String,
+    /// Number of distinct pods running this tag and digest
+    pub pods: usize,
+    /// Number of containers running this tag and digest
+    pub containers: usize,
+    /// Sorted list of namespaces running this tag and digest
+    pub namespaces: Vec<String>,
+    /// Number of distinct tag and digest combinations running for the image
+    pub variants: usize,
+}
+
+/// List every distinct tag and digest running for each image
+///
+/// Within an image the most widely used version comes first, so stragglers
+/// still running an old version of a shared image end up at the bottom.
+///
+/// Containers whose digest is not known yet, e.g. while their image is being
+/// pulled, count towards the most widely used digest of their tag, so a
+/// rollout in progress is not reported as skew. They only form a variant of
+/// their own when no container of the tag has reported a digest.
+///
+/// # Arguments
+///
+/// * `images` - The images to audit
+/// * `skewed_only` - Only report images running more than one tag or digest
+///
+/// # Returns
+///
+/// * `Vec<SkewEntry>` - One entry per image, tag and digest, sorted by image
+pub fn audit_skew(images: &[PodImage], skewed_only: bool) -> Vec<SkewEntry> {
+    #[derive(Default)]
+    struct Accumulator<'a> {
+        pods: BTreeSet<(&'a str, &'a str)>,
+        containers: usize,
+        namespaces: BTreeSet<&'a str>,
+    }
+
+    let mut repositories: BTreeMap<String, BTreeMap<(&str, &str), Accumulator>> = BTreeMap::new();
+    for image in images {
+        let version = image.image_version.split('@').next().unwrap_or_default();
+        let entry = repositories
+            .entry(format!("{}/{}", image.registry, image.image_name))
+            .or_default()
+            .entry((version, image.digest.as_str()))
+            .or_default();
+        entry
+            .pods
+            .insert((image.namespace.as_str(), image.pod_name.as_str()));
+        entry.containers += 1;
+        entry.namespaces.insert(image.namespace.as_str());
+    }
+
+    // Containers without a digest yet join the most widely used digest of their tag
+    for variants in repositories.values_mut() {
+        let pending: Vec<&str> = variants
+            .keys()
+            .filter(|(_, digest)| digest.is_empty())
+            .map(|(version, _)| *version)
+            .collect();
+        for version in pending {
+            let target = variants
+                .iter()
+                .filter(|((v, digest), _)| *v == version && !digest.is_empty())
+                .max_by_key(|(_, usage)| usage.pods.len())
+                .map(|(key, _)| *key);
+            let Some(target) = target else { continue };
+            let usage = variants
+                .remove(&(version, ""))
+                .expect("pending variant was listed");
+            let entry = variants.get_mut(&target).expect("target variant exists");
+            entry.pods.extend(usage.pods);
+            entry.containers += usage.containers;
+            entry.namespaces.extend(usage.namespaces);
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (image, variants) in repositories {
+        if skewed_only && variants.len() < 2 {
+            continue;
+        }
+
+        let count = variants.len();
+        let mut rows: Vec<SkewEntry> = variants
+            .into_iter()
+            .map(|((version, digest), usage)| SkewEntry {
+                image: image.clone(),
+                version: version.to_string(),
+                digest: digest.to_string(),
+                pods: usage.pods.len(),
+                containers: usage.containers,
+                namespaces: usage.namespaces.into_iter().map(String::from).collect(),
+                variants: count,
+            })
+            .collect();
+        rows.sort_by_key(|row| Reverse(row.pods));
+        entries.extend(rows);
+    }
+
+    entries
+}
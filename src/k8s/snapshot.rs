@@ -0,0 +1,164 @@
+use crate::k8s::{ContainerType, ImageChange, PodImage};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+/// The image inventory of a cluster at a point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageSnapshot {
+    /// Name of the cluster the snapshot was taken from
+    pub cluster: String,
+    /// When the snapshot was taken
+    pub taken_at: DateTime<Utc>,
+    /// Namespace the snapshot covers, empty when it covers all namespaces
+    #[serde(default)]
+    pub namespace: String,
+    /// Every container image running when the snapshot was taken
+    pub images: Vec<PodImage>,
+}
+
+/// A container whose images differ between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnapshotChange {
+    /// Whether the container was added, removed or runs different images
+    pub change: ImageChange,
+    /// Kubernetes namespace of the workload
+    pub namespace: String,
+    /// Workload running the container as `Kind/name`
+    pub workload: String,
+    /// Name of the container
+    pub container_name: String,
+    /// Whether the container is an init, regular or ephemeral container
+    pub container_type: ContainerType,
+    /// Sorted images the workload's replicas ran in the first snapshot
+    pub before: Vec<String>,
+    /// Sorted images the workload's replicas run in the second snapshot
+    pub after: Vec<String>,
+}
+
+impl ImageSnapshot {
+    /// Take a snapshot of an image inventory now
+    ///
+    /// # Arguments
+    ///
+    /// * `cluster` - Name of the cluster the images were read from
+    /// * `namespace` - Namespace the images were read from, empty for all namespaces
+    /// * `images` - The images running in the cluster
+    ///
+    /// # Returns
+    ///
+    /// * `ImageSnapshot` - The snapshot, timestamped with the current time
+    pub fn new(cluster: &str, namespace: &str, images: Vec<PodImage>) -> Self {
+        Self {
+            cluster: cluster.to_string(),
+            taken_at: Utc::now(),
+            namespace: namespace.to_string(),
+            images,
+        }
+    }
+
+    /// Load a snapshot saved with [`ImageSnapshot::save`]
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the snapshot file
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ImageSnapshot>` - The parsed snapshot or an error
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read snapshot {}", path.display()))?;
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Failed to parse snapshot {}", path.display()))
+    }
+
+    /// Save the snapshot as JSON
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the file to write
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success or an error if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_json()?)
+            .with_context(|| format!("Failed to write snapshot {}", path.display()))
+    }
+
+    /// Serialize the snapshot as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        let mut json =
+            serde_json::to_string_pretty(self).context("Failed to serialize snapshot")?;
+        json.push('\n');
+        Ok(json)
+    }
+
+    /// Compare this snapshot with a later one, per workload and container
+    ///
+    /// Pods come and go with every rollout, so containers are matched by
+    /// namespace, workload, container type and name, and each side is the set
+    /// of `registry/image:version` references run by the workload's replicas.
+    /// Digests resolved by the runtime are not compared.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - The snapshot to compare against
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<SnapshotChange>` - One row per container that differs, sorted by workload
+    pub fn diff(&self, after: &ImageSnapshot) -> Vec<SnapshotChange> {
+        let before = container_images(&self.images);
+        let after = container_images(&after.images);
+
+        let keys: BTreeSet<&ContainerKey> = before.keys().chain(after.keys()).collect();
+        keys.into_iter()
+            .filter_map(|key| {
+                let (change, before, after) = match (before.get(key), after.get(key)) {
+                    (Some(old), Some(new)) if old == new => return None,
+                    (Some(old), Some(new)) => (ImageChange::Changed, old.clone(), new.clone()),
+                    (Some(old), None) => (ImageChange::Removed, old.clone(), BTreeSet::new()),
+                    (None, Some(new)) => (ImageChange::Added, BTreeSet::new(), new.clone()),
+                    (None, None) => return None,
+                };
+                let (namespace, workload, container_type, container_name) = key.clone();
+
+                Some(SnapshotChange {
+                    change,
+                    namespace,
+                    workload,
+                    container_name,
+                    container_type,
+                    before: before.into_iter().collect(),
+                    after: after.into_iter().collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Identifies a container of a workload across snapshots
+type ContainerKey = (String, String, ContainerType, String);
+
+/// Collect the distinct image references run by each container of each workload
+fn container_images(images: &[PodImage]) -> BTreeMap<ContainerKey, BTreeSet<String>> {
+    let mut containers: BTreeMap<ContainerKey, BTreeSet<String>> = BTreeMap::new();
+    for image in images {
+        let key = (
+            image.namespace.clone(),
+            image.workload.clone(),
+            image.container_type,
+            image.container_name.clone(),
+        );
+        containers.entry(key).or_default().insert(format!(
+            "{}/{}:{}",
+            image.registry, image.image_name, image.image_version
+        ));
+    }
+    containers
+}
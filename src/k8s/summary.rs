@@ -0,0 +1,117 @@
+use crate::k8s::PodImage;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// The field pod images are grouped by in an image summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Registry and image name, e.g. `cr.l5d.io/linkerd/proxy`
+    Image,
+    /// Registry host
+    Registry,
+    /// Pod namespace
+    Namespace,
+    /// Node the pod is scheduled on
+    Node,
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupBy::Image => write!(f, "image"),
+            GroupBy::Registry => write!(f, "registry"),
+            GroupBy::Namespace => write!(f, "namespace"),
+            GroupBy::Node => write!(f, "node"),
+        }
+    }
+}
+
+impl GroupBy {
+    /// Get the group a pod image belongs to
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The image to group
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The group key, `<none>` for pods not scheduled on a node yet
+    pub fn key(&self, image: &PodImage) -> String {
+        match self {
+            GroupBy::Image => format!("{}/{}", image.registry, image.image_name),
+            GroupBy::Registry => image.registry.clone(),
+            GroupBy::Namespace => image.namespace.clone(),
+            GroupBy::Node if image.node_name.is_empty() => "<none>".to_string(),
+            GroupBy::Node => image.node_name.clone(),
+        }
+    }
+}
+
+/// Aggregated usage of the pod images sharing a group key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImageSummary {
+    /// The image, registry, namespace or node the row summarizes
+    pub group: String,
+    /// Number of distinct pods with at least one container in the group
+    pub pods: usize,
+    /// Number of containers in the group
+    pub containers: usize,
+    /// Number of distinct tags running in the group
+    pub tags: usize,
+    /// Number of distinct digests running in the group
+    pub digests: usize,
+    /// Sorted list of the distinct tags running in the group
+    pub versions: Vec<String>,
+}
+
+/// Aggregate pod images into counts of pods, containers, tags and digests per group
+///
+/// Tags are compared without any pinned digest, and containers whose digest is
+/// not known yet do not count towards the digests.
+///
+/// # Arguments
+///
+/// * `images` - The images to summarize
+/// * `group_by` - The field to group the images by
+///
+/// # Returns
+///
+/// * `Vec<ImageSummary>` - One row per group, sorted by group key
+pub fn summarize_images(images: &[PodImage], group_by: GroupBy) -> Vec<ImageSummary> {
+    #[derive(Default)]
+    struct Accumulator<'a> {
+        pods: BTreeSet<(&'a str, &'a str)>,
+        containers: usize,
+        tags: BTreeSet<&'a str>,
+        digests: BTreeSet<&'a str>,
+    }
+
+    let mut groups: BTreeMap<String, Accumulator> = BTreeMap::new();
+    for image in images {
+        let entry = groups.entry(group_by.key(image)).or_default();
+        entry
+            .pods
+            .insert((image.namespace.as_str(), image.pod_name.as_str()));
+        entry.containers += 1;
+        entry
+            .tags
+            .insert(image.image_version.split('@').next().unwrap_or_default());
+        if !image.digest.is_empty() {
+            entry.digests.insert(image.digest.as_str());
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(group, usage)| ImageSummary {
+            group,
+            pods: usage.pods.len(),
+            containers: usage.containers,
+            tags: usage.tags.len(),
+            digests: usage.digests.len(),
+            versions: usage.tags.into_iter().map(String::from).collect(),
+        })
+        .collect()
+}
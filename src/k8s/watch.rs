@@ -0,0 +1,230 @@
+use crate::k8s::{process_pod_with, ContainerType, ImageQuery, K8sClient, K8sError, PodImage};
+use anyhow::Result;
+use futures::StreamExt;
+use k8s_openapi::api::core::v1::Pod;
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::Api;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use tracing::{debug, info, instrument, warn};
+
+/// How a container image changed between two observations of a pod
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageChange {
+    /// The container appeared, either in a new pod or as a new container
+    Added,
+    /// The container disappeared, either with its pod or from its spec
+    Removed,
+    /// The container now runs a different registry, image or tag
+    Changed,
+}
+
+impl fmt::Display for ImageChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageChange::Added => write!(f, "added"),
+            ImageChange::Removed => write!(f, "removed"),
+            ImageChange::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// A container image that was added, removed or changed while watching pods
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageEvent {
+    /// What happened to the container image
+    pub change: ImageChange,
+    /// The image after the change, or the removed image
+    #[serde(flatten)]
+    pub image: PodImage,
+    /// The image before the change as `registry/image:version`, empty unless changed
+    pub previous: String,
+}
+
+/// Identifies a container within a pod across observations
+type ContainerKey = (ContainerType, String);
+
+/// Compare two observations of a pod's images
+///
+/// Containers are matched by type and name. Only the registry, image name and
+/// version are compared: a digest appearing once the runtime resolves it is
+/// not reported as a change.
+///
+/// # Arguments
+///
+/// * `previous` - The images seen before, empty for a new pod
+/// * `current` - The images seen now, empty for a deleted pod
+///
+/// # Returns
+///
+/// * `Vec<ImageEvent>` - Removed containers first, then added and changed ones in pod order
+pub fn diff_pod_images(previous: &[PodImage], current: &[PodImage]) -> Vec<ImageEvent> {
+    let key =
+        |image: &PodImage| -> ContainerKey { (image.container_type, image.container_name.clone()) };
+    let before: HashMap<ContainerKey, &PodImage> =
+        previous.iter().map(|image| (key(image), image)).collect();
+    let after: HashMap<ContainerKey, &PodImage> =
+        current.iter().map(|image| (key(image), image)).collect();
+
+    let removed = previous
+        .iter()
+        .filter(|image| !after.contains_key(&key(image)))
+        .map(|image| ImageEvent {
+            change: ImageChange::Removed,
+            image: image.clone(),
+            previous: String::new(),
+        });
+
+    let added_or_changed = current
+        .iter()
+        .filter_map(|image| match before.get(&key(image)) {
+            None => Some(ImageEvent {
+                change: ImageChange::Added,
+                image: image.clone(),
+                previous: String::new(),
+            }),
+            Some(old)
+                if old.registry != image.registry
+                    || old.image_name != image.image_name
+                    || old.image_version != image.image_version =>
+            {
+                Some(ImageEvent {
+                    change: ImageChange::Changed,
+                    image: image.clone(),
+                    previous: format!("{}/{}:{}", old.registry, old.image_name, old.image_version),
+                })
+            }
+            Some(_) => None,
+        });
+
+    removed.chain(added_or_changed).collect()
+}
+
+impl K8sClient {
+    /// Watch the pods matching a query and report every container image change
+    ///
+    /// The images running when the watch starts are reported as added. The
+    /// watch runs until the stream ends or `on_events` returns an error;
+    /// connection errors are logged and retried with a backoff.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The criteria used to select pods and images
+    /// * `on_events` - Called with every non-empty batch of image events
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success once the watch ends, or the first error of `on_events`
+    #[instrument(skip(self, on_events), fields(
+        namespace = %query.namespace,
+        all_namespaces = %query.all_namespaces
+    ))]
+    pub async fn watch_pod_images(
+        &self,
+        query: &ImageQuery,
+        mut on_events: impl FnMut(Vec<ImageEvent>) -> Result<()>,
+    ) -> Result<()> {
+        debug!(?query, "Watching pod images");
+
+        if !query.all_namespaces && !self.namespace_exists(&query.namespace).await? {
+            let resource = format!("Namespace {} not found", query.namespace);
+            return Err(K8sError::ResourceNotFound(resource).into());
+        }
+
+        let list_params = query.list_params();
+        let config = watcher::Config {
+            label_selector: list_params.label_selector,
+            field_selector: list_params.field_selector,
+            ..Default::default()
+        };
+
+        let api: Api<Pod> = self.get_pods_api(&query.namespace, query.all_namespaces, None)?;
+        let mut stream = watcher(api, config).default_backoff().boxed();
+
+        let mut known: BTreeMap<(String, String), Vec<PodImage>> = BTreeMap::new();
+        let mut relisted: Option<BTreeMap<(String, String), Vec<PodImage>>> = None;
+
+        while let Some(event) = stream.next().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    warn!(error = %e, "Pod watch failed, retrying");
+                    continue;
+                }
+            };
+
+            let events = match event {
+                watcher::Event::Init => {
+                    relisted = Some(BTreeMap::new());
+                    Vec::new()
+                }
+                watcher::Event::InitApply(pod) => {
+                    if let Some(relisted) = relisted.as_mut() {
+                        relisted.insert(pod_key(&pod), self.watched_images(&pod, query));
+                    }
+                    Vec::new()
+                }
+                watcher::Event::InitDone => {
+                    let current = relisted.take().unwrap_or_default();
+                    let mut events = Vec::new();
+                    for (key, images) in &known {
+                        if !current.contains_key(key) {
+                            events.extend(diff_pod_images(images, &[]));
+                        }
+                    }
+                    for (key, images) in &current {
+                        let previous = known.get(key).map(Vec::as_slice).unwrap_or_default();
+                        events.extend(diff_pod_images(previous, images));
+                    }
+                    known = current;
+                    info!(
+                        pods = known.len(),
+                        "Finished listing pods, watching for changes"
+                    );
+                    events
+                }
+                watcher::Event::Apply(pod) => {
+                    let images = self.watched_images(&pod, query);
+                    let previous = known.remove(&pod_key(&pod)).unwrap_or_default();
+                    let events = diff_pod_images(&previous, &images);
+                    if !images.is_empty() {
+                        known.insert(pod_key(&pod), images);
+                    }
+                    events
+                }
+                watcher::Event::Delete(pod) => {
+                    let previous = known.remove(&pod_key(&pod)).unwrap_or_default();
+                    diff_pod_images(&previous, &[])
+                }
+            };
+
+            if !events.is_empty() {
+                debug!(events = events.len(), "Observed image changes");
+                on_events(events)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Extract the images of a watched pod that match a query
+    fn watched_images(&self, pod: &Pod, query: &ImageQuery) -> Vec<PodImage> {
+        if !query.matches_pod(pod) {
+            return Vec::new();
+        }
+
+        let mut images = process_pod_with(pod, &self.resolver);
+        images.retain(|image| query.matches_image(image));
+        images
+    }
+}
+
+/// Identify a pod by namespace and name
+fn pod_key(pod: &Pod) -> (String, String) {
+    (
+        pod.metadata.namespace.clone().unwrap_or_default(),
+        pod.metadata.name.clone().unwrap_or_default(),
+    )
+}
@@ -9,14 +9,41 @@ pub use k8s::K8sClient;
 
 // Internal modules
 mod cli;
+mod image;
 mod k8s;
 mod utils;
 
 // Re-export commonly used items
-pub use cli::{Commands, GetImages, LogFormat, OutputFormat};
-pub use k8s::{extract_registry, process_pod, split_image, K8sError, PodImage};
+pub use cli::{
+    AuditReport, CheckPolicy, Commands, ContextSelection, GetImages, LogFormat, OutputFormat,
+    SnapshotAction,
+};
+pub use image::{
+    Digest, ImageReference, ImageReferenceError, MirrorRule, ProviderKind, RegistryConfig,
+    RegistryProvider, RegistryResolver,
+};
+pub use k8s::{
+    audit_skew, audit_tags, describe_probe, detect_drift, diff_pod_images, extract_registry,
+    image_tag_source, kubeconfig_contexts, label_violations, metadata_entries,
+    parse_image_id_digest, pod_drift, pod_health, pod_workload, process_pod, process_pod_with,
+    query_fleet, split_image, summarize_images, summarize_registry_usage,
+    summarize_registry_usage_with, tag_issues, ClientOptions, ClusterFailure, ClusterRecord,
+    ContainerHealth, ContainerType, ContainerTypeFilter, FleetResult, GroupBy, HealthIssue,
+    ImageChange, ImageDrift, ImageEvent, ImageQuery, ImageSnapshot, ImageSummary, K8sError,
+    LabelAudit, LabelIssue, LabelRequirement, LabelTarget, LabelViolation, MetadataEntry,
+    MetadataField, MetadataResource, PodImage, RegistryPolicy, RegistrySource, RegistryUsage,
+    SkewEntry, SnapshotChange, TagAuditEntry, TagIssue, TagSource, WorkloadRef,
+    DEFAULT_CLUSTER_CONCURRENCY, DEFAULT_RESTART_THRESHOLD,
+};
 pub use utils::logging;
-pub use utils::{display_pod_images, display_registries, strip_registry};
+pub use utils::{
+    display_cluster_image_summary, display_cluster_pod_images, display_cluster_registries,
+    display_drift, display_health, display_image_events, display_image_summary,
+    display_label_violations, display_metadata, display_pod_images, display_registries,
+    display_skew, display_snapshot_diff, display_tag_audit, parse_custom_columns,
+    render_image_events, render_pod_images, render_structured, ColumnSpec, JsonPath,
+    JsonPathTemplate, Pattern, PodImagePages,
+};
 
 /// Result type for Kelper operations
 pub type KelperResult<T> = anyhow::Result<T>;
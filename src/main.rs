@@ -1,14 +1,28 @@
 use anyhow::Context;
 use clap::Parser;
 use kelper::{
-    display_pod_images, display_registries, logging, Args, Commands, GetImages, K8sClient,
-    KelperResult,
+    audit_skew, audit_tags, display_cluster_image_summary, display_cluster_pod_images,
+    display_cluster_registries, display_drift, display_health, display_image_events,
+    display_image_summary, display_label_violations, display_metadata, display_pod_images,
+    display_registries, display_skew, display_snapshot_diff, display_tag_audit,
+    kubeconfig_contexts, logging, query_fleet, render_pod_images, summarize_images, Args,
+    AuditReport, CheckPolicy, ClientOptions, ClusterRecord, Commands, GetImages, ImageQuery,
+    ImageSnapshot, K8sClient, KelperResult, LabelRequirement, MetadataField, MetadataResource,
+    OutputFormat, Pattern, PodImage, PodImagePages, RegistryPolicy, SnapshotAction,
 };
+use std::collections::HashSet;
+use std::process::ExitCode;
 use tracing::{debug, info, instrument, warn};
 
+/// Exit code used when a policy check finds violations (errors exit with 1)
+const POLICY_VIOLATION_EXIT_CODE: u8 = 2;
+
+/// Exit code used when some, but not all, clusters of a multi-cluster query failed
+const PARTIAL_FAILURE_EXIT_CODE: u8 = 3;
+
 /// Main entry point for the Kelper application
 #[tokio::main]
-async fn main() -> KelperResult<()> {
+async fn main() -> KelperResult<ExitCode> {
     let args = Args::parse();
 
     // Initialize logging with the specified format
@@ -17,97 +31,800 @@ async fn main() -> KelperResult<()> {
 
     debug!("Application started with args: {:?}", args);
 
+    if !args.command.requires_cluster() {
+        let exit_code = process_offline_commands(args).await?;
+        debug!("Application completed successfully");
+        return Ok(exit_code);
+    }
+
+    if args.command.context_selection().is_some() {
+        let exit_code = process_fleet_commands(args).await?;
+        debug!("Application completed successfully");
+        return Ok(exit_code);
+    }
+
     // Create the client with improved error context
-    let client = K8sClient::new()
+    let client = K8sClient::from_options(&args.client_options())
         .await
-        .context("Failed to create Kubernetes client")?;
+        .context("Failed to create Kubernetes client")?
+        .with_resolver(args.registry_resolver()?);
 
     info!("Successfully connected to Kubernetes cluster");
 
-    process_commands(args, client).await?;
+    let exit_code = process_commands(args, client).await?;
 
     debug!("Application completed successfully");
+    Ok(exit_code)
+}
+
+/// Execute the commands that never talk to the cluster of the current context
+///
+/// Diffing two snapshots only reads local files, and diffing a snapshot against
+/// a cluster only connects to the context given with `--against-cluster`.
+#[instrument(level = "debug")]
+async fn process_offline_commands(args: Args) -> KelperResult<ExitCode> {
+    let client_options = args.client_options();
+    let resolver = args.registry_resolver()?;
+
+    match args.command {
+        Commands::Snapshot {
+            action:
+                SnapshotAction::Diff {
+                    before,
+                    after,
+                    against_cluster,
+                    namespace,
+                    all_namespaces,
+                    output,
+                    ..
+                },
+        } => {
+            debug!(
+                before = ?before,
+                after = ?after,
+                against_cluster = ?against_cluster,
+                namespace = %namespace,
+                all_namespaces = %all_namespaces,
+                output = ?output,
+                "Processing snapshot diff command"
+            );
+
+            let before = ImageSnapshot::from_file(&before)?;
+            let after = match (after, against_cluster) {
+                (Some(after), _) => ImageSnapshot::from_file(&after)?,
+                (None, Some(context)) => {
+                    // The cluster and user of the current context do not apply to another context
+                    let options = ClientOptions {
+                        context: Some(context),
+                        cluster: None,
+                        user: None,
+                        ..client_options
+                    };
+                    let client = K8sClient::from_options(&options)
+                        .await
+                        .context("Failed to create Kubernetes client")?
+                        .with_resolver(resolver);
+
+                    let scope = if all_namespaces { "" } else { &namespace };
+                    let query = ImageQuery {
+                        namespace: namespace.clone(),
+                        all_namespaces,
+                        ..Default::default()
+                    };
+                    let pod_images = client
+                        .query_pod_images(&query)
+                        .await
+                        .context("Failed to retrieve pod images")?;
+                    ImageSnapshot::new(client.cluster_name(), scope, pod_images)
+                }
+                (None, None) => {
+                    anyhow::bail!("snapshot diff needs a second snapshot or --against-cluster")
+                }
+            };
+
+            display_snapshot_changes(&before, &after, &output)?;
+        }
+        command => anyhow::bail!("{:?} requires a Kubernetes cluster", command),
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Execute a command against several kubeconfig contexts concurrently
+///
+/// Clusters that cannot be queried are reported on stderr after the rows of
+/// the others; the command only fails outright when every cluster failed.
+#[instrument(level = "debug")]
+async fn process_fleet_commands(args: Args) -> KelperResult<ExitCode> {
+    let options = args.client_options();
+    let resolver = args.registry_resolver()?;
+    let Commands::Get { resource } = &args.command else {
+        anyhow::bail!("{:?} cannot query several clusters", args.command);
+    };
+    let Some(selection) = resource.context_selection() else {
+        anyhow::bail!("{:?} cannot query several clusters", resource);
+    };
+
+    // clap only sees the conflict when --context is given after the subcommand
+    if options.context.is_some() {
+        anyhow::bail!("--context cannot be used with --contexts or --all-contexts");
+    }
+
+    let contexts = if selection.all_contexts {
+        kubeconfig_contexts(options.kubeconfig.as_deref())?
+    } else {
+        selection.contexts.clone()
+    };
+    info!(clusters = contexts.len(), "Querying clusters");
+
+    let query = resource.image_query();
+    let failures = match resource {
+        GetImages::Images {
+            group_by, output, ..
+        } => {
+            debug!(query = ?query, group_by = ?group_by, output = ?output, "Processing get images command across clusters");
+
+            let fleet = query_fleet(
+                &options,
+                &contexts,
+                &resolver,
+                selection.max_concurrency,
+                |client| {
+                    let query = &query;
+                    async move { client.query_pod_images(query).await }
+                },
+            )
+            .await;
+
+            if fleet.records.is_empty()
+                && matches!(output, OutputFormat::Normal | OutputFormat::Wide)
+            {
+                warn!("No pod images found matching your criteria");
+            } else if let Some(group_by) = *group_by {
+                let mut summaries = Vec::new();
+                for cluster in &contexts {
+                    let images: Vec<PodImage> = fleet
+                        .records
+                        .iter()
+                        .filter(|row| &row.cluster == cluster)
+                        .map(|row| row.record.clone())
+                        .collect();
+                    summaries.extend(summarize_images(&images, group_by).into_iter().map(
+                        |record| ClusterRecord {
+                            cluster: cluster.clone(),
+                            record,
+                        },
+                    ));
+                }
+                display_cluster_image_summary(&summaries, group_by, output)
+                    .context("Failed to display image summary")?;
+            } else {
+                display_cluster_pod_images(&fleet.records, output)
+                    .context("Failed to display pod images")?;
+            }
+            fleet.failures
+        }
+        GetImages::Registries { source, output, .. } => {
+            debug!(query = ?query, source = %source, output = ?output, "Processing get registries command across clusters");
+
+            let fleet = query_fleet(
+                &options,
+                &contexts,
+                &resolver,
+                selection.max_concurrency,
+                |client| {
+                    let query = &query;
+                    async move {
+                        client
+                            .get_registry_usage(&query.namespace, query.all_namespaces, *source)
+                            .await
+                    }
+                },
+            )
+            .await;
+
+            if fleet.records.is_empty()
+                && matches!(output, OutputFormat::Normal | OutputFormat::Wide)
+            {
+                warn!("No registries found in the specified namespace(s)");
+            } else {
+                display_cluster_registries(&fleet.records, output)
+                    .context("Failed to display registries")?;
+            }
+            fleet.failures
+        }
+        GetImages::Drift { .. }
+        | GetImages::Labels { .. }
+        | GetImages::Annotations { .. }
+        | GetImages::Health { .. } => {
+            anyhow::bail!("{:?} cannot query several clusters", resource)
+        }
+    };
+
+    if failures.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    for failure in &failures {
+        eprintln!("error: {}: {}", failure.cluster, failure.error);
+    }
+    if failures.len() == contexts.len() {
+        anyhow::bail!("All {} clusters failed", contexts.len());
+    }
+    eprintln!("{} of {} clusters failed", failures.len(), contexts.len());
+    Ok(ExitCode::from(PARTIAL_FAILURE_EXIT_CODE))
+}
+
+/// Compare two snapshots and display the containers whose images differ
+fn display_snapshot_changes(
+    before: &ImageSnapshot,
+    after: &ImageSnapshot,
+    output: &OutputFormat,
+) -> KelperResult<()> {
+    let changes = before.diff(after);
+    info!(
+        before = %before.cluster,
+        before_taken_at = %before.taken_at,
+        after = %after.cluster,
+        after_taken_at = %after.taken_at,
+        changes = changes.len(),
+        "Compared snapshots"
+    );
+
+    if changes.is_empty() && !output.is_machine_readable() {
+        println!(
+            "No image differences between {} ({}) and {} ({})",
+            before.cluster, before.taken_at, after.cluster, after.taken_at
+        );
+    } else {
+        display_snapshot_diff(&changes, output).context("Failed to display snapshot diff")?;
+    }
+    Ok(())
+}
+
+/// List and display the labels or annotations of pods, namespaces or nodes
+async fn display_object_metadata(
+    client: &K8sClient,
+    resource: MetadataResource,
+    field: MetadataField,
+    query: &ImageQuery,
+    keys: &[Pattern],
+    output: &OutputFormat,
+) -> KelperResult<()> {
+    debug!(
+        resource = %resource,
+        namespace = %query.namespace,
+        all_namespaces = %query.all_namespaces,
+        node = ?query.node,
+        pod = ?query.pod,
+        selector = ?query.label_selector,
+        keys = ?keys,
+        output = ?output,
+        "Processing get {} command",
+        field
+    );
+
+    let entries = client
+        .get_metadata(resource, field, query, keys)
+        .await
+        .with_context(|| format!("Failed to retrieve {}", field))?;
+
+    if entries.is_empty() && !output.is_machine_readable() {
+        warn!("No {} found matching your criteria", field);
+    } else {
+        display_metadata(&entries, resource, output)
+            .with_context(|| format!("Failed to display {}", field))?;
+        info!(count = entries.len(), "Successfully displayed {}", field);
+    }
     Ok(())
 }
 
 /// Process the command line arguments and execute the corresponding command
 #[instrument(skip(client), level = "debug")]
-async fn process_commands(args: Args, client: K8sClient) -> KelperResult<()> {
+async fn process_commands(args: Args, client: K8sClient) -> KelperResult<ExitCode> {
     match args.command {
-        Commands::Get { resource } => match resource {
-            GetImages::Images {
+        Commands::Get { resource } => {
+            let query = resource.image_query();
+            match resource {
+                GetImages::Images {
+                    namespace,
+                    node,
+                    pod,
+                    pod_regex,
+                    selector,
+                    field_selector,
+                    image,
+                    tag,
+                    registry,
+                    exclude_registry,
+                    provider,
+                    container_type,
+                    all_namespaces,
+                    group_by,
+                    watch,
+                    output,
+                    ..
+                } => {
+                    debug!(
+                        namespace = %namespace,
+                        node = ?node,
+                        pod = ?pod,
+                        pod_regex = ?pod_regex,
+                        image = ?image,
+                        tag = ?tag,
+                        selector = ?selector,
+                        field_selector = ?field_selector,
+                        registry = ?registry,
+                        exclude_registry = ?exclude_registry,
+                        provider = ?provider,
+                        container_type = %container_type,
+                        all_namespaces = %all_namespaces,
+                        group_by = ?group_by,
+                        watch = %watch,
+                        output = ?output,
+                        "Processing get images command"
+                    );
+
+                    if watch {
+                        let mut with_header = true;
+                        client
+                            .watch_pod_images(&query, |events| {
+                                display_image_events(&events, &output, with_header)
+                                    .context("Failed to display image events")?;
+                                with_header = false;
+                                Ok(())
+                            })
+                            .await
+                            .context("Failed to watch pod images")?;
+                        return Ok(ExitCode::SUCCESS);
+                    }
+
+                    if group_by.is_none() && output.is_streamable() {
+                        let mut pages = PodImagePages::new(&output);
+                        let count = client
+                            .stream_pod_images(&query, |images| {
+                                pages
+                                    .display_page(&images)
+                                    .context("Failed to display pod images")?;
+                                Ok(())
+                            })
+                            .await
+                            .context("Failed to retrieve pod images")?;
+
+                        // Closes JSON arrays, or prints the empty output when nothing matched
+                        pages.finish().context("Failed to display pod images")?;
+                        info!(count, "Successfully displayed pod images");
+                        return Ok(ExitCode::SUCCESS);
+                    }
+
+                    let pod_images = client
+                        .query_pod_images(&query)
+                        .await
+                        .context("Failed to retrieve pod images")?;
+
+                    if pod_images.is_empty()
+                        && matches!(output, OutputFormat::Normal | OutputFormat::Wide)
+                    {
+                        warn!("No pod images found matching your criteria");
+                    } else if let Some(group_by) = group_by {
+                        let summary = summarize_images(&pod_images, group_by);
+                        display_image_summary(&summary, group_by, &output)
+                            .context("Failed to display image summary")?;
+                        info!(
+                            groups = summary.len(),
+                            "Successfully displayed image summary"
+                        );
+                    } else {
+                        debug!(output = ?output, "Displaying pod images");
+                        display_pod_images(&pod_images, &output)
+                            .context("Failed to display pod images")?;
+                        info!(
+                            count = pod_images.len(),
+                            "Successfully displayed pod images"
+                        );
+                    }
+                }
+                GetImages::Registries {
+                    namespace,
+                    all_namespaces,
+                    source,
+                    output,
+                    ..
+                } => {
+                    debug!(
+                        namespace = %namespace,
+                        all_namespaces = %all_namespaces,
+                        source = %source,
+                        output = ?output,
+                        "Processing get registries command"
+                    );
+
+                    let registries = client
+                        .get_registry_usage(&namespace, all_namespaces, source)
+                        .await
+                        .context("Failed to retrieve registries")?;
+
+                    if registries.is_empty()
+                        && matches!(output, OutputFormat::Normal | OutputFormat::Wide)
+                    {
+                        warn!("No registries found in the specified namespace(s)");
+                    } else {
+                        debug!(output = ?output, "Displaying registries");
+                        display_registries(&registries, &output)
+                            .context("Failed to display registries")?;
+                        info!(
+                            count = registries.len(),
+                            "Successfully displayed registries"
+                        );
+                    }
+                }
+                GetImages::Drift {
+                    namespace,
+                    pod,
+                    selector,
+                    container_type,
+                    drifted,
+                    all_namespaces,
+                    output,
+                    ..
+                } => {
+                    debug!(
+                        namespace = %namespace,
+                        pod = ?pod,
+                        selector = ?selector,
+                        container_type = %container_type,
+                        drifted = %drifted,
+                        all_namespaces = %all_namespaces,
+                        output = ?output,
+                        "Processing get drift command"
+                    );
+
+                    let mut rows = client
+                        .get_image_drift(&query)
+                        .await
+                        .context("Failed to retrieve image drift")?;
+                    if drifted {
+                        rows.retain(|row| row.has_drift());
+                    }
+
+                    if rows.is_empty() && !output.is_machine_readable() {
+                        warn!("No containers found matching your criteria");
+                    } else {
+                        display_drift(&rows, &output).context("Failed to display image drift")?;
+                        info!(count = rows.len(), "Successfully displayed image drift");
+                    }
+                }
+                GetImages::Health {
+                    namespace,
+                    node,
+                    pod,
+                    selector,
+                    container_type,
+                    all_namespaces,
+                    restart_threshold,
+                    unhealthy,
+                    output,
+                    ..
+                } => {
+                    debug!(
+                        namespace = %namespace,
+                        node = ?node,
+                        pod = ?pod,
+                        selector = ?selector,
+                        container_type = %container_type,
+                        all_namespaces = %all_namespaces,
+                        restart_threshold = %restart_threshold,
+                        unhealthy = %unhealthy,
+                        output = ?output,
+                        "Processing get health command"
+                    );
+
+                    let mut rows = client
+                        .get_container_health(&query, restart_threshold)
+                        .await
+                        .context("Failed to retrieve container health")?;
+                    if unhealthy {
+                        rows.retain(|row| row.is_unhealthy());
+                    }
+
+                    if rows.is_empty() && !output.is_machine_readable() {
+                        if unhealthy {
+                            println!("No unhealthy containers found");
+                        } else {
+                            warn!("No containers found matching your criteria");
+                        }
+                    } else {
+                        display_health(&rows, &output)
+                            .context("Failed to display container health")?;
+                        info!(
+                            count = rows.len(),
+                            "Successfully displayed container health"
+                        );
+                    }
+                }
+                GetImages::Labels {
+                    resource,
+                    key,
+                    output,
+                    ..
+                } => {
+                    display_object_metadata(
+                        &client,
+                        resource,
+                        MetadataField::Labels,
+                        &query,
+                        &key,
+                        &output,
+                    )
+                    .await?;
+                }
+                GetImages::Annotations {
+                    resource,
+                    key,
+                    output,
+                    ..
+                } => {
+                    display_object_metadata(
+                        &client,
+                        resource,
+                        MetadataField::Annotations,
+                        &query,
+                        &key,
+                        &output,
+                    )
+                    .await?;
+                }
+            }
+        }
+        Commands::Check { policy } => match policy {
+            CheckPolicy::Registries {
                 namespace,
-                node,
-                pod,
-                registry,
                 all_namespaces,
+                selector,
+                allow,
+                policy,
                 output,
                 ..
             } => {
                 debug!(
                     namespace = %namespace,
-                    node = ?node,
-                    pod = ?pod,
-                    registry = ?registry,
                     all_namespaces = %all_namespaces,
+                    selector = ?selector,
+                    allow = ?allow,
+                    policy = ?policy,
                     output = ?output,
-                    "Processing get images command"
+                    "Processing check registries command"
                 );
 
+                let mut registry_policy = match &policy {
+                    Some(path) => RegistryPolicy::from_file(path)?,
+                    None => RegistryPolicy::default(),
+                };
+                registry_policy.extend(allow);
+                if registry_policy.is_empty() {
+                    anyhow::bail!("The registry policy does not allow any registry");
+                }
+
+                let query = ImageQuery {
+                    namespace,
+                    all_namespaces,
+                    label_selector: selector,
+                    ..Default::default()
+                };
                 let pod_images = client
-                    .get_pod_images(
-                        &namespace,
-                        node.as_deref(),
-                        pod.as_deref(),
-                        registry.as_deref(),
-                        all_namespaces,
-                    )
+                    .query_pod_images(&query)
                     .await
                     .context("Failed to retrieve pod images")?;
 
-                if pod_images.is_empty() {
-                    warn!("No pod images found matching your criteria");
-                } else {
-                    debug!(output = ?output, "Displaying pod images");
-                    display_pod_images(&pod_images, &output)
-                        .context("Failed to display pod images")?;
-                    info!(
-                        count = pod_images.len(),
-                        "Successfully displayed pod images"
+                let violations = registry_policy.violations(&pod_images);
+                if violations.is_empty() {
+                    if output.is_machine_readable() {
+                        print!(
+                            "{}",
+                            render_pod_images(&violations, &output)
+                                .context("Failed to display violations")?
+                        );
+                    } else {
+                        println!(
+                            "All {} containers pull from allowed registries",
+                            pod_images.len()
+                        );
+                    }
+                    return Ok(ExitCode::SUCCESS);
+                }
+
+                display_pod_images(&violations, &output).context("Failed to display violations")?;
+                eprintln!(
+                    "{} of {} containers pull from disallowed registries",
+                    violations.len(),
+                    pod_images.len()
+                );
+                return Ok(ExitCode::from(POLICY_VIOLATION_EXIT_CODE));
+            }
+        },
+        Commands::Audit { report } => match report {
+            AuditReport::Tags {
+                namespace,
+                all_namespaces,
+                selector,
+                output,
+                ..
+            } => {
+                debug!(
+                    namespace = %namespace,
+                    all_namespaces = %all_namespaces,
+                    selector = ?selector,
+                    output = ?output,
+                    "Processing audit tags command"
+                );
+
+                let query = ImageQuery {
+                    namespace,
+                    all_namespaces,
+                    label_selector: selector,
+                    ..Default::default()
+                };
+                let pod_images = client
+                    .query_pod_images(&query)
+                    .await
+                    .context("Failed to retrieve pod images")?;
+
+                let entries = audit_tags(&pod_images);
+                if entries.is_empty() && !output.is_machine_readable() {
+                    println!(
+                        "All {} containers run images pinned by digest",
+                        pod_images.len()
                     );
+                } else {
+                    display_tag_audit(&entries, &output).context("Failed to display tag audit")?;
+                    info!(count = entries.len(), "Successfully displayed tag audit");
                 }
             }
-            GetImages::Registries {
+            AuditReport::Skew {
                 namespace,
                 all_namespaces,
+                selector,
+                image,
+                all,
                 output,
                 ..
             } => {
                 debug!(
                     namespace = %namespace,
                     all_namespaces = %all_namespaces,
+                    selector = ?selector,
+                    image = ?image,
+                    all = %all,
                     output = ?output,
-                    "Processing get registries command"
+                    "Processing audit skew command"
                 );
 
-                let registries = client
-                    .get_unique_registries(&namespace, all_namespaces)
+                let query = ImageQuery {
+                    namespace,
+                    all_namespaces,
+                    label_selector: selector,
+                    image,
+                    ..Default::default()
+                };
+                let pod_images = client
+                    .query_pod_images(&query)
                     .await
-                    .context("Failed to retrieve registries")?;
+                    .context("Failed to retrieve pod images")?;
 
-                if registries.is_empty() {
-                    warn!("No registries found in the specified namespace(s)");
+                let entries = audit_skew(&pod_images, !all);
+                if entries.is_empty() && !output.is_machine_readable() {
+                    println!("Every image runs a single tag and digest");
                 } else {
-                    debug!(output = ?output, "Displaying registries");
-                    display_registries(&registries, &output)
-                        .context("Failed to display registries")?;
-                    info!(
-                        count = registries.len(),
-                        "Successfully displayed registries"
-                    );
+                    display_skew(&entries, &output).context("Failed to display version skew")?;
+                    info!(count = entries.len(), "Successfully displayed version skew");
                 }
             }
+            AuditReport::Labels {
+                namespace,
+                all_namespaces,
+                selector,
+                require,
+                require_annotation,
+                kind,
+                output,
+                ..
+            } => {
+                debug!(
+                    namespace = %namespace,
+                    all_namespaces = %all_namespaces,
+                    selector = ?selector,
+                    require = ?require,
+                    require_annotation = ?require_annotation,
+                    kind = ?kind,
+                    output = ?output,
+                    "Processing audit labels command"
+                );
+
+                let requirements: Vec<LabelRequirement> =
+                    require.into_iter().chain(require_annotation).collect();
+                let audit = client
+                    .audit_labels(
+                        &namespace,
+                        all_namespaces,
+                        selector.as_deref(),
+                        &kind,
+                        &requirements,
+                    )
+                    .await
+                    .context("Failed to audit labels")?;
+
+                if audit.violations.is_empty() {
+                    if output.is_machine_readable() {
+                        display_label_violations(&audit.violations, &output)
+                            .context("Failed to display violations")?;
+                    } else {
+                        println!(
+                            "All {} objects carry the required labels and annotations",
+                            audit.objects
+                        );
+                    }
+                    return Ok(ExitCode::SUCCESS);
+                }
+
+                display_label_violations(&audit.violations, &output)
+                    .context("Failed to display violations")?;
+                let violating: HashSet<(&str, &str, &str)> = audit
+                    .violations
+                    .iter()
+                    .map(|v| (v.kind.as_str(), v.namespace.as_str(), v.name.as_str()))
+                    .collect();
+                eprintln!(
+                    "{} of {} objects miss required labels or annotations",
+                    violating.len(),
+                    audit.objects
+                );
+                return Ok(ExitCode::from(POLICY_VIOLATION_EXIT_CODE));
+            }
+        },
+        Commands::Snapshot { action } => match action {
+            SnapshotAction::Save {
+                namespace,
+                all_namespaces,
+                selector,
+                output,
+                ..
+            } => {
+                debug!(
+                    namespace = %namespace,
+                    all_namespaces = %all_namespaces,
+                    selector = ?selector,
+                    output = ?output,
+                    "Processing snapshot save command"
+                );
+
+                let scope = if all_namespaces { "" } else { &namespace };
+                let query = ImageQuery {
+                    namespace: namespace.clone(),
+                    all_namespaces,
+                    label_selector: selector,
+                    ..Default::default()
+                };
+                let pod_images = client
+                    .query_pod_images(&query)
+                    .await
+                    .context("Failed to retrieve pod images")?;
+
+                let snapshot = ImageSnapshot::new(client.cluster_name(), scope, pod_images);
+                match &output {
+                    Some(path) => {
+                        snapshot.save(path)?;
+                        eprintln!(
+                            "Saved {} images from {} to {}",
+                            snapshot.images.len(),
+                            snapshot.cluster,
+                            path.display()
+                        );
+                    }
+                    None => print!("{}", snapshot.to_json()?),
+                }
+            }
+            action @ SnapshotAction::Diff { .. } => {
+                anyhow::bail!("{:?} does not use the current cluster", action)
+            }
         },
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,122 @@
+use crate::utils::jsonpath::{find_unquoted, JsonPath};
+use crate::utils::output::value_to_cell;
+use std::fmt;
+use std::str::FromStr;
+
+/// A table column: the header to print and the field path that fills its cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSpec {
+    /// Column header
+    pub header: String,
+    /// Path selecting the cell value from each serialized row
+    pub path: JsonPath,
+    /// Optional prettytable style spec applied to every cell of the column
+    pub style: Option<&'static str>,
+}
+
+impl ColumnSpec {
+    /// Create a column showing a single top-level field of each row
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Column header
+    /// * `field` - Name of the field to show
+    ///
+    /// # Returns
+    ///
+    /// * `ColumnSpec` - The column specification
+    pub fn field(header: &str, field: &str) -> Self {
+        Self {
+            header: header.to_string(),
+            path: JsonPath::field(field),
+            style: None,
+        }
+    }
+
+    /// Apply a prettytable style spec (e.g. `"Fy"`) to the cells of this column
+    pub fn styled(mut self, style: &'static str) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    /// Render the cell of this column for a serialized row
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row serialized as JSON
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The selected values, and the items of selected lists, joined by commas,
+    ///   or `<none>` when nothing matched
+    pub fn cell(&self, row: &serde_json::Value) -> String {
+        let values = self.path.evaluate(row);
+        if values.is_empty() {
+            return "<none>".to_string();
+        }
+        values
+            .into_iter()
+            .map(value_to_cell)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl fmt::Display for ColumnSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.header, self.path)
+    }
+}
+
+/// Parse a kubectl-style custom columns specification
+///
+/// # Arguments
+///
+/// * `spec` - Comma-separated `HEADER:path` pairs, e.g. `POD:.pod_name,IMG:.image_name`
+///
+/// # Returns
+///
+/// * `Result<Vec<ColumnSpec>>` - The parsed columns or a description of the problem
+pub fn parse_custom_columns(spec: &str) -> Result<Vec<ColumnSpec>, String> {
+    if spec.trim().is_empty() {
+        return Err("custom-columns requires at least one HEADER:path pair".to_string());
+    }
+
+    split_columns(spec)
+        .into_iter()
+        .map(|column| {
+            let (header, path) = column.split_once(':').ok_or_else(|| {
+                format!("custom column '{}' must be in the form HEADER:path", column)
+            })?;
+            let header = header.trim();
+            if header.is_empty() {
+                return Err(format!("custom column '{}' has an empty header", column));
+            }
+            let path = path.trim();
+            let path = path
+                .strip_prefix('{')
+                .and_then(|p| p.strip_suffix('}'))
+                .unwrap_or(path);
+
+            Ok(ColumnSpec {
+                header: header.to_string(),
+                path: JsonPath::from_str(path)?,
+                style: None,
+            })
+        })
+        .collect()
+}
+
+/// Split a custom columns specification on the commas between columns
+///
+/// Commas inside quotes or brackets belong to a path, e.g. `KEY:.labels['a,b']`.
+fn split_columns(spec: &str) -> Vec<&str> {
+    let mut columns = Vec::new();
+    let mut rest = spec;
+    while let Some(comma) = find_unquoted(rest, ',') {
+        columns.push(&rest[..comma]);
+        rest = &rest[comma + 1..];
+    }
+    columns.push(rest);
+    columns
+}
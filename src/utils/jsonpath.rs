@@ -0,0 +1,349 @@
+use serde_json::Value;
+use std::fmt;
+use std::str::FromStr;
+
+/// A single step of a field path
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Select a named field of an object (`.name` or `['name']`)
+    Field(String),
+    /// Select an element of a list (`[0]`, `[-1]`)
+    Index(i64),
+    /// Select every element of a list or every value of an object (`[*]`)
+    Wildcard,
+}
+
+/// A kubectl-style field path such as `.image_name` or `.items[*].pod_name`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    /// The path as written by the user
+    raw: String,
+    /// The parsed path segments
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    /// Create a path selecting a single top-level field
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the field to select
+    ///
+    /// # Returns
+    ///
+    /// * `JsonPath` - A path equivalent to `.name`
+    pub fn field(name: &str) -> Self {
+        Self {
+            raw: format!(".{}", name),
+            segments: vec![Segment::Field(name.to_string())],
+        }
+    }
+
+    /// Evaluate the path against a JSON value
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The value the path is relative to
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<&Value>` - Every value selected by the path (empty when nothing matches)
+    pub fn evaluate<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in current {
+                match (segment, value) {
+                    (Segment::Field(name), Value::Object(map)) => next.extend(map.get(name)),
+                    (Segment::Index(index), Value::Array(items)) => {
+                        let position = if *index < 0 {
+                            items.len() as i64 + index
+                        } else {
+                            *index
+                        };
+                        if position >= 0 {
+                            next.extend(items.get(position as usize));
+                        }
+                    }
+                    (Segment::Wildcard, Value::Array(items)) => next.extend(items.iter()),
+                    (Segment::Wildcard, Value::Object(map)) => next.extend(map.values()),
+                    _ => {}
+                }
+            }
+            current = next;
+        }
+
+        current
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = s.trim();
+        let mut rest = raw
+            .strip_prefix('$')
+            .or_else(|| raw.strip_prefix('@'))
+            .unwrap_or(raw);
+        let mut segments = Vec::new();
+
+        // Allow a bare field name at the start, e.g. "pod_name"
+        if !rest.is_empty() && !rest.starts_with('.') && !rest.starts_with('[') {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            segments.push(Segment::Field(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+
+        while !rest.is_empty() {
+            if let Some(after_dot) = rest.strip_prefix('.') {
+                let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+                match &after_dot[..end] {
+                    "" => {}
+                    "*" => segments.push(Segment::Wildcard),
+                    name => segments.push(Segment::Field(name.to_string())),
+                }
+                rest = &after_dot[end..];
+            } else if let Some(after_bracket) = rest.strip_prefix('[') {
+                let end = after_bracket
+                    .find(']')
+                    .ok_or_else(|| format!("unclosed '[' in path '{}'", raw))?;
+                let selector = after_bracket[..end].trim();
+                let segment = if selector == "*" {
+                    Segment::Wildcard
+                } else if let Ok(index) = selector.parse::<i64>() {
+                    Segment::Index(index)
+                } else if selector.len() >= 2
+                    && ((selector.starts_with('\'') && selector.ends_with('\''))
+                        || (selector.starts_with('"') && selector.ends_with('"')))
+                {
+                    Segment::Field(selector[1..selector.len() - 1].to_string())
+                } else {
+                    return Err(format!(
+                        "unsupported selector '[{}]' in path '{}'",
+                        selector, raw
+                    ));
+                };
+                segments.push(segment);
+                rest = &after_bracket[end + 1..];
+            } else {
+                return Err(format!("unexpected '{}' in path '{}'", rest, raw));
+            }
+        }
+
+        Ok(Self {
+            raw: raw.to_string(),
+            segments,
+        })
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+/// A node of a parsed jsonpath template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplateNode {
+    /// Literal text copied to the output
+    Text(String),
+    /// A field path whose values are printed
+    Path(JsonPath),
+    /// A `{range <path>}...{end}` block repeated for every selected value
+    Range(JsonPath, Vec<TemplateNode>),
+}
+
+/// A kubectl-style jsonpath template such as `{range .items[*]}{.pod_name}{"\n"}{end}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPathTemplate {
+    /// The template as written by the user
+    raw: String,
+    /// The parsed template nodes
+    nodes: Vec<TemplateNode>,
+}
+
+impl JsonPathTemplate {
+    /// Render the template against a JSON value
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The value paths outside of any range are relative to
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The rendered template
+    pub fn render(&self, root: &Value) -> String {
+        let mut output = String::new();
+        render_nodes(&self.nodes, root, &mut output);
+        output
+    }
+}
+
+/// Render template nodes relative to the given value
+fn render_nodes(nodes: &[TemplateNode], current: &Value, output: &mut String) {
+    for node in nodes {
+        match node {
+            TemplateNode::Text(text) => output.push_str(text),
+            TemplateNode::Path(path) => {
+                let values: Vec<String> = path
+                    .evaluate(current)
+                    .into_iter()
+                    .map(value_to_text)
+                    .collect();
+                output.push_str(&values.join(" "));
+            }
+            TemplateNode::Range(path, body) => {
+                for value in path.evaluate(current) {
+                    render_nodes(body, value, output);
+                }
+            }
+        }
+    }
+}
+
+/// Convert a selected value to the text printed for it
+pub(crate) fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Parse the contents of a quoted `{"..."}` literal, handling escape sequences
+fn parse_literal(quoted: &str) -> String {
+    let mut literal = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            literal.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => literal.push('\n'),
+            Some('t') => literal.push('\t'),
+            Some('r') => literal.push('\r'),
+            Some(other) => literal.push(other),
+            None => literal.push('\\'),
+        }
+    }
+    literal
+}
+
+/// Find the first occurrence of a character outside quoted literals and `[...]` selectors
+///
+/// Quotes and brackets such as `{"}"}` or `['a,b']` hide the characters they
+/// contain, and a backslash escapes the next character inside quotes.
+///
+/// # Arguments
+///
+/// * `text` - The text to scan
+/// * `target` - The character to find
+///
+/// # Returns
+///
+/// * `Option<usize>` - The byte index of the character, or `None` if it does not occur
+pub(crate) fn find_unquoted(text: &str, target: char) -> Option<usize> {
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, c) if c == target && depth == 0 => return Some(index),
+            (None, '[') => depth += 1,
+            (None, ']') => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    None
+}
+
+impl FromStr for JsonPathTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Each frame holds the nodes collected so far and the path of the range that opened it
+        let mut frames: Vec<(Option<JsonPath>, Vec<TemplateNode>)> = vec![(None, Vec::new())];
+        let mut rest = s;
+
+        while !rest.is_empty() {
+            let Some(open) = rest.find('{') else {
+                frames
+                    .last_mut()
+                    .expect("root frame is never popped")
+                    .1
+                    .push(TemplateNode::Text(rest.to_string()));
+                break;
+            };
+
+            if open > 0 {
+                frames
+                    .last_mut()
+                    .expect("root frame is never popped")
+                    .1
+                    .push(TemplateNode::Text(rest[..open].to_string()));
+            }
+
+            let close = find_unquoted(&rest[open..], '}')
+                .map(|index| open + index)
+                .ok_or_else(|| format!("unclosed '{{' in template '{}'", s))?;
+            let expression = rest[open + 1..close].trim();
+            rest = &rest[close + 1..];
+
+            if let Some(range_path) = expression.strip_prefix("range ") {
+                frames.push((Some(range_path.parse()?), Vec::new()));
+            } else if expression == "end" {
+                let (path, body) = frames.pop().expect("root frame is never popped");
+                let Some(path) = path else {
+                    return Err(format!("unexpected {{end}} in template '{}'", s));
+                };
+                frames
+                    .last_mut()
+                    .ok_or_else(|| format!("unexpected {{end}} in template '{}'", s))?
+                    .1
+                    .push(TemplateNode::Range(path, body));
+            } else if expression.len() >= 2
+                && expression.starts_with('"')
+                && expression.ends_with('"')
+            {
+                let literal = parse_literal(&expression[1..expression.len() - 1]);
+                frames
+                    .last_mut()
+                    .expect("root frame is never popped")
+                    .1
+                    .push(TemplateNode::Text(literal));
+            } else {
+                frames
+                    .last_mut()
+                    .expect("root frame is never popped")
+                    .1
+                    .push(TemplateNode::Path(expression.parse()?));
+            }
+        }
+
+        if frames.len() != 1 {
+            return Err(format!("missing {{end}} for {{range}} in template '{}'", s));
+        }
+
+        let (_, nodes) = frames.pop().expect("root frame is never popped");
+        Ok(Self {
+            raw: s.to_string(),
+            nodes,
+        })
+    }
+}
+
+impl fmt::Display for JsonPathTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
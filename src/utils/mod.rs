@@ -1,24 +1,27 @@
-use crate::{k8s::PodImage, OutputFormat};
+use crate::{
+    k8s::{
+        ClusterRecord, ContainerHealth, GroupBy, ImageDrift, ImageEvent, ImageSummary,
+        LabelViolation, MetadataEntry, MetadataResource, PodImage, RegistryUsage, SkewEntry,
+        SnapshotChange, TagAuditEntry,
+    },
+    OutputFormat,
+};
 use anyhow::Result;
 use prettytable::{format::FormatBuilder, Cell, Row, Table};
+use serde::Serialize;
+use std::io::Write;
 use tracing::warn;
 
+mod columns;
+mod jsonpath;
 pub mod logging;
+mod output;
+mod pattern;
 
-/// List of known container image registries
-pub const KNOWN_REGISTRIES: [&str; 11] = [
-    "docker.io",
-    "registry.hub.docker.com",
-    "ghcr.io",
-    "gcr.io",
-    "quay.io",
-    "registry.gitlab.com",
-    "mcr.microsoft.com",
-    "registry.k8s.io",
-    "public.ecr.aws",
-    "docker.pkg.github.com",
-    "pkg.dev",
-];
+pub use columns::{parse_custom_columns, ColumnSpec};
+pub use jsonpath::{JsonPath, JsonPathTemplate};
+pub use output::render_structured;
+pub use pattern::Pattern;
 
 /// Error type for table display operations
 #[derive(Debug)]
@@ -43,7 +46,7 @@ impl TableDisplayError {
     }
 }
 
-/// Display pod images in a formatted table
+/// Display pod images in the requested output format
 ///
 /// # Arguments
 ///
@@ -57,25 +60,389 @@ pub fn display_pod_images(
     images: &[PodImage],
     output_format: &OutputFormat,
 ) -> Result<(), TableDisplayError> {
-    if images.is_empty() {
-        warn!("No images found matching criteria");
-        return Ok(());
+    match output_format {
+        OutputFormat::Normal | OutputFormat::Wide if images.is_empty() => {
+            warn!("No images found matching criteria");
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            print!("{}", render_pod_images(images, output_format)?)
+        }
+        _ => display_records(images, output_format, pod_image_columns)?,
     }
+    Ok(())
+}
 
-    let mut table = create_table()?;
-    let header_row = create_header_row(output_format);
-    table.add_row(header_row);
+/// Render pod images in the requested output format without printing them
+///
+/// # Arguments
+///
+/// * `images` - List of pod images to render
+/// * `output_format` - Format to use for rendering the images
+///
+/// # Returns
+///
+/// * `Result<String>` - The rendered output or error
+pub fn render_pod_images(
+    images: &[PodImage],
+    output_format: &OutputFormat,
+) -> Result<String, TableDisplayError> {
+    if images.is_empty() && matches!(output_format, OutputFormat::Csv | OutputFormat::Tsv) {
+        return render_delimited_header::<PodImage>(output_format);
+    }
+    render_records(images, output_format, pod_image_columns)
+}
 
-    for image in images {
-        let row = create_image_row(image, output_format)
-            .map_err(|e| TableDisplayError::new(&e.message))?;
-        table.add_row(row);
+/// Pod images printed page by page as they are listed
+///
+/// Tables print their header with the first rows and keep the column widths
+/// of earlier pages, so columns only widen when a later page has longer
+/// values. JSON is written as an array that stays open until
+/// [`PodImagePages::finish`], YAML as sequence items and CSV and TSV as rows
+/// under a single header. Jsonpath templates see every row at once and cannot
+/// be printed page by page; see [`OutputFormat::is_streamable`].
+pub struct PodImagePages {
+    /// Format the pages are printed in
+    output_format: OutputFormat,
+    /// Columns of the table formats
+    columns: Vec<ColumnSpec>,
+    /// Widest cell of every table column so far, header included
+    widths: Vec<usize>,
+    /// Number of rows printed so far
+    rows: usize,
+}
+
+impl PodImagePages {
+    /// Start printing pod images in the requested output format
+    ///
+    /// # Arguments
+    ///
+    /// * `output_format` - Format to print the pages in
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - A printer that has not printed anything yet
+    pub fn new(output_format: &OutputFormat) -> Self {
+        let columns = match output_format {
+            OutputFormat::CustomColumns(columns) => columns.clone(),
+            _ => pod_image_columns(output_format),
+        };
+        let widths = columns
+            .iter()
+            .map(|column| column.header.chars().count())
+            .collect();
+        Self {
+            output_format: output_format.clone(),
+            columns,
+            widths,
+            rows: 0,
+        }
     }
 
-    table.printstd();
+    /// Print one page of pod images and flush it to stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - The pod images of the page
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success or error
+    pub fn display_page(&mut self, images: &[PodImage]) -> Result<(), TableDisplayError> {
+        if self.output_format.is_table() {
+            if let Some(table) = self.page_table(images)? {
+                table.printstd();
+            }
+        } else {
+            print!("{}", self.render_page(images)?);
+        }
+        std::io::stdout()
+            .flush()
+            .map_err(|e| TableDisplayError::new(&e.to_string()))
+    }
+
+    /// Print what closes the output once every page is printed
+    ///
+    /// Without any rows this prints the same as [`display_pod_images`] with no
+    /// images, e.g. an empty JSON array or a CSV header.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<()>` - Success or error
+    pub fn finish(&self) -> Result<(), TableDisplayError> {
+        if self.rows == 0 {
+            return display_pod_images(&[], &self.output_format);
+        }
+        print!("{}", self.render_end()?);
+        std::io::stdout()
+            .flush()
+            .map_err(|e| TableDisplayError::new(&e.to_string()))
+    }
+
+    /// Render one page of pod images without printing it
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - The pod images of the page
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String>` - The rendered page or error
+    pub fn render_page(&mut self, images: &[PodImage]) -> Result<String, TableDisplayError> {
+        if self.output_format.is_table() {
+            return Ok(self
+                .page_table(images)?
+                .map(|table| table.to_string())
+                .unwrap_or_default());
+        }
+
+        let first = self.rows == 0;
+        let rendered = match &self.output_format {
+            OutputFormat::Json => images.iter().enumerate().try_fold(
+                String::new(),
+                |mut rendered, (index, image)| {
+                    let item = serde_json::to_string_pretty(image)
+                        .map_err(|e| TableDisplayError::new(&e.to_string()))?;
+                    rendered.push_str(if first && index == 0 { "[\n" } else { ",\n" });
+                    let lines: Vec<String> =
+                        item.lines().map(|line| format!("  {}", line)).collect();
+                    rendered.push_str(&lines.join("\n"));
+                    Ok(rendered)
+                },
+            )?,
+            OutputFormat::Yaml if images.is_empty() => String::new(),
+            OutputFormat::Yaml => render_structured(images, &self.output_format)?,
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                render_delimited(images, &self.output_format, first)?
+            }
+            other => {
+                return Err(TableDisplayError::new(&format!(
+                    "{} output cannot be printed page by page",
+                    other
+                )))
+            }
+        };
+        self.rows += images.len();
+        Ok(rendered)
+    }
+
+    /// Render what closes the output once every page is rendered
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String>` - The closing output, e.g. the end of the JSON array, or error
+    pub fn render_end(&self) -> Result<String, TableDisplayError> {
+        match &self.output_format {
+            _ if self.rows == 0 => render_pod_images(&[], &self.output_format),
+            OutputFormat::Json => Ok("\n]\n".to_string()),
+            _ => Ok(String::new()),
+        }
+    }
+
+    /// Build the table of one page, padding cells to the widths of earlier pages
+    fn page_table(&mut self, images: &[PodImage]) -> Result<Option<Table>, TableDisplayError> {
+        if images.is_empty() {
+            return Ok(None);
+        }
+
+        let mut rows = Vec::with_capacity(images.len());
+        for image in images {
+            let value =
+                serde_json::to_value(image).map_err(|e| TableDisplayError::new(&e.to_string()))?;
+            let cells: Vec<String> = self
+                .columns
+                .iter()
+                .map(|column| column.cell(&value))
+                .collect();
+            for (width, cell) in self.widths.iter_mut().zip(&cells) {
+                *width = (*width).max(cell.chars().count());
+            }
+            rows.push(cells);
+        }
+
+        let last = self.columns.len().saturating_sub(1);
+        let pad = |index: usize, text: &str, width: usize| {
+            if index == last {
+                text.to_string()
+            } else {
+                format!("{:<width$}", text, width = width)
+            }
+        };
+
+        let mut table = create_table()?;
+        if self.rows == 0 {
+            table.add_row(Row::new(
+                self.columns
+                    .iter()
+                    .zip(&self.widths)
+                    .enumerate()
+                    .map(|(index, (column, width))| Cell::new(&pad(index, &column.header, *width)))
+                    .collect(),
+            ));
+        }
+        for cells in rows {
+            table.add_row(Row::new(
+                cells
+                    .iter()
+                    .zip(self.columns.iter().zip(&self.widths))
+                    .enumerate()
+                    .map(|(index, (text, (column, width)))| {
+                        let cell = Cell::new(&pad(index, text, *width));
+                        match column.style {
+                            Some(style) => cell.style_spec(style),
+                            None => cell,
+                        }
+                    })
+                    .collect(),
+            ));
+        }
+
+        self.rows += images.len();
+        Ok(Some(table))
+    }
+}
+
+/// Render only the header line of CSV or TSV output, for lists without rows
+///
+/// The header is taken from the field names of a placeholder record, so that
+/// consumers still see the columns when nothing matched.
+fn render_delimited_header<T: Serialize + Default>(
+    output_format: &OutputFormat,
+) -> Result<String, TableDisplayError> {
+    let rendered = render_structured(&[T::default()], output_format)?;
+    Ok(rendered
+        .split_inclusive('\n')
+        .next()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Render rows as CSV or TSV, leaving out the header line unless `with_header` is set
+fn render_delimited<T: Serialize>(
+    records: &[T],
+    output_format: &OutputFormat,
+    with_header: bool,
+) -> Result<String, TableDisplayError> {
+    let rendered = render_structured(records, output_format)?;
+    if with_header {
+        return Ok(rendered);
+    }
+    Ok(rendered
+        .split_once('\n')
+        .map(|(_, rows)| rows.to_string())
+        .unwrap_or_default())
+}
+
+/// Display serializable rows in the requested output format
+///
+/// Tables are printed with colors when writing to a terminal; every other
+/// format is rendered with [`render_records`].
+///
+/// # Arguments
+///
+/// * `records` - The rows to display
+/// * `output_format` - Format to use for displaying the rows
+/// * `default_columns` - Columns used by the normal and wide formats
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub(crate) fn display_records<T: Serialize>(
+    records: &[T],
+    output_format: &OutputFormat,
+    default_columns: impl Fn(&OutputFormat) -> Vec<ColumnSpec>,
+) -> Result<(), TableDisplayError> {
+    match output_format {
+        OutputFormat::Normal | OutputFormat::Wide => {
+            create_record_table(records, &default_columns(output_format), true)?.printstd();
+        }
+        OutputFormat::CustomColumns(columns) => {
+            create_record_table(records, columns, true)?.printstd();
+        }
+        _ => print!(
+            "{}",
+            render_records(records, output_format, default_columns)?
+        ),
+    }
     Ok(())
 }
 
+/// Render serializable rows in the requested output format
+///
+/// # Arguments
+///
+/// * `records` - The rows to render
+/// * `output_format` - Format to use for rendering the rows
+/// * `default_columns` - Columns used by the normal and wide formats
+///
+/// # Returns
+///
+/// * `Result<String>` - The rendered output or error
+pub(crate) fn render_records<T: Serialize>(
+    records: &[T],
+    output_format: &OutputFormat,
+    default_columns: impl Fn(&OutputFormat) -> Vec<ColumnSpec>,
+) -> Result<String, TableDisplayError> {
+    match output_format {
+        OutputFormat::Normal | OutputFormat::Wide => {
+            Ok(create_record_table(records, &default_columns(output_format), true)?.to_string())
+        }
+        OutputFormat::CustomColumns(columns) => {
+            Ok(create_record_table(records, columns, true)?.to_string())
+        }
+        OutputFormat::JsonPath(template) => {
+            let items = serde_json::to_value(records)
+                .map_err(|e| TableDisplayError::new(&e.to_string()))?;
+            Ok(template.render(&serde_json::json!({ "items": items })))
+        }
+        _ => render_structured(records, output_format),
+    }
+}
+
+/// Build a table with one row per record and one cell per column
+///
+/// # Arguments
+///
+/// * `records` - The rows to put in the table
+/// * `columns` - The columns to show
+/// * `with_header` - Whether to start the table with a header row
+///
+/// # Returns
+///
+/// * `Result<Table>` - The populated table or error
+fn create_record_table<T: Serialize>(
+    records: &[T],
+    columns: &[ColumnSpec],
+    with_header: bool,
+) -> Result<Table, TableDisplayError> {
+    let mut table = create_table()?;
+    if with_header {
+        table.add_row(Row::new(
+            columns
+                .iter()
+                .map(|column| Cell::new(&column.header))
+                .collect(),
+        ));
+    }
+
+    for record in records {
+        let value =
+            serde_json::to_value(record).map_err(|e| TableDisplayError::new(&e.to_string()))?;
+        let cells = columns
+            .iter()
+            .map(|column| {
+                let cell = Cell::new(&column.cell(&value));
+                match column.style {
+                    Some(style) => cell.style_spec(style),
+                    None => cell,
+                }
+            })
+            .collect();
+        table.add_row(Row::new(cells));
+    }
+
+    Ok(table)
+}
+
 /// Create a new table with default formatting
 ///
 /// # Returns
@@ -96,7 +463,7 @@ fn create_table() -> Result<Table, TableDisplayError> {
     Ok(table)
 }
 
-/// Create a header row for the table based on output format
+/// Columns shown for pod images by the normal and wide formats
 ///
 /// # Arguments
 ///
@@ -104,106 +471,584 @@ fn create_table() -> Result<Table, TableDisplayError> {
 ///
 /// # Returns
 ///
-/// * `Row` - A row containing the table headers
-fn create_header_row(output_format: &OutputFormat) -> Row {
-    let mut header_cells = vec![
-        Cell::new("POD"),
-        Cell::new("NAMESPACE"),
-        Cell::new("CONTAINER"),
+/// * `Vec<ColumnSpec>` - The columns to show
+fn pod_image_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![
+        ColumnSpec::field("POD", "pod_name"),
+        ColumnSpec::field("NAMESPACE", "namespace"),
+        ColumnSpec::field("CONTAINER", "container_name"),
+    ];
+
+    if output_format.includes_container_type() {
+        columns.push(ColumnSpec::field("CONTAINER TYPE", "container_type"));
+    }
+
+    if output_format.includes_registry() {
+        columns.push(ColumnSpec::field("REGISTRY", "registry").styled("Fy"));
+    }
+
+    if output_format.includes_upstream() {
+        columns.push(ColumnSpec::field("UPSTREAM", "upstream"));
+    }
+
+    if output_format.includes_provider() {
+        columns.extend([
+            ColumnSpec::field("PROVIDER", "provider"),
+            ColumnSpec::field("ACCOUNT", "provider_account"),
+            ColumnSpec::field("REGION", "provider_region"),
+        ]);
+    }
+
+    columns.extend([
+        ColumnSpec::field("IMAGE", "image_name"),
+        ColumnSpec::field("VERSION", "image_version"),
+    ]);
+
+    if output_format.includes_digest() {
+        columns.push(ColumnSpec::field("DIGEST", "digest"));
+    }
+
+    if output_format.includes_node() {
+        columns.push(ColumnSpec::field("NODE", "node_name"));
+    }
+
+    columns
+}
+
+/// Display pod images read from several clusters, with a leading CLUSTER column
+///
+/// # Arguments
+///
+/// * `images` - Pod images of every cluster to display
+/// * `output_format` - Format to use for displaying the images
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_cluster_pod_images(
+    images: &[ClusterRecord<PodImage>],
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(images, output_format, |format| {
+        with_cluster_column(pod_image_columns(format))
+    })
+}
+
+/// Prefix the columns of a single-cluster table with the cluster each row was read from
+fn with_cluster_column(columns: Vec<ColumnSpec>) -> Vec<ColumnSpec> {
+    std::iter::once(ColumnSpec::field("CLUSTER", "cluster").styled("Fc"))
+        .chain(columns)
+        .collect()
+}
+
+/// Display container image registries and their usage in the specified format
+///
+/// # Arguments
+///
+/// * `registries` - Usage of each registry to display
+/// * `output_format` - Format to display the registries in
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_registries(
+    registries: &[RegistryUsage],
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    match output_format {
+        OutputFormat::Normal | OutputFormat::Wide if registries.is_empty() => {
+            warn!("No registries found");
+        }
+        OutputFormat::Csv | OutputFormat::Tsv if registries.is_empty() => {
+            print!(
+                "{}",
+                render_delimited_header::<RegistryUsage>(output_format)?
+            )
+        }
+        _ => display_records(registries, output_format, registry_columns)?,
+    }
+    Ok(())
+}
+
+/// Display the registries used by several clusters, with a leading CLUSTER column
+///
+/// # Arguments
+///
+/// * `registries` - Usage of each registry in every cluster
+/// * `output_format` - Format to display the registries in
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_cluster_registries(
+    registries: &[ClusterRecord<RegistryUsage>],
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(registries, output_format, |format| {
+        with_cluster_column(registry_columns(format))
+    })
+}
+
+/// Columns shown for registries by the normal and wide formats
+fn registry_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![ColumnSpec::field("CONTAINER REGISTRY", "registry")];
+
+    if matches!(output_format, OutputFormat::Wide) {
+        columns.extend([
+            ColumnSpec::field("PROVIDER", "provider"),
+            ColumnSpec::field("UPSTREAM", "upstreams"),
+            ColumnSpec::field("WORKLOADS", "workloads"),
+            ColumnSpec::field("CONTAINERS", "containers"),
+            ColumnSpec::field("KINDS", "kinds"),
+        ]);
+    }
+
+    columns
+}
+
+/// Display the containers found by a tag audit in the specified format
+///
+/// # Arguments
+///
+/// * `entries` - The audit entries to display
+/// * `output_format` - Format to display the entries in
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_tag_audit(
+    entries: &[TagAuditEntry],
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(entries, output_format, tag_audit_columns)
+}
+
+/// Columns shown for tag audit entries by the normal and wide formats
+fn tag_audit_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![
+        ColumnSpec::field("POD", "pod_name"),
+        ColumnSpec::field("NAMESPACE", "namespace"),
+        ColumnSpec::field("CONTAINER", "container_name"),
+    ];
+
+    if output_format.includes_container_type() {
+        columns.push(ColumnSpec::field("CONTAINER TYPE", "container_type"));
+    }
+
+    if output_format.includes_registry() {
+        columns.push(ColumnSpec::field("REGISTRY", "registry").styled("Fy"));
+    }
+
+    columns.extend([
+        ColumnSpec::field("IMAGE", "image_name"),
+        ColumnSpec::field("VERSION", "image_version"),
+        ColumnSpec::field("ISSUES", "issues").styled("Fr"),
+    ]);
+
+    columns
+}
+
+/// Display the tags and digests running for each image in the specified format
+///
+/// # Arguments
+///
+/// * `entries` - The skew entries to display
+/// * `output_format` - Format to display the entries in
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_skew(
+    entries: &[SkewEntry],
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(entries, output_format, skew_columns)
+}
+
+/// Columns shown for skew entries by the normal and wide formats
+fn skew_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![
+        ColumnSpec::field("IMAGE", "image").styled("Fy"),
+        ColumnSpec::field("VERSION", "version"),
+        ColumnSpec::field("DIGEST", "digest"),
+        ColumnSpec::field("PODS", "pods"),
     ];
 
     if matches!(output_format, OutputFormat::Wide) {
-        header_cells.push(Cell::new("REGISTRY"));
+        columns.extend([
+            ColumnSpec::field("CONTAINERS", "containers"),
+            ColumnSpec::field("VARIANTS", "variants"),
+        ]);
     }
 
-    header_cells.extend_from_slice(&[Cell::new("IMAGE"), Cell::new("VERSION")]);
+    columns.push(ColumnSpec::field("NAMESPACES", "namespaces"));
+    columns
+}
+
+/// Display label and annotation violations in the specified format
+///
+/// # Arguments
+///
+/// * `violations` - The violations to display
+/// * `output_format` - Format to display the violations in
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_label_violations(
+    violations: &[LabelViolation],
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(violations, output_format, label_violation_columns)
+}
+
+/// Columns shown for label and annotation violations by the normal and wide formats
+fn label_violation_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![
+        ColumnSpec::field("KIND", "kind"),
+        ColumnSpec::field("NAMESPACE", "namespace"),
+        ColumnSpec::field("NAME", "name"),
+    ];
 
     if matches!(output_format, OutputFormat::Wide) {
-        header_cells.extend_from_slice(&[Cell::new("DIGEST"), Cell::new("NODE")]);
+        columns.push(ColumnSpec::field("FIELD", "field"));
+    }
+
+    columns.extend([
+        ColumnSpec::field("KEY", "key").styled("Fy"),
+        ColumnSpec::field("ISSUE", "issue").styled("Fr"),
+        ColumnSpec::field("VALUE", "value"),
+    ]);
+
+    if matches!(output_format, OutputFormat::Wide) {
+        columns.push(ColumnSpec::field("EXPECTED", "expected"));
+    }
+
+    columns
+}
+
+/// Display spec-versus-running image drift in the specified format
+///
+/// # Arguments
+///
+/// * `rows` - The drift rows to display
+/// * `output_format` - Format to display the rows in
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_drift(
+    rows: &[ImageDrift],
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(rows, output_format, drift_columns)
+}
+
+/// Columns shown for drift rows by the normal and wide formats
+fn drift_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![
+        ColumnSpec::field("POD", "pod_name"),
+        ColumnSpec::field("NAMESPACE", "namespace"),
+        ColumnSpec::field("CONTAINER", "container_name"),
+    ];
+
+    if output_format.includes_container_type() {
+        columns.push(ColumnSpec::field("CONTAINER TYPE", "container_type"));
+        columns.push(ColumnSpec::field("OWNER", "owner"));
     }
 
-    Row::new(header_cells)
+    columns.extend([
+        ColumnSpec::field("SPEC IMAGE", "spec_image"),
+        ColumnSpec::field("RUNNING IMAGE", "running_image"),
+        ColumnSpec::field("DIGEST", "digest"),
+        ColumnSpec::field("MISMATCH", "image_mismatch").styled("Fr"),
+        ColumnSpec::field("REPUSHED", "tag_repushed").styled("Fr"),
+    ]);
+
+    columns
+}
+
+/// Display labels or annotations in the specified format
+///
+/// # Arguments
+///
+/// * `entries` - The label or annotation entries to display
+/// * `resource` - The kind of objects the entries were read from, used for the name column
+/// * `output_format` - Format to display the entries in
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_metadata(
+    entries: &[MetadataEntry],
+    resource: MetadataResource,
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(entries, output_format, |_| metadata_columns(resource))
+}
+
+/// Columns shown for label and annotation entries by the normal and wide formats
+fn metadata_columns(resource: MetadataResource) -> Vec<ColumnSpec> {
+    let mut columns = match resource {
+        MetadataResource::Pods => vec![
+            ColumnSpec::field("POD", "name"),
+            ColumnSpec::field("NAMESPACE", "namespace"),
+        ],
+        MetadataResource::Namespaces => vec![ColumnSpec::field("NAMESPACE", "name")],
+        MetadataResource::Nodes => vec![ColumnSpec::field("NODE", "name")],
+    };
+
+    columns.extend([
+        ColumnSpec::field("KEY", "key").styled("Fy"),
+        ColumnSpec::field("VALUE", "value"),
+    ]);
+    columns
 }
 
-/// Create a row for a single pod image
+/// Display the probes and status of containers in the specified format
 ///
 /// # Arguments
 ///
-/// * `image` - The pod image to create a row for
-/// * `output_format` - Format to use for displaying the image
+/// * `rows` - The container health rows to display
+/// * `output_format` - Format to display the rows in
 ///
 /// # Returns
 ///
-/// * `Result<Row>` - A row containing the image information or error
-fn create_image_row(
-    image: &PodImage,
+/// * `Result<()>` - Success or error
+pub fn display_health(
+    rows: &[ContainerHealth],
     output_format: &OutputFormat,
-) -> Result<Row, TableDisplayError> {
-    let mut cells = vec![
-        Cell::new(&image.pod_name),
-        Cell::new(&image.namespace),
-        Cell::new(&image.container_name),
+) -> Result<(), TableDisplayError> {
+    display_records(rows, output_format, health_columns)
+}
+
+/// Columns shown for container health rows by the normal and wide formats
+fn health_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![
+        ColumnSpec::field("POD", "pod_name"),
+        ColumnSpec::field("NAMESPACE", "namespace"),
+        ColumnSpec::field("CONTAINER", "container_name"),
     ];
 
+    if output_format.includes_container_type() {
+        columns.push(ColumnSpec::field("CONTAINER TYPE", "container_type"));
+    }
+
+    columns.extend([
+        ColumnSpec::field("READY", "ready"),
+        ColumnSpec::field("RESTARTS", "restart_count"),
+        ColumnSpec::field("STATE", "state"),
+        ColumnSpec::field("LAST TERMINATION", "last_termination_reason"),
+    ]);
+
     if matches!(output_format, OutputFormat::Wide) {
-        cells.push(Cell::new(&image.registry).style_spec("Fy"));
+        columns.push(ColumnSpec::field("EXIT CODE", "last_exit_code"));
     }
 
-    cells.extend_from_slice(&[
-        Cell::new(&image.image_name),
-        Cell::new(&image.image_version),
+    columns.extend([
+        ColumnSpec::field("LIVENESS", "liveness"),
+        ColumnSpec::field("READINESS", "readiness"),
+        ColumnSpec::field("STARTUP", "startup"),
+        ColumnSpec::field("ISSUES", "issues").styled("Fr"),
     ]);
 
     if matches!(output_format, OutputFormat::Wide) {
-        cells.extend_from_slice(&[Cell::new(&image.digest), Cell::new(&image.node_name)]);
+        columns.push(ColumnSpec::field("NODE", "node_name"));
     }
 
-    Ok(Row::new(cells))
+    columns
 }
 
-/// Strips the registry prefix from an image name if it exists
+/// Display an image summary in the specified format
 ///
 /// # Arguments
 ///
-/// * `image_name` - The full image name that may include a registry prefix
-/// * `registry` - The registry to strip from the image name
+/// * `rows` - The summary rows to display
+/// * `group_by` - The field the rows are grouped by, used as the first column header
+/// * `output_format` - Format to display the rows in
 ///
 /// # Returns
 ///
-/// The image name without the registry prefix
-pub fn strip_registry(image_name: &str, registry: &str) -> String {
-    image_name
-        .strip_prefix(&format!("{}/", registry))
-        .unwrap_or(image_name)
-        .to_string()
+/// * `Result<()>` - Success or error
+pub fn display_image_summary(
+    rows: &[ImageSummary],
+    group_by: GroupBy,
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(rows, output_format, |format| {
+        image_summary_columns(group_by, format)
+    })
 }
 
-/// Display a list of container image registries in the specified format
+/// Display image summaries of several clusters, with a leading CLUSTER column
 ///
 /// # Arguments
 ///
-/// * `registries` - List of registry URLs to display
-/// * `_output_format` - Format to display the registries in (currently unused)
+/// * `rows` - The summary rows of every cluster
+/// * `group_by` - The field the rows are grouped by
+/// * `output_format` - Format to display the rows in
 ///
 /// # Returns
 ///
 /// * `Result<()>` - Success or error
-pub fn display_registries(
-    registries: &[String],
-    _output_format: &OutputFormat,
+pub fn display_cluster_image_summary(
+    rows: &[ClusterRecord<ImageSummary>],
+    group_by: GroupBy,
+    output_format: &OutputFormat,
 ) -> Result<(), TableDisplayError> {
-    if registries.is_empty() {
-        warn!("No registries found");
-        return Ok(());
+    display_records(rows, output_format, |format| {
+        with_cluster_column(image_summary_columns(group_by, format))
+    })
+}
+
+/// Columns shown for image summary rows by the normal and wide formats
+fn image_summary_columns(group_by: GroupBy, output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let header = group_by.to_string().to_uppercase();
+    let mut columns = vec![
+        ColumnSpec::field(&header, "group").styled("Fy"),
+        ColumnSpec::field("PODS", "pods"),
+        ColumnSpec::field("CONTAINERS", "containers"),
+        ColumnSpec::field("TAGS", "tags"),
+        ColumnSpec::field("DIGESTS", "digests"),
+    ];
+
+    if matches!(output_format, OutputFormat::Wide) {
+        columns.push(ColumnSpec::field("VERSIONS", "versions"));
     }
 
-    let mut table = create_table()?;
-    table.add_row(Row::new(vec![Cell::new("CONTAINER REGISTRY")]));
+    columns
+}
 
-    for registry in registries {
-        table.add_row(Row::new(vec![Cell::new(registry)]));
+/// Display a batch of watched image events as soon as they arrive
+///
+/// Tables and delimited formats only print their header with the first batch,
+/// JSON is printed as one object per line and YAML as one document per event.
+///
+/// # Arguments
+///
+/// * `events` - The image events to display
+/// * `output_format` - Format to display the events in
+/// * `with_header` - Whether this is the first batch and the header should be printed
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_image_events(
+    events: &[ImageEvent],
+    output_format: &OutputFormat,
+    with_header: bool,
+) -> Result<(), TableDisplayError> {
+    match output_format {
+        OutputFormat::Normal | OutputFormat::Wide => {
+            create_record_table(events, &image_event_columns(output_format), with_header)?
+                .printstd();
+        }
+        OutputFormat::CustomColumns(columns) => {
+            create_record_table(events, columns, with_header)?.printstd();
+        }
+        _ => print!(
+            "{}",
+            render_image_events(events, output_format, with_header)?
+        ),
     }
+    std::io::stdout()
+        .flush()
+        .map_err(|e| TableDisplayError::new(&e.to_string()))
+}
 
-    table.printstd();
-    Ok(())
+/// Render a batch of watched image events without printing them
+///
+/// # Arguments
+///
+/// * `events` - The image events to render
+/// * `output_format` - Format to use for rendering the events
+/// * `with_header` - Whether to include the header of table and delimited formats
+///
+/// # Returns
+///
+/// * `Result<String>` - The rendered output or error
+pub fn render_image_events(
+    events: &[ImageEvent],
+    output_format: &OutputFormat,
+    with_header: bool,
+) -> Result<String, TableDisplayError> {
+    match output_format {
+        OutputFormat::Normal | OutputFormat::Wide => {
+            Ok(
+                create_record_table(events, &image_event_columns(output_format), with_header)?
+                    .to_string(),
+            )
+        }
+        OutputFormat::CustomColumns(columns) => {
+            Ok(create_record_table(events, columns, with_header)?.to_string())
+        }
+        OutputFormat::Json => events
+            .iter()
+            .try_fold(String::new(), |mut rendered, event| {
+                let line = serde_json::to_string(event)
+                    .map_err(|e| TableDisplayError::new(&e.to_string()))?;
+                rendered.push_str(&line);
+                rendered.push('\n');
+                Ok(rendered)
+            }),
+        OutputFormat::Yaml => events
+            .iter()
+            .try_fold(String::new(), |mut rendered, event| {
+                let document = serde_yaml::to_string(event)
+                    .map_err(|e| TableDisplayError::new(&e.to_string()))?;
+                rendered.push_str("---\n");
+                rendered.push_str(&document);
+                Ok(rendered)
+            }),
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            render_delimited(events, output_format, with_header)
+        }
+        _ => render_records(events, output_format, image_event_columns),
+    }
+}
+
+/// Columns shown for watched image events by the normal and wide formats
+fn image_event_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![ColumnSpec::field("CHANGE", "change").styled("Fc")];
+    columns.extend(pod_image_columns(output_format));
+    columns.push(ColumnSpec::field("PREVIOUS", "previous"));
+    columns
+}
+
+/// Display the differences between two image snapshots in the specified format
+///
+/// # Arguments
+///
+/// * `changes` - The changed containers to display
+/// * `output_format` - Format to display the changes in
+///
+/// # Returns
+///
+/// * `Result<()>` - Success or error
+pub fn display_snapshot_diff(
+    changes: &[SnapshotChange],
+    output_format: &OutputFormat,
+) -> Result<(), TableDisplayError> {
+    display_records(changes, output_format, snapshot_diff_columns)
+}
+
+/// Columns shown for snapshot differences by the normal and wide formats
+fn snapshot_diff_columns(output_format: &OutputFormat) -> Vec<ColumnSpec> {
+    let mut columns = vec![
+        ColumnSpec::field("CHANGE", "change").styled("Fc"),
+        ColumnSpec::field("NAMESPACE", "namespace"),
+        ColumnSpec::field("WORKLOAD", "workload"),
+        ColumnSpec::field("CONTAINER", "container_name"),
+    ];
+
+    if output_format.includes_container_type() {
+        columns.push(ColumnSpec::field("CONTAINER TYPE", "container_type"));
+    }
+
+    columns.extend([
+        ColumnSpec::field("BEFORE", "before").styled("Fr"),
+        ColumnSpec::field("AFTER", "after").styled("Fg"),
+    ]);
+
+    columns
 }
@@ -0,0 +1,100 @@
+use crate::utils::TableDisplayError;
+use crate::OutputFormat;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Render a list of records in one of the machine-readable output formats
+///
+/// # Arguments
+///
+/// * `records` - The records to render
+/// * `output_format` - One of the json, yaml, csv or tsv formats
+///
+/// # Returns
+///
+/// * `Result<String>` - The rendered output or error
+pub fn render_structured<T: Serialize>(
+    records: &[T],
+    output_format: &OutputFormat,
+) -> Result<String, TableDisplayError> {
+    match output_format {
+        OutputFormat::Json => {
+            let mut rendered = serde_json::to_string_pretty(records)
+                .map_err(|e| TableDisplayError::new(&e.to_string()))?;
+            rendered.push('\n');
+            Ok(rendered)
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_string(records).map_err(|e| TableDisplayError::new(&e.to_string()))
+        }
+        OutputFormat::Csv => render_delimited(records, b','),
+        OutputFormat::Tsv => render_delimited(records, b'\t'),
+        other => Err(TableDisplayError::new(&format!(
+            "{} is not a machine-readable output format",
+            other
+        ))),
+    }
+}
+
+/// Render records as delimiter-separated values with a header row of field names
+///
+/// # Arguments
+///
+/// * `records` - The records to render, each serializing to a flat object
+/// * `delimiter` - The field delimiter to use
+///
+/// # Returns
+///
+/// * `Result<String>` - The rendered output or error
+fn render_delimited<T: Serialize>(
+    records: &[T],
+    delimiter: u8,
+) -> Result<String, TableDisplayError> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+
+    let mut header_written = false;
+    for record in records {
+        let value =
+            serde_json::to_value(record).map_err(|e| TableDisplayError::new(&e.to_string()))?;
+        let Value::Object(fields) = value else {
+            return Err(TableDisplayError::new(
+                "Only records with named fields can be rendered as delimited values",
+            ));
+        };
+
+        if !header_written {
+            writer
+                .write_record(fields.keys())
+                .map_err(|e| TableDisplayError::new(&e.to_string()))?;
+            header_written = true;
+        }
+
+        writer
+            .write_record(fields.values().map(value_to_cell))
+            .map_err(|e| TableDisplayError::new(&e.to_string()))?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| TableDisplayError::new(&e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| TableDisplayError::new(&e.to_string()))
+}
+
+/// Convert a JSON value into a single delimited-output cell
+///
+/// Strings are written verbatim, lists are joined with commas and nested
+/// objects are written as compact JSON.
+pub(crate) fn value_to_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(value_to_cell)
+            .collect::<Vec<_>>()
+            .join(","),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,139 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A name filter that matches exactly, or as a shell-style glob when it contains `*`, `?` or `[...]`
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    /// The pattern as written by the user
+    raw: String,
+    /// The compiled glob, or `None` for an exact match
+    glob: Option<Regex>,
+}
+
+impl Pattern {
+    /// Check if the pattern uses glob syntax rather than an exact name
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the pattern contains glob wildcards
+    pub fn is_glob(&self) -> bool {
+        self.glob.is_some()
+    }
+
+    /// Get the pattern as written by the user
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// Check if a value matches the pattern
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to test
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - True if the whole value matches
+    pub fn matches(&self, value: &str) -> bool {
+        match &self.glob {
+            Some(glob) => glob.is_match(value),
+            None => self.raw == value,
+        }
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for Pattern {}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let glob = if s.contains(['*', '?', '[']) {
+            let regex = Regex::new(&glob_to_regex(s)).map_err(|e| {
+                // Only keep the reason; the rest of the message quotes the translated regex
+                let error = e.to_string();
+                let reason = error.lines().last().unwrap_or_default();
+                format!(
+                    "invalid glob '{}': {}",
+                    s,
+                    reason.trim_start_matches("error: ")
+                )
+            })?;
+            Some(regex)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            raw: s.to_string(),
+            glob,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+/// Translate a shell-style glob into an anchored regular expression
+///
+/// `*` matches any run of characters (including `.` and `/`), `?` matches a
+/// single character and `[...]`/`[!...]` match a character class. A `[`
+/// without a closing `]` is matched literally.
+fn glob_to_regex(glob: &str) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::from("^");
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => match chars[i + 1..].iter().position(|&c| c == ']') {
+                Some(offset) if offset > 0 => {
+                    let class: String = chars[i + 1..i + 1 + offset].iter().collect();
+                    regex.push('[');
+                    let class = match class.strip_prefix('!') {
+                        Some(negated) => {
+                            regex.push('^');
+                            negated.to_string()
+                        }
+                        None => class,
+                    };
+                    for c in class.chars() {
+                        if matches!(c, '\\' | '[' | ']' | '^' | '&' | '~') {
+                            regex.push('\\');
+                        }
+                        regex.push(c);
+                    }
+                    regex.push(']');
+                    i += offset + 1;
+                }
+                _ => regex.push_str(r"\["),
+            },
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    regex.push('$');
+    regex
+}
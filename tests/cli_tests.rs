@@ -1,11 +1,18 @@
 use clap::Parser;
-use kelper::{Args, Commands, GetImages, OutputFormat};
+use kelper::{
+    Args, AuditReport, CheckPolicy, ClientOptions, Commands, ContainerTypeFilter, ContextSelection,
+    GetImages, GroupBy, LabelTarget, MetadataField, MetadataResource, OutputFormat, Pattern,
+    ProviderKind, RegistrySource, SnapshotAction, DEFAULT_RESTART_THRESHOLD,
+};
+use std::path::PathBuf;
 
 #[test]
 fn test_cli_parse_get_images_default() {
     let args = Args::parse_from(["kelper", "get", "images"]);
 
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Images {
         namespace,
         node,
@@ -13,13 +20,13 @@ fn test_cli_parse_get_images_default() {
         registry,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "default");
         assert!(node.is_none());
         assert!(pod.is_none());
-        assert!(registry.is_none());
+        assert!(registry.is_empty());
         assert!(!all_namespaces);
         assert_eq!(output, OutputFormat::Normal);
     } else {
@@ -30,7 +37,9 @@ fn test_cli_parse_get_images_default() {
 #[test]
 fn test_cli_parse_get_images_namespace() {
     let args = Args::parse_from(["kelper", "get", "images", "--namespace", "test-ns"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Images {
         namespace,
         node,
@@ -38,13 +47,13 @@ fn test_cli_parse_get_images_namespace() {
         registry,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "test-ns");
         assert!(node.is_none());
         assert!(pod.is_none());
-        assert!(registry.is_none());
+        assert!(registry.is_empty());
         assert!(!all_namespaces);
         assert_eq!(output, OutputFormat::Normal);
     } else {
@@ -55,7 +64,9 @@ fn test_cli_parse_get_images_namespace() {
 #[test]
 fn test_cli_parse_get_images_all_namespaces() {
     let args = Args::parse_from(["kelper", "get", "images", "--all-namespaces"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Images {
         namespace,
         node,
@@ -63,14 +74,14 @@ fn test_cli_parse_get_images_all_namespaces() {
         registry,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         // namespace should still be default, but all_namespaces flag should be true
         assert_eq!(namespace, "default");
         assert!(node.is_none());
         assert!(pod.is_none());
-        assert!(registry.is_none());
+        assert!(registry.is_empty());
         assert!(all_namespaces);
         assert_eq!(output, OutputFormat::Normal);
     } else {
@@ -82,7 +93,9 @@ fn test_cli_parse_get_images_all_namespaces() {
 fn test_cli_parse_get_images_all_namespaces_short() {
     // Test the short flag version (-A)
     let args = Args::parse_from(["kelper", "get", "images", "-A"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Images {
         namespace,
         node,
@@ -90,13 +103,13 @@ fn test_cli_parse_get_images_all_namespaces_short() {
         registry,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "default");
         assert!(node.is_none());
         assert!(pod.is_none());
-        assert!(registry.is_none());
+        assert!(registry.is_empty());
         assert!(all_namespaces);
         assert_eq!(output, OutputFormat::Normal);
     } else {
@@ -108,7 +121,9 @@ fn test_cli_parse_get_images_all_namespaces_short() {
 fn test_cli_parse_get_images_node() {
     // Test combining node filter
     let args = Args::parse_from(["kelper", "get", "images", "--node", "worker1"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Images {
         namespace,
         node,
@@ -116,13 +131,13 @@ fn test_cli_parse_get_images_node() {
         registry,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "default");
         assert_eq!(node, Some("worker1".to_string()));
         assert!(pod.is_none());
-        assert!(registry.is_none());
+        assert!(registry.is_empty());
         assert!(!all_namespaces);
         assert_eq!(output, OutputFormat::Normal);
     } else {
@@ -141,7 +156,9 @@ fn test_cli_parse_get_images_pod_and_all_namespaces() {
         "nginx-pod",
         "--all-namespaces",
     ]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Images {
         namespace,
         node,
@@ -149,13 +166,13 @@ fn test_cli_parse_get_images_pod_and_all_namespaces() {
         registry,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "default");
         assert!(node.is_none());
-        assert_eq!(pod, Some("nginx-pod".to_string()));
-        assert!(registry.is_none());
+        assert_eq!(pod, Some("nginx-pod".parse::<Pattern>().unwrap()));
+        assert!(registry.is_empty());
         assert!(all_namespaces);
         assert_eq!(output, OutputFormat::Normal);
     } else {
@@ -167,7 +184,9 @@ fn test_cli_parse_get_images_pod_and_all_namespaces() {
 fn test_cli_parse_get_images_wide_output() {
     // Test wide output format
     let args = Args::parse_from(["kelper", "get", "images", "-o", "wide"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Images {
         namespace,
         node,
@@ -175,13 +194,13 @@ fn test_cli_parse_get_images_wide_output() {
         registry,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "default");
         assert!(node.is_none());
         assert!(pod.is_none());
-        assert!(registry.is_none());
+        assert!(registry.is_empty());
         assert!(!all_namespaces);
         assert_eq!(output, OutputFormat::Wide);
     } else {
@@ -193,7 +212,9 @@ fn test_cli_parse_get_images_wide_output() {
 fn test_cli_parse_get_images_wide_output_long() {
     // Test wide output format with long flag
     let args = Args::parse_from(["kelper", "get", "images", "--output", "wide"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Images {
         namespace,
         node,
@@ -201,13 +222,13 @@ fn test_cli_parse_get_images_wide_output_long() {
         registry,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "default");
         assert!(node.is_none());
         assert!(pod.is_none());
-        assert!(registry.is_none());
+        assert!(registry.is_empty());
         assert!(!all_namespaces);
         assert_eq!(output, OutputFormat::Wide);
     } else {
@@ -257,12 +278,14 @@ fn test_cli_parse_get_pods_namespace_and_all_namespaces_conflict() {
 #[test]
 fn test_cli_parse_get_registries_default() {
     let args = Args::parse_from(["kelper", "get", "registries"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Registries {
         namespace,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "default");
@@ -276,12 +299,14 @@ fn test_cli_parse_get_registries_default() {
 #[test]
 fn test_cli_parse_get_registries_namespace() {
     let args = Args::parse_from(["kelper", "get", "registries", "--namespace", "test-ns"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Registries {
         namespace,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "test-ns");
@@ -295,12 +320,14 @@ fn test_cli_parse_get_registries_namespace() {
 #[test]
 fn test_cli_parse_get_registries_all_namespaces() {
     let args = Args::parse_from(["kelper", "get", "registries", "--all-namespaces"]);
-    let Commands::Get { resource } = args.command;
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
     if let GetImages::Registries {
         namespace,
         all_namespaces,
         output,
-        kubeconfig: _,
+        ..
     } = resource
     {
         assert_eq!(namespace, "default");
@@ -326,3 +353,775 @@ fn test_cli_parse_get_registries_namespace_and_all_namespaces_conflict() {
         "Expected parser to reject conflicting arguments for 'get registries'"
     );
 }
+
+#[test]
+fn test_cli_client_options_default() {
+    let args = Args::parse_from(["kelper", "get", "images"]);
+    assert_eq!(args.client_options(), ClientOptions::default());
+}
+
+#[test]
+fn test_cli_client_options_global_flags() {
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "images",
+        "--kubeconfig",
+        "/tmp/ci-kubeconfig",
+        "--context",
+        "staging",
+        "--cluster",
+        "staging-eu",
+        "--user",
+        "ci-bot",
+        "--as",
+        "system:serviceaccount:ci:auditor",
+    ]);
+    let options = args.client_options();
+    assert_eq!(
+        options.kubeconfig,
+        Some(PathBuf::from("/tmp/ci-kubeconfig"))
+    );
+    assert_eq!(options.context.as_deref(), Some("staging"));
+    assert_eq!(options.cluster.as_deref(), Some("staging-eu"));
+    assert_eq!(options.user.as_deref(), Some("ci-bot"));
+    assert_eq!(
+        options.impersonate.as_deref(),
+        Some("system:serviceaccount:ci:auditor")
+    );
+}
+
+#[test]
+fn test_cli_client_options_global_flags_before_subcommand() {
+    let args = Args::parse_from([
+        "kelper",
+        "--context",
+        "prod",
+        "--kubeconfig",
+        "/tmp/global-kubeconfig",
+        "get",
+        "registries",
+    ]);
+    let options = args.client_options();
+    assert_eq!(
+        options.kubeconfig,
+        Some(PathBuf::from("/tmp/global-kubeconfig"))
+    );
+    assert_eq!(options.context.as_deref(), Some("prod"));
+    assert!(options.impersonate.is_none());
+}
+
+#[test]
+fn test_cli_client_options_chunk_size() {
+    let args = Args::parse_from(["kelper", "get", "images"]);
+    assert_eq!(args.client_options().chunk_size, 500);
+
+    let args = Args::parse_from(["kelper", "--chunk-size", "0", "get", "images", "-A"]);
+    assert_eq!(args.client_options().chunk_size, 0);
+
+    let args = Args::parse_from(["kelper", "audit", "tags", "--chunk-size", "100"]);
+    assert_eq!(args.client_options().chunk_size, 100);
+}
+
+#[test]
+fn test_cli_parse_get_images_custom_columns_output() {
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "images",
+        "-o",
+        "custom-columns=POD:.pod_name,IMG:.image_name",
+    ]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images { output, .. } = resource {
+        let OutputFormat::CustomColumns(columns) = output else {
+            panic!("Expected custom-columns output format");
+        };
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns[0].header, "POD");
+        assert_eq!(columns[1].header, "IMG");
+        assert_eq!(columns[1].path.to_string(), ".image_name");
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+}
+
+#[test]
+fn test_cli_parse_get_images_jsonpath_output() {
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "images",
+        "-o",
+        "jsonpath={.items[*].image_name}",
+    ]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images { output, .. } = resource {
+        assert!(matches!(output, OutputFormat::JsonPath(_)));
+        assert_eq!(output.to_string(), "jsonpath={.items[*].image_name}");
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+}
+
+#[test]
+fn test_cli_parse_get_images_container_type() {
+    let args = Args::parse_from(["kelper", "get", "images"]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images { container_type, .. } = resource {
+        assert_eq!(container_type, ContainerTypeFilter::All);
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+
+    let args = Args::parse_from(["kelper", "get", "images", "--container-type", "init"]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images { container_type, .. } = resource {
+        assert_eq!(container_type, ContainerTypeFilter::Init);
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+
+    let result = Args::try_parse_from(["kelper", "get", "images", "--container-type", "sidecar"]);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_cli_parse_get_registries_source() {
+    let args = Args::parse_from(["kelper", "get", "registries"]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Registries { source, .. } = resource {
+        assert_eq!(source, RegistrySource::Workloads);
+    } else {
+        panic!("Expected GetImages::Registries variant");
+    }
+
+    let args = Args::parse_from(["kelper", "get", "registries", "-A", "--source", "all"]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Registries {
+        source,
+        all_namespaces,
+        ..
+    } = resource
+    {
+        assert_eq!(source, RegistrySource::All);
+        assert!(all_namespaces);
+    } else {
+        panic!("Expected GetImages::Registries variant");
+    }
+}
+
+#[test]
+fn test_cli_parse_get_images_selectors() {
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "images",
+        "-l",
+        "app=payments,tier=backend",
+        "--field-selector",
+        "status.phase=Running",
+        "--node",
+        "worker1",
+    ]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images {
+        selector,
+        field_selector,
+        node,
+        ..
+    } = resource
+    {
+        assert_eq!(selector.as_deref(), Some("app=payments,tier=backend"));
+        assert_eq!(field_selector.as_deref(), Some("status.phase=Running"));
+        assert_eq!(node.as_deref(), Some("worker1"));
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+}
+
+#[test]
+fn test_cli_parse_get_images_name_filters() {
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "images",
+        "--pod",
+        "api-*",
+        "--pod-regex",
+        "^api-[a-z0-9]+$",
+        "--image",
+        "linkerd/*",
+        "--tag",
+        "edge-*",
+    ]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images {
+        pod,
+        pod_regex,
+        image,
+        tag,
+        ..
+    } = resource
+    {
+        assert_eq!(pod, Some("api-*".parse::<Pattern>().unwrap()));
+        assert!(pod_regex.unwrap().is_match("api-7d9f8b"));
+        assert_eq!(image, Some("linkerd/*".parse::<Pattern>().unwrap()));
+        assert_eq!(tag, Some("edge-*".parse::<Pattern>().unwrap()));
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+
+    let result = Args::try_parse_from(["kelper", "get", "images", "--pod-regex", "api-("]);
+    assert!(result.is_err(), "Expected parser to reject invalid regex");
+}
+
+#[test]
+fn test_cli_parse_get_images_group_by() {
+    let args = Args::parse_from(["kelper", "get", "images", "-A", "--group-by", "registry"]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images { group_by, .. } = resource {
+        assert_eq!(group_by, Some(GroupBy::Registry));
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+
+    assert!(Args::try_parse_from(["kelper", "get", "images", "--group-by", "cluster"]).is_err());
+}
+
+#[test]
+fn test_cli_parse_get_images_watch() {
+    let args = Args::parse_from(["kelper", "get", "images", "-A", "-w", "-l", "app=api"]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images {
+        watch, selector, ..
+    } = resource
+    {
+        assert!(watch);
+        assert_eq!(selector.as_deref(), Some("app=api"));
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+
+    let result =
+        Args::try_parse_from(["kelper", "get", "images", "--watch", "--group-by", "image"]);
+    assert!(
+        result.is_err(),
+        "Expected --watch to conflict with --group-by"
+    );
+}
+
+#[test]
+fn test_cli_parse_get_images_multiple_registries() {
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "images",
+        "-R",
+        "*.dkr.ecr.*.amazonaws.com",
+        "-R",
+        "ghcr.io",
+        "--exclude-registry",
+        "mirror.corp",
+        "--provider",
+        "ecr",
+        "--provider",
+        "artifact-registry",
+    ]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Images {
+        registry,
+        exclude_registry,
+        provider,
+        ..
+    } = resource
+    {
+        assert_eq!(
+            registry,
+            vec![
+                "*.dkr.ecr.*.amazonaws.com".parse::<Pattern>().unwrap(),
+                "ghcr.io".parse::<Pattern>().unwrap()
+            ]
+        );
+        assert_eq!(
+            exclude_registry,
+            vec!["mirror.corp".parse::<Pattern>().unwrap()]
+        );
+        assert_eq!(
+            provider,
+            vec![ProviderKind::Ecr, ProviderKind::ArtifactRegistry]
+        );
+    } else {
+        panic!("Expected GetImages::Images variant");
+    }
+}
+
+#[test]
+fn test_cli_rejects_invalid_patterns() {
+    for (flag, value) in [
+        ("--pod", "api-[z-a]"),
+        ("--registry", "[z-a].example.com"),
+        ("--image", "linkerd/[9-0]*"),
+    ] {
+        let error = Args::try_parse_from(["kelper", "get", "images", flag, value]).unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation);
+        assert!(error.to_string().contains(value), "{}", error);
+    }
+
+    assert!(
+        Args::try_parse_from(["kelper", "check", "registries", "--allow", "ghcr.io/[z-a]"])
+            .is_err()
+    );
+}
+
+#[test]
+fn test_cli_parse_check_registries() {
+    let args = Args::parse_from([
+        "kelper",
+        "check",
+        "registries",
+        "-A",
+        "--allow",
+        "ghcr.io/ourorg",
+        "--allow",
+        "registry.k8s.io",
+        "--policy",
+        "policy.yaml",
+    ]);
+    let Commands::Check { policy } = args.command else {
+        panic!("Expected Commands::Check variant");
+    };
+    let CheckPolicy::Registries {
+        all_namespaces,
+        allow,
+        policy,
+        output,
+        ..
+    } = policy;
+    assert!(all_namespaces);
+    assert_eq!(
+        allow,
+        vec![
+            "ghcr.io/ourorg".parse::<Pattern>().unwrap(),
+            "registry.k8s.io".parse::<Pattern>().unwrap()
+        ]
+    );
+    assert_eq!(policy, Some(PathBuf::from("policy.yaml")));
+    assert_eq!(output, OutputFormat::Normal);
+
+    let result = Args::try_parse_from(["kelper", "check", "registries"]);
+    assert!(
+        result.is_err(),
+        "Expected --allow or --policy to be required"
+    );
+}
+
+#[test]
+fn test_cli_parse_audit_tags() {
+    let args = Args::parse_from(["kelper", "audit", "tags", "-A", "-o", "json"]);
+    let Commands::Audit { report } = args.command else {
+        panic!("Expected Commands::Audit variant");
+    };
+    let AuditReport::Tags {
+        all_namespaces,
+        selector,
+        output,
+        ..
+    } = report
+    else {
+        panic!("Expected AuditReport::Tags variant");
+    };
+    assert!(all_namespaces);
+    assert!(selector.is_none());
+    assert_eq!(output, OutputFormat::Json);
+}
+
+#[test]
+fn test_cli_parse_audit_skew() {
+    let args = Args::parse_from([
+        "kelper",
+        "audit",
+        "skew",
+        "-A",
+        "--image",
+        "linkerd/*",
+        "--all",
+    ]);
+    let Commands::Audit { report } = args.command else {
+        panic!("Expected Commands::Audit variant");
+    };
+    let AuditReport::Skew {
+        all_namespaces,
+        image,
+        all,
+        output,
+        ..
+    } = report
+    else {
+        panic!("Expected AuditReport::Skew variant");
+    };
+    assert!(all_namespaces);
+    assert_eq!(image, Some("linkerd/*".parse::<Pattern>().unwrap()));
+    assert!(all);
+    assert_eq!(output, OutputFormat::Normal);
+}
+
+#[test]
+fn test_cli_parse_snapshot() {
+    let args = Args::parse_from(["kelper", "snapshot", "save", "-A", "-o", "before.json"]);
+    assert!(args.command.requires_cluster());
+    let Commands::Snapshot { action } = args.command else {
+        panic!("Expected Commands::Snapshot variant");
+    };
+    let SnapshotAction::Save {
+        all_namespaces,
+        output,
+        ..
+    } = action
+    else {
+        panic!("Expected SnapshotAction::Save variant");
+    };
+    assert!(all_namespaces);
+    assert_eq!(output, Some(PathBuf::from("before.json")));
+
+    let args = Args::parse_from([
+        "kelper", "snapshot", "diff", "a.json", "b.json", "-o", "json",
+    ]);
+    assert!(!args.command.requires_cluster());
+    let Commands::Snapshot { action } = args.command else {
+        panic!("Expected Commands::Snapshot variant");
+    };
+    let SnapshotAction::Diff {
+        before,
+        after,
+        against_cluster,
+        output,
+        ..
+    } = action
+    else {
+        panic!("Expected SnapshotAction::Diff variant");
+    };
+    assert_eq!(before, PathBuf::from("a.json"));
+    assert_eq!(after, Some(PathBuf::from("b.json")));
+    assert!(against_cluster.is_none());
+    assert_eq!(output, OutputFormat::Json);
+
+    let args = Args::parse_from([
+        "kelper",
+        "snapshot",
+        "diff",
+        "a.json",
+        "--against-cluster",
+        "prod",
+    ]);
+    assert!(!args.command.requires_cluster());
+
+    assert!(Args::try_parse_from(["kelper", "snapshot", "diff", "a.json"]).is_err());
+    assert!(Args::try_parse_from([
+        "kelper",
+        "snapshot",
+        "diff",
+        "a.json",
+        "b.json",
+        "--against-cluster",
+        "prod"
+    ])
+    .is_err());
+}
+
+#[test]
+fn test_cli_parse_contexts() {
+    let args = Args::parse_from(["kelper", "get", "images", "-A"]);
+    assert!(args.command.context_selection().is_none());
+
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "images",
+        "-A",
+        "--contexts",
+        "prod-eu,prod-us",
+        "--max-concurrency",
+        "4",
+    ]);
+    assert_eq!(
+        args.command.context_selection(),
+        Some(&ContextSelection {
+            contexts: vec!["prod-eu".to_string(), "prod-us".to_string()],
+            all_contexts: false,
+            max_concurrency: 4,
+        })
+    );
+
+    let args = Args::parse_from(["kelper", "get", "registries", "--all-contexts"]);
+    let selection = args.command.context_selection().unwrap();
+    assert!(selection.all_contexts);
+    assert_eq!(selection.max_concurrency, 8);
+
+    let args = Args::parse_from(["kelper", "get", "drift"]);
+    assert!(args.command.context_selection().is_none());
+
+    for invalid in [
+        vec![
+            "kelper",
+            "get",
+            "images",
+            "--contexts",
+            "a",
+            "--all-contexts",
+        ],
+        vec!["kelper", "get", "images", "--contexts", "a", "--watch"],
+        vec![
+            "kelper",
+            "get",
+            "images",
+            "--context",
+            "a",
+            "--contexts",
+            "b",
+        ],
+        vec![
+            "kelper",
+            "get",
+            "images",
+            "--all-contexts",
+            "--max-concurrency",
+            "0",
+        ],
+    ] {
+        assert!(Args::try_parse_from(&invalid).is_err(), "{:?}", invalid);
+    }
+}
+
+#[test]
+fn test_cli_parse_get_drift() {
+    let args = Args::parse_from(["kelper", "get", "drift", "-n", "payments", "--drifted"]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    if let GetImages::Drift {
+        namespace,
+        drifted,
+        container_type,
+        ..
+    } = resource
+    {
+        assert_eq!(namespace, "payments");
+        assert!(drifted);
+        assert_eq!(container_type, ContainerTypeFilter::All);
+    } else {
+        panic!("Expected GetImages::Drift variant");
+    }
+}
+
+#[test]
+fn test_cli_parse_normalize_flag() {
+    let args = Args::parse_from(["kelper", "get", "images"]);
+    assert!(args.normalize);
+    assert!(args.registry_resolver().unwrap().normalize);
+
+    let args = Args::parse_from(["kelper", "get", "registries", "--normalize=false"]);
+    assert!(!args.normalize);
+    assert!(!args.registry_resolver().unwrap().normalize);
+
+    let args = Args::parse_from(["kelper", "--normalize", "get", "registries"]);
+    assert!(args.normalize);
+
+    let args = Args::parse_from(["kelper", "get", "images", "--normalize=true"]);
+    assert!(args.normalize);
+}
+
+#[test]
+fn test_cli_parse_get_labels() {
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "labels",
+        "-A",
+        "-l",
+        "tier=backend",
+        "--key",
+        "app.kubernetes.io/*",
+        "-k",
+        "team",
+        "-o",
+        "json",
+    ]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    let query = resource.image_query();
+    assert!(query.all_namespaces);
+    assert_eq!(query.label_selector.as_deref(), Some("tier=backend"));
+    assert!(resource.context_selection().is_none());
+    let GetImages::Labels {
+        resource,
+        key,
+        output,
+        ..
+    } = resource
+    else {
+        panic!("Expected GetImages::Labels variant");
+    };
+    assert_eq!(resource, MetadataResource::Pods);
+    assert_eq!(
+        key,
+        vec![
+            "app.kubernetes.io/*".parse::<Pattern>().unwrap(),
+            "team".parse::<Pattern>().unwrap()
+        ]
+    );
+    assert_eq!(output, OutputFormat::Json);
+
+    let args = Args::parse_from(["kelper", "get", "annotations", "nodes", "-N", "worker-1"]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    assert_eq!(resource.image_query().node.as_deref(), Some("worker-1"));
+    let GetImages::Annotations { resource, key, .. } = resource else {
+        panic!("Expected GetImages::Annotations variant");
+    };
+    assert_eq!(resource, MetadataResource::Nodes);
+    assert!(key.is_empty());
+
+    assert!(Args::try_parse_from(["kelper", "get", "labels", "services"]).is_err());
+}
+
+#[test]
+fn test_cli_parse_audit_labels() {
+    let args = Args::parse_from([
+        "kelper",
+        "audit",
+        "labels",
+        "-A",
+        "--require",
+        "team",
+        "--require",
+        "app.kubernetes.io/name=^[a-z-]+$",
+        "--require-annotation",
+        "owner",
+        "--kind",
+        "pods,namespaces",
+    ]);
+    let Commands::Audit { report } = args.command else {
+        panic!("Expected Commands::Audit variant");
+    };
+    let AuditReport::Labels {
+        all_namespaces,
+        require,
+        require_annotation,
+        kind,
+        ..
+    } = report
+    else {
+        panic!("Expected AuditReport::Labels variant");
+    };
+    assert!(all_namespaces);
+    assert_eq!(require.len(), 2);
+    assert_eq!(require[0].key, "team");
+    assert!(require[0].value.is_none());
+    assert_eq!(require[1].key, "app.kubernetes.io/name");
+    assert_eq!(require[1].expected(), "^[a-z-]+$");
+    assert_eq!(require_annotation[0].field, MetadataField::Annotations);
+    assert_eq!(kind, vec![LabelTarget::Pods, LabelTarget::Namespaces]);
+
+    let args = Args::parse_from(["kelper", "audit", "labels", "--require-annotation", "owner"]);
+    let Commands::Audit {
+        report: AuditReport::Labels { require, kind, .. },
+    } = args.command
+    else {
+        panic!("Expected AuditReport::Labels variant");
+    };
+    assert!(require.is_empty());
+    assert_eq!(
+        kind,
+        vec![
+            LabelTarget::Pods,
+            LabelTarget::Workloads,
+            LabelTarget::Namespaces
+        ]
+    );
+
+    assert!(Args::try_parse_from(["kelper", "audit", "labels"]).is_err());
+    assert!(Args::try_parse_from(["kelper", "audit", "labels", "--require", "team=("]).is_err());
+    assert!(Args::try_parse_from(["kelper", "audit", "labels", "--require", "=x"]).is_err());
+}
+
+#[test]
+fn test_cli_parse_get_health() {
+    let args = Args::parse_from([
+        "kelper",
+        "get",
+        "health",
+        "-A",
+        "--pod",
+        "api-*",
+        "--registry",
+        "ghcr.io",
+        "--container-type",
+        "regular",
+        "--restart-threshold",
+        "3",
+        "--unhealthy",
+        "-o",
+        "wide",
+    ]);
+    let Commands::Get { resource } = args.command else {
+        panic!("Expected Commands::Get variant");
+    };
+    let query = resource.image_query();
+    assert!(query.all_namespaces);
+    assert_eq!(query.pod.as_ref().map(|p| p.as_str()), Some("api-*"));
+    assert_eq!(query.registries.len(), 1);
+    assert_eq!(query.container_type, ContainerTypeFilter::Regular);
+    assert!(resource.context_selection().is_none());
+    let GetImages::Health {
+        restart_threshold,
+        unhealthy,
+        output,
+        ..
+    } = resource
+    else {
+        panic!("Expected GetImages::Health variant");
+    };
+    assert_eq!(restart_threshold, 3);
+    assert!(unhealthy);
+    assert_eq!(output, OutputFormat::Wide);
+
+    let args = Args::parse_from(["kelper", "get", "health"]);
+    let Commands::Get {
+        resource:
+            GetImages::Health {
+                restart_threshold,
+                unhealthy,
+                ..
+            },
+    } = args.command
+    else {
+        panic!("Expected GetImages::Health variant");
+    };
+    assert_eq!(restart_threshold, DEFAULT_RESTART_THRESHOLD);
+    assert!(!unhealthy);
+}
@@ -0,0 +1,472 @@
+//! A minimal in-process Kubernetes API server serving fixed lists of objects
+
+#![allow(dead_code)] // each test crate uses a different part of the fake server
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use k8s_openapi::api::core::v1::{Container, Pod, PodSpec};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kelper::{ClientOptions, K8sClient};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Serves `GET` requests for the `default` namespace, named namespaced objects and
+/// paginated lists over plain HTTP, reducing objects to their metadata when asked to
+pub struct FakeApiServer {
+    /// Kubeconfig pointing at the server
+    pub kubeconfig: PathBuf,
+    /// Path and query of every request received, in order
+    pub requests: Arc<Mutex<Vec<String>>>,
+    /// Number of response bytes written, headers included
+    pub bytes_sent: Arc<AtomicUsize>,
+    /// Number of responses compressed because the client accepted gzip
+    pub gzipped: Arc<AtomicUsize>,
+    /// Path and query of every request answered with `PartialObjectMetadata`
+    pub metadata_requests: Arc<Mutex<Vec<String>>>,
+    /// Whether responses are compressed when the client accepts gzip
+    pub gzip: Arc<AtomicBool>,
+}
+
+/// Shared state of a running server
+#[derive(Clone)]
+struct State {
+    objects: Arc<HashMap<String, Vec<Value>>>,
+    requests: Arc<Mutex<Vec<String>>>,
+    bytes_sent: Arc<AtomicUsize>,
+    gzipped: Arc<AtomicUsize>,
+    metadata_requests: Arc<Mutex<Vec<String>>>,
+    gzip: Arc<AtomicBool>,
+}
+
+impl FakeApiServer {
+    /// Start serving the given pods in the background
+    pub async fn start(pods: Vec<Pod>) -> Self {
+        let pods = pods
+            .iter()
+            .map(|pod| serde_json::to_value(pod).unwrap())
+            .collect();
+        Self::start_with(vec![("pods", pods)]).await
+    }
+
+    /// Start serving lists of objects keyed by their plural resource name, e.g. `deployments`
+    ///
+    /// Resources without objects are served as empty lists.
+    pub async fn start_with(objects: Vec<(&str, Vec<Value>)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let state = State {
+            objects: Arc::new(
+                objects
+                    .into_iter()
+                    .map(|(plural, items)| (plural.to_string(), items))
+                    .collect(),
+            ),
+            requests: Arc::new(Mutex::new(Vec::new())),
+            bytes_sent: Arc::new(AtomicUsize::new(0)),
+            gzipped: Arc::new(AtomicUsize::new(0)),
+            metadata_requests: Arc::new(Mutex::new(Vec::new())),
+            gzip: Arc::new(AtomicBool::new(true)),
+        };
+
+        let served = state.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, served.clone()));
+            }
+        });
+
+        let kubeconfig = std::env::temp_dir().join(format!(
+            "kelper-fake-apiserver-{}-{}.yaml",
+            std::process::id(),
+            address.port()
+        ));
+        std::fs::write(
+            &kubeconfig,
+            format!(
+                "apiVersion: v1\nkind: Config\nclusters:\n- name: fake\n  cluster:\n    server: http://{}\nusers:\n- name: fake\n  user:\n    token: fake\ncontexts:\n- name: fake\n  context:\n    cluster: fake\n    user: fake\ncurrent-context: fake\n",
+                address
+            ),
+        )
+        .unwrap();
+
+        Self {
+            kubeconfig,
+            requests: state.requests,
+            bytes_sent: state.bytes_sent,
+            gzipped: state.gzipped,
+            metadata_requests: state.metadata_requests,
+            gzip: state.gzip,
+        }
+    }
+
+    /// Connect a client to the server, listing objects in chunks of `chunk_size`
+    pub async fn client(&self, chunk_size: u32) -> K8sClient {
+        K8sClient::from_options(&self.client_options(chunk_size))
+            .await
+            .unwrap()
+    }
+
+    /// Options connecting a client to the server, listing objects in chunks of `chunk_size`
+    pub fn client_options(&self, chunk_size: u32) -> ClientOptions {
+        ClientOptions {
+            kubeconfig: Some(self.kubeconfig.clone()),
+            chunk_size,
+            ..Default::default()
+        }
+    }
+
+    /// Path and query of every pod list request received so far
+    pub fn pod_list_requests(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|request| request.contains("/pods"))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for FakeApiServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.kubeconfig);
+    }
+}
+
+/// Create `count` single-container pods named `pod-<i>` in the `default` namespace
+pub fn create_pods(count: usize) -> Vec<Pod> {
+    (0..count)
+        .map(|i| Pod {
+            metadata: ObjectMeta {
+                name: Some(format!("pod-{}", i)),
+                namespace: Some("default".to_string()),
+                ..Default::default()
+            },
+            spec: Some(PodSpec {
+                node_name: Some(format!("node-{}", i % 10)),
+                containers: vec![Container {
+                    name: "app".to_string(),
+                    image: Some(format!("ghcr.io/acme/app-{}:1.{}", i % 50, i % 7)),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Create `count` pods shaped like those of a real Deployment
+///
+/// Every pod has managed fields, annotations, an init container, an app container
+/// and a sidecar with env vars, probes, resources and volume mounts, and running
+/// container statuses with resolved digests.
+pub fn create_realistic_pods(count: usize) -> Vec<Value> {
+    let env: Vec<Value> = (0..20)
+        .map(|i| json!({"name": format!("SETTING_{}", i), "value": format!("value-{}", i)}))
+        .collect();
+    let container = |name: &str, image: &str| {
+        json!({
+            "name": name,
+            "image": image,
+            "imagePullPolicy": "IfNotPresent",
+            "env": env,
+            "ports": [{"containerPort": 8080, "name": "http", "protocol": "TCP"}],
+            "resources": {
+                "limits": {"cpu": "500m", "memory": "512Mi"},
+                "requests": {"cpu": "100m", "memory": "128Mi"}
+            },
+            "livenessProbe": {"httpGet": {"path": "/healthz", "port": 8080}, "periodSeconds": 10},
+            "readinessProbe": {"httpGet": {"path": "/ready", "port": 8080}, "periodSeconds": 5},
+            "volumeMounts": [
+                {"name": "config", "mountPath": "/etc/app"},
+                {"name": "kube-api-access", "mountPath": "/var/run/secrets/kubernetes.io/serviceaccount", "readOnly": true}
+            ],
+            "terminationMessagePath": "/dev/termination-log",
+            "terminationMessagePolicy": "File"
+        })
+    };
+    let status = |name: &str, image: &str, digest: &str| {
+        json!({
+            "name": name,
+            "image": image,
+            "imageID": format!("{}@sha256:{}", image.split(':').next().unwrap(), digest),
+            "containerID": format!("containerd://{}", digest),
+            "ready": true,
+            "restartCount": 0,
+            "started": true,
+            "state": {"running": {"startedAt": "2026-10-01T00:00:00Z"}}
+        })
+    };
+
+    (0..count)
+        .map(|i| {
+            let app = format!("app-{}", i % 50);
+            let hash = format!("{:x}", 0x7d9f8b00 + i % 50);
+            let image = format!("123456789012.dkr.ecr.eu-west-1.amazonaws.com/{}:1.{}", app, i % 7);
+            let digest = format!("{:064x}", i % 50);
+            json!({
+                "apiVersion": "v1",
+                "kind": "Pod",
+                "metadata": {
+                    "name": format!("{}-{}-{:05}", app, hash, i),
+                    "namespace": "default",
+                    "uid": format!("00000000-0000-0000-0000-{:012}", i),
+                    "resourceVersion": "1",
+                    "creationTimestamp": "2026-10-01T00:00:00Z",
+                    "labels": {"app": app, "pod-template-hash": hash, "tier": "backend"},
+                    "annotations": {
+                        "kubectl.kubernetes.io/restartedAt": "2026-10-01T00:00:00Z",
+                        "prometheus.io/scrape": "true",
+                        "prometheus.io/port": "9090"
+                    },
+                    "ownerReferences": [{
+                        "apiVersion": "apps/v1",
+                        "kind": "ReplicaSet",
+                        "name": format!("{}-{}", app, hash),
+                        "uid": format!("10000000-0000-0000-0000-{:012}", i % 50),
+                        "controller": true,
+                        "blockOwnerDeletion": true
+                    }],
+                    "managedFields": [{
+                        "manager": "kube-controller-manager",
+                        "operation": "Update",
+                        "apiVersion": "v1",
+                        "time": "2026-10-01T00:00:00Z",
+                        "fieldsType": "FieldsV1",
+                        "fieldsV1": {
+                            "f:metadata": {"f:labels": {".": {}, "f:app": {}, "f:pod-template-hash": {}}},
+                            "f:spec": {"f:containers": {"k:{\"name\":\"app\"}": {".": {}, "f:env": {}, "f:image": {}, "f:ports": {}, "f:resources": {}}}}
+                        }
+                    }, {
+                        "manager": "kubelet",
+                        "operation": "Update",
+                        "apiVersion": "v1",
+                        "time": "2026-10-01T00:00:00Z",
+                        "fieldsType": "FieldsV1",
+                        "subresource": "status",
+                        "fieldsV1": {"f:status": {"f:conditions": {}, "f:containerStatuses": {}, "f:hostIP": {}, "f:phase": {}, "f:podIP": {}}}
+                    }]
+                },
+                "spec": {
+                    "nodeName": format!("node-{}", i % 100),
+                    "serviceAccountName": "default",
+                    "restartPolicy": "Always",
+                    "terminationGracePeriodSeconds": 30,
+                    "dnsPolicy": "ClusterFirst",
+                    "schedulerName": "default-scheduler",
+                    "initContainers": [container("migrate", &image)],
+                    "containers": [
+                        container("app", &image),
+                        container("linkerd-proxy", "cr.l5d.io/linkerd/proxy:edge-25.3.3")
+                    ],
+                    "volumes": [
+                        {"name": "config", "configMap": {"name": format!("{}-config", app), "defaultMode": 420}},
+                        {"name": "kube-api-access", "projected": {"defaultMode": 420, "sources": [
+                            {"serviceAccountToken": {"expirationSeconds": 3607, "path": "token"}},
+                            {"configMap": {"name": "kube-root-ca.crt", "items": [{"key": "ca.crt", "path": "ca.crt"}]}}
+                        ]}}
+                    ],
+                    "tolerations": [
+                        {"key": "node.kubernetes.io/not-ready", "operator": "Exists", "effect": "NoExecute", "tolerationSeconds": 300},
+                        {"key": "node.kubernetes.io/unreachable", "operator": "Exists", "effect": "NoExecute", "tolerationSeconds": 300}
+                    ]
+                },
+                "status": {
+                    "phase": "Running",
+                    "hostIP": "10.0.0.1",
+                    "podIP": format!("10.1.{}.{}", i / 250 % 250, i % 250),
+                    "qosClass": "Burstable",
+                    "startTime": "2026-10-01T00:00:00Z",
+                    "conditions": [
+                        {"type": "Initialized", "status": "True", "lastTransitionTime": "2026-10-01T00:00:00Z"},
+                        {"type": "Ready", "status": "True", "lastTransitionTime": "2026-10-01T00:00:00Z"},
+                        {"type": "ContainersReady", "status": "True", "lastTransitionTime": "2026-10-01T00:00:00Z"},
+                        {"type": "PodScheduled", "status": "True", "lastTransitionTime": "2026-10-01T00:00:00Z"}
+                    ],
+                    "initContainerStatuses": [status("migrate", &image, &digest)],
+                    "containerStatuses": [
+                        status("app", &image, &digest),
+                        status("linkerd-proxy", "cr.l5d.io/linkerd/proxy:edge-25.3.3", &"ab".repeat(32))
+                    ]
+                }
+            })
+        })
+        .collect()
+}
+
+/// Answer a single request and close the connection
+async fn serve(mut stream: TcpStream, state: State) {
+    let mut head = Vec::new();
+    let mut buffer = [0; 4096];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => head.extend_from_slice(&buffer[..read]),
+        }
+    }
+
+    let head = String::from_utf8_lossy(&head).to_lowercase();
+    let target = head
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+    state.requests.lock().unwrap().push(target.clone());
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let metadata_only = head
+        .lines()
+        .any(|line| line.starts_with("accept:") && line.contains("as=partialobjectmetadata"));
+    if metadata_only {
+        state.metadata_requests.lock().unwrap().push(target.clone());
+    }
+    let (status, body) = respond(path, query, metadata_only, &state.objects);
+    let mut body = body.to_string().into_bytes();
+
+    let accepts_gzip = head
+        .lines()
+        .any(|line| line.starts_with("accept-encoding:") && line.contains("gzip"));
+    let mut headers = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nConnection: close\r\n",
+        status
+    );
+    if accepts_gzip && state.gzip.load(Ordering::Relaxed) {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&body).unwrap();
+        body = encoder.finish().unwrap();
+        headers.push_str("Content-Encoding: gzip\r\n");
+        state.gzipped.fetch_add(1, Ordering::Relaxed);
+    }
+    headers.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+
+    state
+        .bytes_sent
+        .fetch_add(headers.len() + body.len(), Ordering::Relaxed);
+    let _ = stream.write_all(headers.as_bytes()).await;
+    let _ = stream.write_all(&body).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Build the status line and JSON body answering a request
+///
+/// Objects are reduced to their metadata when the client asked for `PartialObjectMetadata`.
+fn respond(
+    path: &str,
+    query: &str,
+    metadata_only: bool,
+    objects: &HashMap<String, Vec<Value>>,
+) -> (&'static str, Value) {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let list = match segments.as_slice() {
+        ["api", "v1", "namespaces", "default"] => {
+            return (
+                "200 OK",
+                json!({"apiVersion": "v1", "kind": "Namespace", "metadata": {"name": "default"}}),
+            )
+        }
+        ["api", "v1", plural] | ["apis", _, _, plural] => Some((None, *plural)),
+        ["api", "v1", "namespaces", namespace, plural]
+        | ["apis", _, _, "namespaces", namespace, plural] => Some((Some(*namespace), *plural)),
+        ["api", "v1", "namespaces", namespace, plural, name]
+        | ["apis", _, _, "namespaces", namespace, plural, name] => {
+            let object = objects.get(*plural).and_then(|items| {
+                items.iter().find(|item| {
+                    item["metadata"]["namespace"].as_str() == Some(*namespace)
+                        && item["metadata"]["name"].as_str() == Some(*name)
+                })
+            });
+            return match object {
+                Some(object) if metadata_only => ("200 OK", partial_metadata(object)),
+                Some(object) => ("200 OK", object.clone()),
+                None => not_found(path),
+            };
+        }
+        _ => None,
+    };
+
+    match list {
+        Some((namespace, plural)) => {
+            let items: Vec<Value> = objects
+                .get(plural)
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter(|item| {
+                            namespace.is_none_or(|namespace| {
+                                item["metadata"]["namespace"].as_str() == Some(namespace)
+                            })
+                        })
+                        .map(|item| {
+                            if metadata_only {
+                                partial_metadata(item)
+                            } else {
+                                item.clone()
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            ("200 OK", page(query, &items))
+        }
+        None => not_found(path),
+    }
+}
+
+/// Reduce an object to the `PartialObjectMetadata` the API server returns for metadata requests
+fn partial_metadata(object: &Value) -> Value {
+    json!({
+        "apiVersion": "meta.k8s.io/v1",
+        "kind": "PartialObjectMetadata",
+        "metadata": object["metadata"],
+    })
+}
+
+/// Build the `NotFound` status answering a request for a missing path or object
+fn not_found(path: &str) -> (&'static str, Value) {
+    (
+        "404 Not Found",
+        json!({
+            "apiVersion": "v1",
+            "kind": "Status",
+            "status": "Failure",
+            "message": format!("{} not found", path),
+            "reason": "NotFound",
+            "code": 404
+        }),
+    )
+}
+
+/// Build one page of a list, using the offset of the next object as continue token
+fn page(query: &str, items: &[Value]) -> Value {
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+    };
+    let start = param("continue").unwrap_or(0).min(items.len());
+    let end = match param("limit") {
+        Some(limit) if limit > 0 => (start + limit).min(items.len()),
+        _ => items.len(),
+    };
+
+    let mut metadata = json!({"resourceVersion": "1"});
+    if end < items.len() {
+        metadata["continue"] = json!(end.to_string());
+        metadata["remainingItemCount"] = json!(items.len() - end);
+    }
+    json!({
+        "apiVersion": "v1",
+        "kind": "List",
+        "metadata": metadata,
+        "items": &items[start..end],
+    })
+}
@@ -0,0 +1,372 @@
+use kelper::{Digest, ImageReference, ImageReferenceError, ProviderKind, RegistryProvider};
+
+#[test]
+fn test_parse_image_reference() {
+    let test_cases = vec![
+        ("nginx", "docker.io", "nginx", None, None),
+        ("nginx:1.25", "docker.io", "nginx", Some("1.25"), None),
+        (
+            "docker.io/library/nginx:latest",
+            "docker.io",
+            "library/nginx",
+            Some("latest"),
+            None,
+        ),
+        (
+            "linkerd/proxy:edge-25.3.3",
+            "docker.io",
+            "linkerd/proxy",
+            Some("edge-25.3.3"),
+            None,
+        ),
+        (
+            "localhost:5000/team/app:v1",
+            "localhost:5000",
+            "team/app",
+            Some("v1"),
+            None,
+        ),
+        ("localhost/app", "localhost", "app", None, None),
+        (
+            "[::1]:5000/app:v1.10.0-rc.1",
+            "[::1]:5000",
+            "app",
+            Some("v1.10.0-rc.1"),
+            None,
+        ),
+        (
+            "gcr.io/project/nginx:1.21@sha256:496429c2a4a4e3b5f4ae2e53a26ffa4f2f1cee6bc16fee7fce71d0b19d2e19f8",
+            "gcr.io",
+            "project/nginx",
+            Some("1.21"),
+            Some("sha256:496429c2a4a4e3b5f4ae2e53a26ffa4f2f1cee6bc16fee7fce71d0b19d2e19f8"),
+        ),
+        (
+            "quay.io/coreos/etcd@sha512:abababababababababababababababababababababababababababababababababababababababababababababababababababababababababababababababab",
+            "quay.io",
+            "coreos/etcd",
+            None,
+            Some("sha512:abababababababababababababababababababababababababababababababababababababababababababababababababababababababababababababababab"),
+        ),
+    ];
+
+    for (image, registry, repository, tag, digest) in test_cases {
+        let reference: ImageReference = image
+            .parse()
+            .unwrap_or_else(|e| panic!("Failed to parse {}: {}", image, e));
+        assert_eq!(reference.registry, registry, "registry of {}", image);
+        assert_eq!(reference.repository, repository, "repository of {}", image);
+        assert_eq!(reference.tag.as_deref(), tag, "tag of {}", image);
+        assert_eq!(
+            reference.digest.as_ref().map(Digest::to_string).as_deref(),
+            digest,
+            "digest of {}",
+            image
+        );
+        assert_eq!(reference.to_string(), image, "round trip of {}", image);
+        assert_eq!(
+            reference.to_string().parse::<ImageReference>(),
+            Ok(reference)
+        );
+    }
+}
+
+#[test]
+fn test_parse_digest() {
+    let hex = "496429c2a4a4e3b5f4ae2e53a26ffa4f2f1cee6bc16fee7fce71d0b19d2e19f8";
+    let digest: Digest = format!("sha256:{}", hex).parse().unwrap();
+    assert_eq!(digest.algorithm, "sha256");
+    assert_eq!(digest.hex, hex);
+
+    assert!(hex.parse::<Digest>().is_err());
+    assert!("sha256:".parse::<Digest>().is_err());
+    assert!("SHA256:abc".parse::<Digest>().is_err());
+
+    // Registered algorithms must be lowercase hex of their exact length
+    assert!("sha256:496429c2a4a4".parse::<Digest>().is_err());
+    assert!(format!("sha256:{}0", hex).parse::<Digest>().is_err());
+    assert!(format!("sha256:{}", hex.to_uppercase())
+        .parse::<Digest>()
+        .is_err());
+    assert!(format!("sha256:{}", "g".repeat(64))
+        .parse::<Digest>()
+        .is_err());
+    assert!(format!("sha512:{}", hex).parse::<Digest>().is_err());
+    assert!(format!("sha512:{}{}", hex, hex).parse::<Digest>().is_ok());
+
+    // Other algorithms only follow the generic grammar
+    assert!(
+        "multihash+base58:QmRZxt2b1FVZPNqd8hsiykDL3TdBDeTSPX9Kv46HmX4Gx8"
+            .parse::<Digest>()
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_parse_invalid_image_references() {
+    assert_eq!(
+        "".parse::<ImageReference>(),
+        Err(ImageReferenceError::Empty)
+    );
+    assert!(matches!(
+        "Nginx:1.25".parse::<ImageReference>(),
+        Err(ImageReferenceError::InvalidRepository(_))
+    ));
+    assert!(matches!(
+        "nginx:-bad".parse::<ImageReference>(),
+        Err(ImageReferenceError::InvalidTag(_))
+    ));
+    assert!(matches!(
+        "nginx@abc123".parse::<ImageReference>(),
+        Err(ImageReferenceError::InvalidDigest(_))
+    ));
+    assert!(matches!(
+        "nginx@sha256:abc123".parse::<ImageReference>(),
+        Err(ImageReferenceError::InvalidDigest(_))
+    ));
+    assert!(matches!(
+        "bad_host.io:port/app".parse::<ImageReference>(),
+        Err(ImageReferenceError::InvalidRegistry(_))
+    ));
+    assert!(matches!(
+        format!("ghcr.io/{}", "a".repeat(250)).parse::<ImageReference>(),
+        Err(ImageReferenceError::NameTooLong(_))
+    ));
+}
+
+#[test]
+fn test_image_reference_helpers() {
+    let reference: ImageReference = "nginx".parse().unwrap();
+    assert!(reference.is_official_library);
+    assert!(!reference.has_explicit_registry());
+    assert_eq!(reference.tag_or_default(), "latest");
+    assert_eq!(reference.version(), "latest");
+
+    let reference: ImageReference = "docker.io/library/redis:7.2".parse().unwrap();
+    assert!(reference.is_official_library);
+    assert!(reference.has_explicit_registry());
+    assert_eq!(reference.name(), "docker.io/library/redis");
+
+    let reference = ImageReference::lossy("linkerd/proxy@sha256:abc123");
+    assert!(!reference.is_official_library);
+    assert_eq!(reference.version(), "latest@sha256:abc123");
+
+    let reference = ImageReference::lossy("Registry.Example.com/My_App:1.0");
+    assert_eq!(reference.registry, "registry.example.com");
+    assert_eq!(reference.repository, "My_App");
+}
+
+#[test]
+fn test_registry_host_is_lowercased() {
+    let reference: ImageReference = "Registry.Example.com:5000/team/app:1.0".parse().unwrap();
+    assert_eq!(reference.registry, "registry.example.com:5000");
+    assert_eq!(
+        reference.to_string(),
+        "registry.example.com:5000/team/app:1.0"
+    );
+
+    let reference: ImageReference = "Docker.IO/library/nginx".parse().unwrap();
+    assert!(reference.is_official_library);
+    assert_eq!(reference.normalized().to_string(), "docker.io/nginx");
+}
+
+#[test]
+fn test_uppercase_first_component_is_a_registry() {
+    let reference: ImageReference = "MyRegistry/app:1.0".parse().unwrap();
+    assert_eq!(reference.registry, "myregistry");
+    assert_eq!(reference.repository, "app");
+    assert!(reference.has_explicit_registry());
+    assert!(!reference.is_official_library);
+
+    let reference: ImageReference = "myregistry/app:1.0".parse().unwrap();
+    assert_eq!(reference.registry, "docker.io");
+    assert_eq!(reference.repository, "myregistry/app");
+    assert!(!reference.has_explicit_registry());
+
+    assert!(matches!(
+        "MyRegistry/App".parse::<ImageReference>(),
+        Err(ImageReferenceError::InvalidRepository(_))
+    ));
+}
+
+#[test]
+fn test_equality_ignores_explicit_registry() {
+    use std::collections::HashSet;
+
+    let implicit: ImageReference = "nginx:1.25".parse().unwrap();
+    let explicit: ImageReference = "docker.io/nginx:1.25".parse().unwrap();
+    assert_eq!(implicit, explicit);
+    assert_ne!(implicit.to_string(), explicit.to_string());
+    assert_eq!(HashSet::from([implicit, explicit]).len(), 1);
+
+    let library: ImageReference = "docker.io/library/nginx:1.25".parse().unwrap();
+    let familiar: ImageReference = "nginx:1.25".parse().unwrap();
+    assert_ne!(library, familiar);
+    assert_eq!(library.normalized(), familiar.normalized());
+    assert_eq!(familiar.normalized(), familiar.clone().normalized());
+}
+
+#[test]
+fn test_normalize_docker_hub_references() {
+    for image in [
+        "nginx:1.25",
+        "library/nginx:1.25",
+        "docker.io/nginx:1.25",
+        "docker.io/library/nginx:1.25",
+        "index.docker.io/library/nginx:1.25",
+        "registry-1.docker.io/library/nginx:1.25",
+        "registry.hub.docker.com/library/nginx:1.25",
+    ] {
+        let reference = ImageReference::lossy(image).normalized();
+        assert_eq!(
+            reference.to_string(),
+            "docker.io/nginx:1.25",
+            "for {}",
+            image
+        );
+    }
+
+    let reference = ImageReference::lossy("index.docker.io/linkerd/proxy").normalized();
+    assert_eq!(reference.to_string(), "docker.io/linkerd/proxy");
+
+    let reference = ImageReference::lossy("ghcr.io/library/app:1").normalized();
+    assert_eq!(reference.to_string(), "ghcr.io/library/app:1");
+}
+
+#[test]
+fn test_classify_registry_provider() {
+    let test_cases = vec![
+        (
+            "123456789012.dkr.ecr.eu-west-1.amazonaws.com",
+            "acme/api",
+            ProviderKind::Ecr,
+            Some("123456789012"),
+            Some("eu-west-1"),
+        ),
+        (
+            "123456789012.dkr.ecr-fips.us-gov-west-1.amazonaws.com",
+            "api",
+            ProviderKind::Ecr,
+            Some("123456789012"),
+            Some("us-gov-west-1"),
+        ),
+        (
+            "europe-west4-docker.pkg.dev",
+            "acme-prod/images/api",
+            ProviderKind::ArtifactRegistry,
+            Some("acme-prod"),
+            Some("europe-west4"),
+        ),
+        (
+            "eu.gcr.io",
+            "acme-prod/api",
+            ProviderKind::Gcr,
+            Some("acme-prod"),
+            Some("eu"),
+        ),
+        (
+            "gcr.io",
+            "acme-prod/api",
+            ProviderKind::Gcr,
+            Some("acme-prod"),
+            None,
+        ),
+        (
+            "acme.azurecr.io",
+            "api",
+            ProviderKind::Acr,
+            Some("acme"),
+            None,
+        ),
+        (
+            "ghcr.io",
+            "acme/api",
+            ProviderKind::Ghcr,
+            Some("acme"),
+            None,
+        ),
+        (
+            "quay.io",
+            "coreos/etcd",
+            ProviderKind::Quay,
+            Some("coreos"),
+            None,
+        ),
+        (
+            "public.ecr.aws",
+            "eks/coredns",
+            ProviderKind::EcrPublic,
+            Some("eks"),
+            None,
+        ),
+        (
+            "docker.io",
+            "nginx",
+            ProviderKind::DockerHub,
+            Some("library"),
+            None,
+        ),
+        (
+            "docker.io",
+            "ollama/ollama",
+            ProviderKind::DockerHub,
+            Some("ollama"),
+            None,
+        ),
+        (
+            "registry.k8s.io",
+            "pause",
+            ProviderKind::Kubernetes,
+            None,
+            None,
+        ),
+        (
+            "localhost:5000",
+            "team/app",
+            ProviderKind::Private,
+            None,
+            None,
+        ),
+        (
+            "10.0.12.4:5000",
+            "team/app",
+            ProviderKind::Private,
+            None,
+            None,
+        ),
+        ("[::1]:5000", "app", ProviderKind::Private, None, None),
+        ("registry", "app", ProviderKind::Private, None, None),
+        (
+            "cr.l5d.io",
+            "linkerd/proxy",
+            ProviderKind::SelfHosted,
+            None,
+            None,
+        ),
+    ];
+
+    for (registry, repository, kind, account, region) in test_cases {
+        let provider = RegistryProvider::classify(registry, repository);
+        assert_eq!(provider.kind, kind, "provider of {}", registry);
+        assert_eq!(
+            provider.account.as_deref(),
+            account,
+            "account of {}",
+            registry
+        );
+        assert_eq!(provider.region.as_deref(), region, "region of {}", registry);
+    }
+}
+
+#[test]
+fn test_provider_kind_display() {
+    assert_eq!(
+        ProviderKind::ArtifactRegistry.to_string(),
+        "artifact-registry"
+    );
+    assert_eq!(ProviderKind::SelfHosted.to_string(), "self-hosted");
+    assert_eq!(
+        serde_json::to_value(ProviderKind::DockerHub).unwrap(),
+        "docker-hub"
+    );
+}
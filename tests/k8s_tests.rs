@@ -1,6 +1,20 @@
-use k8s_openapi::api::core::v1::{Container, Pod, PodSpec};
-use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
-use kelper::{extract_registry, process_pod, split_image};
+use k8s_openapi::api::core::v1::{
+    Container, ContainerState, ContainerStateTerminated, ContainerStateWaiting, ContainerStatus,
+    EphemeralContainer, ExecAction, HTTPGetAction, Pod, PodSpec, PodStatus, Probe, TCPSocketAction,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kelper::{
+    audit_skew, audit_tags, describe_probe, detect_drift, diff_pod_images, extract_registry,
+    image_tag_source, kubeconfig_contexts, label_violations, metadata_entries,
+    parse_image_id_digest, pod_health, pod_workload, process_pod, process_pod_with, query_fleet,
+    split_image, summarize_images, summarize_registry_usage, summarize_registry_usage_with,
+    ClientOptions, ContainerType, ContainerTypeFilter, GroupBy, HealthIssue, ImageChange,
+    ImageQuery, ImageSnapshot, LabelIssue, LabelRequirement, LabelViolation, MetadataEntry,
+    MetadataField, Pattern, ProviderKind, RegistryConfig, RegistryPolicy, RegistryResolver,
+    TagIssue, TagSource, WorkloadRef, DEFAULT_RESTART_THRESHOLD,
+};
+use regex::Regex;
 
 fn create_test_pod(name: &str, namespace: &str, containers: Vec<Container>) -> Pod {
     Pod {
@@ -442,3 +456,1398 @@ fn test_registry_filtering_with_empty_pod() {
         .collect();
     assert_eq!(filtered_images.len(), 0);
 }
+
+#[test]
+fn test_process_pod_with_init_and_ephemeral_containers() {
+    let mut pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![create_test_container("app", "ghcr.io/acme/app:1.0")],
+    );
+    let spec = pod.spec.as_mut().unwrap();
+    spec.init_containers = Some(vec![create_test_container(
+        "linkerd-init",
+        "cr.l5d.io/linkerd/proxy-init:v2.4.1",
+    )]);
+    spec.ephemeral_containers = Some(vec![EphemeralContainer {
+        name: "debugger".to_string(),
+        image: Some("busybox:1.36".to_string()),
+        ..Default::default()
+    }]);
+
+    let images = process_pod(&pod);
+    assert_eq!(images.len(), 3);
+
+    assert_eq!(images[0].container_name, "linkerd-init");
+    assert_eq!(images[0].container_type, ContainerType::Init);
+    assert_eq!(images[0].registry, "cr.l5d.io");
+    assert_eq!(images[0].image_name, "linkerd/proxy-init");
+
+    assert_eq!(images[1].container_name, "app");
+    assert_eq!(images[1].container_type, ContainerType::Regular);
+
+    assert_eq!(images[2].container_name, "debugger");
+    assert_eq!(images[2].container_type, ContainerType::Ephemeral);
+    assert_eq!(images[2].image_version, "1.36");
+}
+
+#[test]
+fn test_process_pod_digest_uses_matching_status_list() {
+    let mut pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![create_test_container("app", "nginx:1.25")],
+    );
+    pod.spec.as_mut().unwrap().init_containers =
+        Some(vec![create_test_container("app", "busybox:1.36")]);
+    pod.status = Some(PodStatus {
+        container_statuses: Some(vec![ContainerStatus {
+            name: "app".to_string(),
+            image_id: "sha256:regulardigest".to_string(),
+            ..Default::default()
+        }]),
+        init_container_statuses: Some(vec![ContainerStatus {
+            name: "app".to_string(),
+            image_id: "sha256:initdigest".to_string(),
+            ..Default::default()
+        }]),
+        ..Default::default()
+    });
+
+    let images = process_pod(&pod);
+    assert_eq!(images[0].container_type, ContainerType::Init);
+    assert_eq!(images[0].digest, "initdigest");
+    assert_eq!(images[1].container_type, ContainerType::Regular);
+    assert_eq!(images[1].digest, "regulardigest");
+}
+
+#[test]
+fn test_image_query_container_type_filter() {
+    let mut pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![create_test_container("app", "nginx:1.25")],
+    );
+    pod.spec.as_mut().unwrap().init_containers =
+        Some(vec![create_test_container("init", "busybox:1.36")]);
+    let images = process_pod(&pod);
+
+    let count = |container_type| {
+        let query = ImageQuery {
+            container_type,
+            ..Default::default()
+        };
+        images.iter().filter(|i| query.matches_image(i)).count()
+    };
+
+    assert_eq!(count(ContainerTypeFilter::All), 2);
+    assert_eq!(count(ContainerTypeFilter::Init), 1);
+    assert_eq!(count(ContainerTypeFilter::Regular), 1);
+    assert_eq!(count(ContainerTypeFilter::Ephemeral), 0);
+}
+
+fn workload(kind: &str, name: &str) -> WorkloadRef {
+    WorkloadRef {
+        kind: kind.to_string(),
+        namespace: "default".to_string(),
+        name: name.to_string(),
+    }
+}
+
+#[test]
+fn test_summarize_registry_usage() {
+    let api = PodSpec {
+        init_containers: Some(vec![create_test_container(
+            "init",
+            "ghcr.io/acme/migrate:1.0",
+        )]),
+        containers: vec![
+            create_test_container("api", "ghcr.io/acme/api:1.0"),
+            create_test_container("proxy", "cr.l5d.io/linkerd/proxy:edge-25.3.3"),
+        ],
+        ..Default::default()
+    };
+    let db = PodSpec {
+        containers: vec![create_test_container("postgres", "postgres:16")],
+        ..Default::default()
+    };
+    let backup = PodSpec {
+        containers: vec![create_test_container("backup", "ghcr.io/acme/backup:2.1")],
+        ..Default::default()
+    };
+
+    let usage = summarize_registry_usage(vec![
+        (workload("Deployment", "api"), &api),
+        (workload("StatefulSet", "db"), &db),
+        (workload("CronJob", "backup"), &backup),
+    ]);
+
+    let registries: Vec<&str> = usage.iter().map(|u| u.registry.as_str()).collect();
+    assert_eq!(registries, vec!["cr.l5d.io", "docker.io", "ghcr.io"]);
+
+    let ghcr = &usage[2];
+    assert_eq!(ghcr.workloads, 2);
+    assert_eq!(ghcr.containers, 3);
+    assert_eq!(ghcr.kinds, vec!["CronJob", "Deployment"]);
+
+    let docker = &usage[1];
+    assert_eq!(docker.workloads, 1);
+    assert_eq!(docker.containers, 1);
+    assert_eq!(docker.kinds, vec!["StatefulSet"]);
+}
+
+#[test]
+fn test_summarize_images() {
+    let mut images = Vec::new();
+    for (pod, tag, digest) in [
+        ("api-1", "edge-25.3.3", "aaa"),
+        ("api-2", "edge-25.3.3", "aaa"),
+        ("api-3", "edge-25.2.1", "bbb"),
+    ] {
+        let pod = create_test_pod(
+            pod,
+            "default",
+            vec![
+                create_test_container("api", "ghcr.io/acme/api:1.0"),
+                create_test_container("linkerd-proxy", &format!("cr.l5d.io/linkerd/proxy:{}", tag)),
+            ],
+        );
+        let mut pod_images = process_pod(&pod);
+        pod_images[1].digest = digest.to_string();
+        images.extend(pod_images);
+    }
+
+    let summary = summarize_images(&images, GroupBy::Image);
+    assert_eq!(summary.len(), 2);
+    assert_eq!(summary[0].group, "cr.l5d.io/linkerd/proxy");
+    assert_eq!(summary[0].pods, 3);
+    assert_eq!(summary[0].containers, 3);
+    assert_eq!(summary[0].tags, 2);
+    assert_eq!(summary[0].digests, 2);
+    assert_eq!(summary[0].versions, vec!["edge-25.2.1", "edge-25.3.3"]);
+    assert_eq!(summary[1].group, "ghcr.io/acme/api");
+    assert_eq!(summary[1].tags, 1);
+    assert_eq!(summary[1].digests, 0);
+
+    let summary = summarize_images(&images, GroupBy::Namespace);
+    assert_eq!(summary.len(), 1);
+    assert_eq!(summary[0].group, "default");
+    assert_eq!(summary[0].pods, 3);
+    assert_eq!(summary[0].containers, 6);
+    assert_eq!(summary[0].tags, 3);
+
+    let summary = summarize_images(&images, GroupBy::Node);
+    assert_eq!(summary[0].group, "<none>");
+}
+
+#[test]
+fn test_audit_skew() {
+    let mut images = Vec::new();
+    for (pod, namespace, tag, digest) in [
+        ("api-1", "payments", "edge-25.3.3", "aaa"),
+        ("api-2", "payments", "edge-25.3.3", "aaa"),
+        ("web-1", "frontend", "edge-25.3.3", "aaa"),
+        ("web-2", "frontend", "edge-25.2.1", "bbb"),
+    ] {
+        let pod = create_test_pod(
+            pod,
+            namespace,
+            vec![
+                create_test_container("app", "ghcr.io/acme/app:1.0"),
+                create_test_container("linkerd-proxy", &format!("cr.l5d.io/linkerd/proxy:{}", tag)),
+            ],
+        );
+        let mut pod_images = process_pod(&pod);
+        pod_images[1].digest = digest.to_string();
+        images.extend(pod_images);
+    }
+
+    let entries = audit_skew(&images, true);
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.image == "cr.l5d.io/linkerd/proxy"));
+    assert!(entries.iter().all(|e| e.variants == 2));
+
+    assert_eq!(entries[0].version, "edge-25.3.3");
+    assert_eq!(entries[0].digest, "aaa");
+    assert_eq!(entries[0].pods, 3);
+    assert_eq!(entries[0].namespaces, vec!["frontend", "payments"]);
+
+    assert_eq!(entries[1].version, "edge-25.2.1");
+    assert_eq!(entries[1].digest, "bbb");
+    assert_eq!(entries[1].pods, 1);
+    assert_eq!(entries[1].namespaces, vec!["frontend"]);
+
+    let entries = audit_skew(&images, false);
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[2].image, "ghcr.io/acme/app");
+    assert_eq!(entries[2].pods, 4);
+    assert_eq!(entries[2].variants, 1);
+}
+
+#[test]
+fn test_audit_skew_merges_containers_without_digest() {
+    let mut images = Vec::new();
+    for (pod, tag, digest) in [
+        ("api-1", "1.1", "aaa"),
+        ("api-2", "1.1", "aaa"),
+        // Still pulling its image during the rollout
+        ("api-3", "1.1", ""),
+        ("api-4", "1.0", ""),
+    ] {
+        let pod = create_test_pod(
+            pod,
+            "default",
+            vec![create_test_container(
+                "app",
+                &format!("ghcr.io/acme/app:{}", tag),
+            )],
+        );
+        let mut pod_images = process_pod(&pod);
+        pod_images[0].digest = digest.to_string();
+        images.extend(pod_images);
+    }
+
+    let entries = audit_skew(&images[..3], true);
+    assert!(entries.is_empty());
+
+    let entries = audit_skew(&images, false);
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.variants == 2));
+    assert_eq!(entries[0].version, "1.1");
+    assert_eq!(entries[0].digest, "aaa");
+    assert_eq!(entries[0].pods, 3);
+    assert_eq!(entries[0].containers, 3);
+    assert_eq!(entries[1].version, "1.0");
+    assert_eq!(entries[1].digest, "");
+    assert_eq!(entries[1].pods, 1);
+}
+
+#[test]
+fn test_diff_pod_images() {
+    let before = process_pod(&create_test_pod(
+        "api-0",
+        "default",
+        vec![
+            create_test_container("app", "ghcr.io/acme/api:1.0"),
+            create_test_container("linkerd-proxy", "cr.l5d.io/linkerd/proxy:edge-25.2.1"),
+            create_test_container("debug", "busybox:1.36"),
+        ],
+    ));
+    let mut after = process_pod(&create_test_pod(
+        "api-0",
+        "default",
+        vec![
+            create_test_container("app", "ghcr.io/acme/api:1.0"),
+            create_test_container("linkerd-proxy", "cr.l5d.io/linkerd/proxy:edge-25.3.3"),
+            create_test_container("metrics", "prom/statsd-exporter:v0.26.0"),
+        ],
+    ));
+    after[0].digest = "496429c2a4a4".to_string();
+
+    let events = diff_pod_images(&before, &after);
+    let summary: Vec<(ImageChange, &str)> = events
+        .iter()
+        .map(|e| (e.change, e.image.container_name.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (ImageChange::Removed, "debug"),
+            (ImageChange::Changed, "linkerd-proxy"),
+            (ImageChange::Added, "metrics"),
+        ]
+    );
+    assert_eq!(events[1].image.image_version, "edge-25.3.3");
+    assert_eq!(events[1].previous, "cr.l5d.io/linkerd/proxy:edge-25.2.1");
+
+    assert!(diff_pod_images(&after, &after).is_empty());
+    assert!(diff_pod_images(&[], &after)
+        .iter()
+        .all(|e| e.change == ImageChange::Added));
+    assert!(diff_pod_images(&after, &[])
+        .iter()
+        .all(|e| e.change == ImageChange::Removed));
+}
+
+#[test]
+fn test_summarize_registry_usage_empty() {
+    let empty = PodSpec::default();
+    let usage = summarize_registry_usage(vec![(workload("Pod", "bare"), &empty)]);
+    assert!(usage.is_empty());
+}
+
+#[test]
+fn test_image_query_list_params_combines_selectors() {
+    let query = ImageQuery {
+        node: Some("worker-1".to_string()),
+        pod: Some("api-0".parse::<Pattern>().unwrap()),
+        label_selector: Some("app=payments,tier=backend".to_string()),
+        field_selector: Some("status.phase=Running".to_string()),
+        ..Default::default()
+    };
+    let params = query.list_params();
+
+    assert_eq!(
+        params.field_selector.as_deref(),
+        Some("spec.nodeName=worker-1,metadata.name=api-0,status.phase=Running")
+    );
+    assert_eq!(
+        params.label_selector.as_deref(),
+        Some("app=payments,tier=backend")
+    );
+}
+
+#[test]
+fn test_image_query_list_params_without_selectors() {
+    let params = ImageQuery::default().list_params();
+    assert!(params.label_selector.is_none());
+    assert_eq!(params.field_selector.as_deref(), Some(""));
+}
+
+#[test]
+fn test_pattern_matching() {
+    let test_cases = vec![
+        ("api-*", "api-7d9f8b-xk2p4", true),
+        ("api-*", "web-api-1", false),
+        ("api-?", "api-1", true),
+        ("api-?", "api-12", false),
+        ("api-[0-9]", "api-3", true),
+        ("api-[!0-9]", "api-3", false),
+        (
+            "*.dkr.ecr.*.amazonaws.com",
+            "1234.dkr.ecr.eu-west-1.amazonaws.com",
+            true,
+        ),
+        ("*.dkr.ecr.*.amazonaws.com", "ghcr.io", false),
+        ("nginx", "nginx", true),
+        ("nginx", "nginx-ingress", false),
+        ("registry.k8s.io", "registryxk8s.io", false),
+        ("broken[", "broken[", true),
+    ];
+
+    for (pattern, value, expected) in test_cases {
+        assert_eq!(
+            pattern.parse::<Pattern>().unwrap().matches(value),
+            expected,
+            "Pattern {} against {}",
+            pattern,
+            value
+        );
+    }
+
+    assert!("api-*".parse::<Pattern>().unwrap().is_glob());
+    assert!(!"api-0".parse::<Pattern>().unwrap().is_glob());
+}
+
+#[test]
+fn test_invalid_pattern() {
+    let error = "api-[z-a]".parse::<Pattern>().unwrap_err();
+    assert!(error.contains("api-[z-a]"), "{}", error);
+    assert!("api-[a-z]".parse::<Pattern>().is_ok());
+
+    let policy = serde_yaml::from_str::<RegistryPolicy>("allow:\n  - ghcr.io/[z-a]*\n");
+    assert!(policy.is_err());
+}
+
+#[test]
+fn test_image_query_pod_glob_and_regex() {
+    let pod = create_test_pod(
+        "api-7d9f8b-xk2p4",
+        "default",
+        vec![create_test_container("api", "nginx:1.25")],
+    );
+
+    let glob = ImageQuery {
+        pod: Some("api-*".parse::<Pattern>().unwrap()),
+        ..Default::default()
+    };
+    assert!(glob.matches_pod(&pod));
+    // Globs are matched client-side instead of through a field selector
+    assert_eq!(glob.list_params().field_selector.as_deref(), Some(""));
+
+    let regex = ImageQuery {
+        pod_regex: Some(Regex::new("^api-[a-z0-9]+-[a-z0-9]{5}$").unwrap()),
+        ..Default::default()
+    };
+    assert!(regex.matches_pod(&pod));
+
+    let other = ImageQuery {
+        pod: Some("web-*".parse::<Pattern>().unwrap()),
+        ..Default::default()
+    };
+    assert!(!other.matches_pod(&pod));
+}
+
+#[test]
+fn test_image_query_image_and_tag_filters() {
+    let pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![
+            create_test_container("proxy", "cr.l5d.io/linkerd/proxy:edge-25.3.3"),
+            create_test_container("app", "ghcr.io/acme/app:v1.4.2@sha256:abc123"),
+            create_test_container("cache", "redis:7.2"),
+        ],
+    );
+    let images = process_pod(&pod);
+
+    let names = |query: ImageQuery| -> Vec<String> {
+        images
+            .iter()
+            .filter(|i| query.matches_image(i))
+            .map(|i| i.container_name.clone())
+            .collect()
+    };
+
+    assert_eq!(
+        names(ImageQuery {
+            image: Some("linkerd/*".parse::<Pattern>().unwrap()),
+            ..Default::default()
+        }),
+        vec!["proxy"]
+    );
+    assert_eq!(
+        names(ImageQuery {
+            image: Some("ghcr.io/acme/*".parse::<Pattern>().unwrap()),
+            ..Default::default()
+        }),
+        vec!["app"]
+    );
+    assert_eq!(
+        names(ImageQuery {
+            tag: Some("v1.*".parse::<Pattern>().unwrap()),
+            ..Default::default()
+        }),
+        vec!["app"]
+    );
+    assert_eq!(
+        names(ImageQuery {
+            image: Some("redis".parse::<Pattern>().unwrap()),
+            tag: Some("7.2".parse::<Pattern>().unwrap()),
+            ..Default::default()
+        }),
+        vec!["cache"]
+    );
+}
+
+#[test]
+fn test_image_query_registry_include_and_exclude() {
+    let pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![
+            create_test_container(
+                "api",
+                "123456789012.dkr.ecr.eu-west-1.amazonaws.com/acme/api:1.0",
+            ),
+            create_test_container("proxy", "cr.l5d.io/linkerd/proxy:edge-25.3.3"),
+            create_test_container("mirror", "mirror.corp/dockerhub/nginx:1.25"),
+            create_test_container("cache", "redis:7.2"),
+        ],
+    );
+    let images = process_pod(&pod);
+
+    let names = |query: ImageQuery| -> Vec<String> {
+        images
+            .iter()
+            .filter(|i| query.matches_image(i))
+            .map(|i| i.container_name.clone())
+            .collect()
+    };
+
+    assert_eq!(
+        names(ImageQuery {
+            registries: vec!["*.dkr.ecr.*.amazonaws.com".parse::<Pattern>().unwrap()],
+            ..Default::default()
+        }),
+        vec!["api"]
+    );
+    assert_eq!(
+        names(ImageQuery {
+            registries: vec![
+                "cr.l5d.io".parse::<Pattern>().unwrap(),
+                "docker.io".parse::<Pattern>().unwrap()
+            ],
+            ..Default::default()
+        }),
+        vec!["proxy", "cache"]
+    );
+    assert_eq!(
+        names(ImageQuery {
+            exclude_registries: vec!["mirror.corp".parse::<Pattern>().unwrap()],
+            ..Default::default()
+        }),
+        vec!["api", "proxy", "cache"]
+    );
+    assert_eq!(
+        names(ImageQuery {
+            registries: vec!["*".parse::<Pattern>().unwrap()],
+            exclude_registries: vec![
+                "*.amazonaws.com".parse::<Pattern>().unwrap(),
+                "docker.io".parse::<Pattern>().unwrap()
+            ],
+            ..Default::default()
+        }),
+        vec!["proxy", "mirror"]
+    );
+}
+
+#[test]
+fn test_process_pod_classifies_provider() {
+    let pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![
+            create_test_container(
+                "api",
+                "123456789012.dkr.ecr.eu-west-1.amazonaws.com/acme/api:1.0",
+            ),
+            create_test_container("worker", "us-docker.pkg.dev/acme-prod/images/worker:2.0"),
+            create_test_container("cache", "redis:7.2"),
+        ],
+    );
+    let images = process_pod(&pod);
+
+    assert_eq!(images[0].provider, ProviderKind::Ecr);
+    assert_eq!(images[0].provider_account, "123456789012");
+    assert_eq!(images[0].provider_region, "eu-west-1");
+    assert_eq!(images[1].provider, ProviderKind::ArtifactRegistry);
+    assert_eq!(images[1].provider_account, "acme-prod");
+    assert_eq!(images[1].provider_region, "us");
+    assert_eq!(images[2].provider, ProviderKind::DockerHub);
+    assert_eq!(images[2].provider_account, "library");
+    assert_eq!(images[2].provider_region, "");
+
+    let query = ImageQuery {
+        providers: vec![ProviderKind::Ecr, ProviderKind::ArtifactRegistry],
+        ..Default::default()
+    };
+    let matched: Vec<&str> = images
+        .iter()
+        .filter(|i| query.matches_image(i))
+        .map(|i| i.container_name.as_str())
+        .collect();
+    assert_eq!(matched, vec!["api", "worker"]);
+}
+
+#[test]
+fn test_registry_policy_violations() {
+    let pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![
+            create_test_container("app", "ghcr.io/ourorg/app:1.0"),
+            create_test_container("other", "ghcr.io/someone-else/app:1.0"),
+            create_test_container("dns", "registry.k8s.io/coredns/coredns:v1.11.1"),
+            create_test_container("ecr", "123456789012.dkr.ecr.eu-west-1.amazonaws.com/api:2"),
+            create_test_container("hub", "nginx:1.25"),
+        ],
+    );
+    let images = process_pod(&pod);
+
+    let policy = RegistryPolicy::new([
+        "ghcr.io/ourorg".parse::<Pattern>().unwrap(),
+        "registry.k8s.io".parse::<Pattern>().unwrap(),
+        "*.dkr.ecr.*.amazonaws.com".parse::<Pattern>().unwrap(),
+    ]);
+    let violations: Vec<String> = policy
+        .violations(&images)
+        .into_iter()
+        .map(|i| i.container_name)
+        .collect();
+    assert_eq!(violations, vec!["other", "hub"]);
+
+    let policy: RegistryPolicy =
+        serde_yaml::from_str("allow:\n  - ghcr.io/*\n  - docker.io\n").unwrap();
+    let violations: Vec<String> = policy
+        .violations(&images)
+        .into_iter()
+        .map(|i| i.container_name)
+        .collect();
+    assert_eq!(violations, vec!["dns", "ecr"]);
+}
+
+#[test]
+fn test_registry_policy_normalizes_docker_hub_entries() {
+    let pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![
+            create_test_container("official", "nginx:1.25"),
+            create_test_container("library", "docker.io/library/redis:7.2"),
+            create_test_container("alias", "index.docker.io/library/busybox:1.36"),
+            create_test_container("namespaced", "linkerd/proxy:edge-25.3.3"),
+            create_test_container("ghcr", "ghcr.io/acme/app:1.0"),
+        ],
+    );
+    let images = process_pod(&pod);
+    let violations = |allow: &[&str]| -> Vec<String> {
+        let policy = RegistryPolicy::new(allow.iter().map(|rule| rule.parse::<Pattern>().unwrap()));
+        policy
+            .violations(&images)
+            .into_iter()
+            .map(|i| i.container_name)
+            .collect()
+    };
+
+    assert_eq!(
+        violations(&["docker.io/library"]),
+        vec!["namespaced", "ghcr"]
+    );
+    assert_eq!(
+        violations(&["index.docker.io/library"]),
+        vec!["namespaced", "ghcr"]
+    );
+    assert_eq!(violations(&["index.docker.io"]), vec!["ghcr"]);
+    assert_eq!(
+        violations(&["index.docker.io/library/nginx", "docker.io/linkerd"]),
+        vec!["library", "alias", "ghcr"]
+    );
+    assert_eq!(
+        violations(&["docker.io/library/*"]),
+        vec!["namespaced", "ghcr"]
+    );
+}
+
+#[test]
+fn test_image_tag_source() {
+    assert_eq!(image_tag_source("nginx"), TagSource::Implicit);
+    assert_eq!(image_tag_source("nginx:latest"), TagSource::Explicit);
+    assert_eq!(image_tag_source("nginx:1.25"), TagSource::Explicit);
+    assert_eq!(image_tag_source("registry:5000/app"), TagSource::Implicit);
+    assert_eq!(
+        image_tag_source("registry:5000/app:v1"),
+        TagSource::Explicit
+    );
+    assert_eq!(image_tag_source("nginx@sha256:abc123"), TagSource::Implicit);
+    assert_eq!(
+        image_tag_source("nginx:1.25@sha256:abc123"),
+        TagSource::Explicit
+    );
+}
+
+#[test]
+fn test_audit_tags() {
+    let pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![
+            create_test_container("untagged", "nginx"),
+            create_test_container("latest", "nginx:latest"),
+            create_test_container("tagged", "nginx:1.25"),
+            create_test_container("pinned", "nginx@sha256:abc123"),
+            create_test_container("both", "nginx:latest@sha256:abc123"),
+        ],
+    );
+    let images = process_pod(&pod);
+
+    assert_eq!(images[0].image_version, "latest");
+    assert_eq!(images[0].tag_source, TagSource::Implicit);
+    assert_eq!(images[1].tag_source, TagSource::Explicit);
+    assert!(!images[1].pinned_by_digest);
+    assert!(images[3].pinned_by_digest);
+
+    let findings: Vec<(String, Vec<TagIssue>)> = audit_tags(&images)
+        .into_iter()
+        .map(|entry| (entry.container_name, entry.issues))
+        .collect();
+    assert_eq!(
+        findings,
+        vec![
+            (
+                "untagged".to_string(),
+                vec![TagIssue::Untagged, TagIssue::Undigested]
+            ),
+            (
+                "latest".to_string(),
+                vec![TagIssue::Latest, TagIssue::Undigested]
+            ),
+            ("tagged".to_string(), vec![TagIssue::Undigested]),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_image_id_digest() {
+    assert_eq!(
+        parse_image_id_digest("sha256:abc123").as_deref(),
+        Some("abc123")
+    );
+    assert_eq!(
+        parse_image_id_digest("docker.io/library/nginx@sha256:abc123").as_deref(),
+        Some("abc123")
+    );
+    assert_eq!(
+        parse_image_id_digest("docker-pullable://nginx@sha256:abc123").as_deref(),
+        Some("abc123")
+    );
+    assert_eq!(parse_image_id_digest(""), None);
+    assert_eq!(parse_image_id_digest("docker.io/library/nginx"), None);
+}
+
+fn create_replica(name: &str, image: &str, running_image: &str, image_id: &str) -> Pod {
+    let mut pod = create_test_pod(name, "default", vec![create_test_container("app", image)]);
+    pod.metadata.owner_references = Some(vec![OwnerReference {
+        kind: "ReplicaSet".to_string(),
+        name: "api-7d9f8b".to_string(),
+        controller: Some(true),
+        ..Default::default()
+    }]);
+    pod.status = Some(PodStatus {
+        container_statuses: Some(vec![ContainerStatus {
+            name: "app".to_string(),
+            image: running_image.to_string(),
+            image_id: image_id.to_string(),
+            ..Default::default()
+        }]),
+        ..Default::default()
+    });
+    pod
+}
+
+#[test]
+fn test_detect_drift() {
+    let pods = vec![
+        create_replica(
+            "api-0",
+            "nginx:1.25",
+            "docker.io/library/nginx:1.25",
+            "docker.io/library/nginx@sha256:aaa",
+        ),
+        create_replica(
+            "api-1",
+            "nginx:1.25",
+            "docker.io/library/nginx:1.25",
+            "docker.io/library/nginx@sha256:bbb",
+        ),
+        create_replica(
+            "api-2",
+            "nginx:1.25",
+            "docker.io/library/nginx:1.24",
+            "docker.io/library/nginx@sha256:aaa",
+        ),
+    ];
+    let rows = detect_drift(&pods);
+
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].owner, "ReplicaSet/api-7d9f8b");
+    assert_eq!(rows[0].digest, "aaa");
+    assert!(!rows[0].image_mismatch);
+    assert!(rows.iter().all(|row| row.tag_repushed));
+    assert!(rows[2].image_mismatch);
+
+    let pinned = vec![
+        create_replica(
+            "web-0",
+            "nginx@sha256:aaa",
+            "docker.io/library/nginx@sha256:aaa",
+            "docker.io/library/nginx@sha256:aaa",
+        ),
+        create_replica("web-1", "nginx:1.25", "", ""),
+    ];
+    let rows = detect_drift(&pinned);
+    assert!(rows.iter().all(|row| !row.has_drift()));
+
+    // Runtimes reporting the image config ID give no repo digest to compare with
+    let config_id = [
+        create_replica(
+            "web-0",
+            "nginx@sha256:aaa",
+            "docker.io/library/nginx@sha256:aaa",
+            "sha256:ccc",
+        ),
+        create_replica(
+            "web-1",
+            "nginx@sha256:aaa",
+            "docker.io/library/nginx@sha256:aaa",
+            "docker.io/library/nginx@sha256:bbb",
+        ),
+    ];
+    let rows = detect_drift(&config_id);
+    assert_eq!(rows[0].digest, "ccc");
+    assert!(!rows[0].image_mismatch);
+    assert!(rows[1].image_mismatch);
+
+    // Replicas of two ReplicaSets of a Deployment, e.g. during a rollout, form one workload
+    let rollout: Vec<Pod> = [("web-5d8f", "aaa"), ("web-7c9b", "bbb")]
+        .into_iter()
+        .map(|(replica_set, digest)| {
+            let mut pod = create_replica(
+                &format!("{}-x7k2p", replica_set),
+                "nginx:1.25",
+                "docker.io/library/nginx:1.25",
+                &format!("docker.io/library/nginx@sha256:{}", digest),
+            );
+            pod.metadata.owner_references.as_mut().unwrap()[0].name = replica_set.to_string();
+            let hash = replica_set.trim_start_matches("web-").to_string();
+            pod.metadata.labels = Some([("pod-template-hash".to_string(), hash)].into());
+            pod
+        })
+        .collect();
+    let rows = detect_drift(&rollout);
+    assert!(rows.iter().all(|row| row.owner == "Deployment/web"));
+    assert!(rows.iter().all(|row| row.tag_repushed));
+
+    let bare = create_test_pod(
+        "debug",
+        "default",
+        vec![create_test_container("app", "nginx:1.25")],
+    );
+    assert_eq!(detect_drift([&bare])[0].owner, "Pod/debug");
+}
+
+#[test]
+fn test_process_pod_normalizes_docker_hub_references() {
+    let pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![
+            create_test_container("a", "nginx:1.25"),
+            create_test_container("b", "library/nginx:1.25"),
+            create_test_container("c", "docker.io/library/nginx:1.25"),
+            create_test_container("d", "index.docker.io/library/nginx:1.25"),
+            create_test_container("e", "registry.hub.docker.com/linkerd/proxy:1.0"),
+        ],
+    );
+
+    let images = process_pod(&pod);
+    for image in &images[..4] {
+        assert_eq!(image.registry, "docker.io");
+        assert_eq!(image.image_name, "nginx");
+    }
+    assert_eq!(images[4].registry, "docker.io");
+    assert_eq!(images[4].image_name, "linkerd/proxy");
+
+    let raw = RegistryResolver {
+        normalize: false,
+        ..Default::default()
+    };
+    let images = process_pod_with(&pod, &raw);
+    assert_eq!(images[1].image_name, "library/nginx");
+    assert_eq!(images[3].registry, "index.docker.io");
+    assert_eq!(images[4].registry, "registry.hub.docker.com");
+
+    let spec = pod.spec.as_ref().unwrap();
+    let usage = summarize_registry_usage_with(
+        vec![(workload("Pod", "test-pod"), spec)],
+        &RegistryResolver::default(),
+    );
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].registry, "docker.io");
+    assert_eq!(usage[0].containers, 5);
+
+    let usage = summarize_registry_usage_with(vec![(workload("Pod", "test-pod"), spec)], &raw);
+    let registries: Vec<&str> = usage.iter().map(|u| u.registry.as_str()).collect();
+    assert_eq!(
+        registries,
+        vec!["docker.io", "index.docker.io", "registry.hub.docker.com"]
+    );
+}
+
+#[test]
+fn test_process_pod_with_mirrors_reports_upstream() {
+    let config: RegistryConfig = serde_yaml::from_str(
+        "aliases:\n  registry.corp: [registry.corp.internal]\nmirrors:\n  - prefix: mirror.corp/dockerhub\n    upstream: index.docker.io\n  - prefix: mirror.corp/ghcr\n    upstream: ghcr.io\n",
+    )
+    .unwrap();
+    let resolver = RegistryResolver::from_config(config, true);
+
+    let pod = create_test_pod(
+        "test-pod",
+        "default",
+        vec![
+            create_test_container("proxied", "mirror.corp/dockerhub/nginx:1.25"),
+            create_test_container("ghcr", "mirror.corp/ghcr/acme/app:1.0"),
+            create_test_container("aliased", "registry.corp.internal/team/api:2"),
+            create_test_container("direct", "quay.io/coreos/etcd:v3.3.0"),
+        ],
+    );
+    let images = process_pod_with(&pod, &resolver);
+
+    let registries: Vec<(&str, &str)> = images
+        .iter()
+        .map(|i| (i.registry.as_str(), i.upstream.as_str()))
+        .collect();
+    assert_eq!(
+        registries,
+        vec![
+            ("mirror.corp", "docker.io"),
+            ("mirror.corp", "ghcr.io"),
+            ("registry.corp", "registry.corp"),
+            ("quay.io", "quay.io"),
+        ]
+    );
+
+    let spec = pod.spec.as_ref().unwrap();
+    let usage = summarize_registry_usage_with(vec![(workload("Pod", "test-pod"), spec)], &resolver);
+    let mirror = usage.iter().find(|u| u.registry == "mirror.corp").unwrap();
+    assert_eq!(mirror.upstreams, vec!["docker.io", "ghcr.io"]);
+}
+
+#[test]
+fn test_pod_workload() {
+    let mut pod = create_replica("api-7d9f8b-x2x7q", "nginx:1.25", "", "");
+    assert_eq!(pod_workload(&pod), "ReplicaSet/api-7d9f8b");
+
+    pod.metadata.labels = Some(
+        [("pod-template-hash".to_string(), "7d9f8b".to_string())]
+            .into_iter()
+            .collect(),
+    );
+    assert_eq!(pod_workload(&pod), "Deployment/api");
+
+    pod.metadata.owner_references = Some(vec![OwnerReference {
+        kind: "StatefulSet".to_string(),
+        name: "db".to_string(),
+        controller: Some(true),
+        ..Default::default()
+    }]);
+    assert_eq!(pod_workload(&pod), "StatefulSet/db");
+
+    let bare = create_test_pod("debug", "default", vec![]);
+    assert_eq!(pod_workload(&bare), "Pod/debug");
+}
+
+#[test]
+fn test_metadata_entries() {
+    let metadata = ObjectMeta {
+        name: Some("api-0".to_string()),
+        namespace: Some("payments".to_string()),
+        labels: Some(
+            [
+                ("app.kubernetes.io/name", "api"),
+                ("app.kubernetes.io/version", "1.4.0"),
+                ("team", "payments"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        ),
+        annotations: Some(
+            [("prometheus.io/scrape".to_string(), "true".to_string())]
+                .into_iter()
+                .collect(),
+        ),
+        ..Default::default()
+    };
+
+    let labels = metadata_entries("Pod", &metadata, MetadataField::Labels, &[]);
+    assert_eq!(labels.len(), 3);
+    assert_eq!(
+        labels[0],
+        MetadataEntry {
+            kind: "Pod".to_string(),
+            namespace: "payments".to_string(),
+            name: "api-0".to_string(),
+            key: "app.kubernetes.io/name".to_string(),
+            value: "api".to_string(),
+        }
+    );
+
+    let keys = ["app.kubernetes.io/*".parse::<Pattern>().unwrap()];
+    let labels = metadata_entries("Pod", &metadata, MetadataField::Labels, &keys);
+    let keys: Vec<&str> = labels.iter().map(|entry| entry.key.as_str()).collect();
+    assert_eq!(
+        keys,
+        vec!["app.kubernetes.io/name", "app.kubernetes.io/version"]
+    );
+
+    let annotations = metadata_entries("Pod", &metadata, MetadataField::Annotations, &[]);
+    assert_eq!(annotations.len(), 1);
+    assert_eq!(annotations[0].value, "true");
+
+    let keys = [
+        "team".parse::<Pattern>().unwrap(),
+        "missing".parse::<Pattern>().unwrap(),
+    ];
+    assert_eq!(
+        metadata_entries("Pod", &metadata, MetadataField::Annotations, &keys),
+        vec![]
+    );
+    assert!(
+        metadata_entries("Node", &ObjectMeta::default(), MetadataField::Labels, &[]).is_empty()
+    );
+}
+
+#[test]
+fn test_label_violations() {
+    let metadata = ObjectMeta {
+        name: Some("api".to_string()),
+        namespace: Some("payments".to_string()),
+        labels: Some(
+            [("team", "Payments"), ("app.kubernetes.io/name", "api")]
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    let requirements = [
+        LabelRequirement::label("team=^[a-z-]+$").unwrap(),
+        LabelRequirement::label("app.kubernetes.io/name").unwrap(),
+        LabelRequirement::label("cost-center").unwrap(),
+        LabelRequirement::annotation("owner").unwrap(),
+    ];
+
+    let violations = label_violations("Deployment", &metadata, &requirements);
+    assert_eq!(violations.len(), 3);
+    assert_eq!(
+        violations[0],
+        LabelViolation {
+            kind: "Deployment".to_string(),
+            namespace: "payments".to_string(),
+            name: "api".to_string(),
+            field: MetadataField::Labels,
+            key: "team".to_string(),
+            value: "Payments".to_string(),
+            issue: LabelIssue::Mismatch,
+            expected: "^[a-z-]+$".to_string(),
+        }
+    );
+    assert_eq!(violations[1].key, "cost-center");
+    assert_eq!(violations[1].issue, LabelIssue::Missing);
+    assert_eq!(violations[1].value, "");
+    assert_eq!(violations[2].field, MetadataField::Annotations);
+    assert_eq!(violations[2].issue, LabelIssue::Missing);
+
+    // A label does not satisfy an annotation requirement with the same key
+    let requirements = [LabelRequirement::annotation("team").unwrap()];
+    assert_eq!(
+        label_violations("Deployment", &metadata, &requirements)[0].issue,
+        LabelIssue::Missing
+    );
+
+    assert!(LabelRequirement::label("").is_err());
+    assert!(LabelRequirement::label("team=[").is_err());
+}
+
+#[test]
+fn test_label_requirement_matches_whole_value() {
+    let requirements = [LabelRequirement::label("env=prod|staging").unwrap()];
+    assert_eq!(requirements[0].expected(), "prod|staging");
+
+    for (value, complies) in [
+        ("prod", true),
+        ("staging", true),
+        ("preprod", false),
+        ("production-old", false),
+        ("staging-2", false),
+    ] {
+        let metadata = ObjectMeta {
+            name: Some("api".to_string()),
+            labels: Some([("env".to_string(), value.to_string())].into()),
+            ..Default::default()
+        };
+        let violations = label_violations("Deployment", &metadata, &requirements);
+        assert_eq!(violations.is_empty(), complies, "env={}", value);
+    }
+}
+
+#[test]
+fn test_describe_probe() {
+    let http = Probe {
+        http_get: Some(HTTPGetAction {
+            path: Some("/healthz".to_string()),
+            port: IntOrString::Int(8080),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert_eq!(describe_probe(&http), "http-get http://:8080/healthz");
+
+    let https = Probe {
+        http_get: Some(HTTPGetAction {
+            port: IntOrString::String("admin".to_string()),
+            scheme: Some("HTTPS".to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert_eq!(describe_probe(&https), "http-get https://:admin/");
+
+    let tcp = Probe {
+        tcp_socket: Some(TCPSocketAction {
+            port: IntOrString::Int(5432),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    assert_eq!(describe_probe(&tcp), "tcp-socket :5432");
+
+    let exec = Probe {
+        exec: Some(ExecAction {
+            command: Some(vec!["cat".to_string(), "/tmp/ready".to_string()]),
+        }),
+        ..Default::default()
+    };
+    assert_eq!(describe_probe(&exec), "exec [cat /tmp/ready]");
+}
+
+#[test]
+fn test_pod_health() {
+    let probed = Container {
+        readiness_probe: Some(Probe {
+            tcp_socket: Some(TCPSocketAction {
+                port: IntOrString::Int(8080),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..create_test_container("app", "ghcr.io/acme/app:1.0")
+    };
+    let sidecar = Container {
+        restart_policy: Some("Always".to_string()),
+        ..create_test_container("proxy", "cr.l5d.io/linkerd/proxy:edge-25.3.3")
+    };
+    let mut pod = create_test_pod(
+        "app-0",
+        "payments",
+        vec![
+            probed,
+            create_test_container("worker", "ghcr.io/acme/worker:1.0"),
+        ],
+    );
+    let spec = pod.spec.as_mut().unwrap();
+    spec.init_containers = Some(vec![
+        create_test_container("migrate", "ghcr.io/acme/app:1.0"),
+        sidecar,
+    ]);
+
+    let terminated = |reason: &str, exit_code: i32| ContainerState {
+        terminated: Some(ContainerStateTerminated {
+            reason: Some(reason.to_string()),
+            exit_code,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    pod.status = Some(PodStatus {
+        init_container_statuses: Some(vec![ContainerStatus {
+            name: "migrate".to_string(),
+            state: Some(terminated("Completed", 0)),
+            ..Default::default()
+        }]),
+        container_statuses: Some(vec![
+            ContainerStatus {
+                name: "app".to_string(),
+                ready: true,
+                restart_count: 1,
+                state: Some(ContainerState {
+                    running: Some(Default::default()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ContainerStatus {
+                name: "worker".to_string(),
+                restart_count: 7,
+                state: Some(ContainerState {
+                    waiting: Some(ContainerStateWaiting {
+                        reason: Some("CrashLoopBackOff".to_string()),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                last_state: Some(terminated("OOMKilled", 137)),
+                ..Default::default()
+            },
+        ]),
+        ..Default::default()
+    });
+
+    let rows = pod_health(&pod, DEFAULT_RESTART_THRESHOLD);
+    let names: Vec<&str> = rows.iter().map(|row| row.container_name.as_str()).collect();
+    assert_eq!(names, vec!["migrate", "proxy", "app", "worker"]);
+
+    // Plain init containers cannot have probes, sidecars can
+    assert_eq!(rows[0].state, "Completed");
+    assert!(rows[0].issues.is_empty());
+    assert_eq!(rows[1].container_type, ContainerType::Init);
+    assert_eq!(rows[1].issues, vec![HealthIssue::NoProbes]);
+    assert_eq!(rows[1].state, "");
+
+    let app = &rows[2];
+    assert_eq!(app.readiness, "tcp-socket :8080");
+    assert_eq!(app.liveness, "");
+    assert!(app.ready);
+    assert_eq!(app.state, "Running");
+    assert!(!app.is_unhealthy());
+
+    let worker = &rows[3];
+    assert_eq!(worker.state, "CrashLoopBackOff");
+    assert_eq!(worker.restart_count, 7);
+    assert_eq!(worker.last_termination_reason, "OOMKilled");
+    assert_eq!(worker.last_exit_code, Some(137));
+    assert!(worker.oom_killed);
+    assert_eq!(
+        worker.issues,
+        vec![
+            HealthIssue::NoProbes,
+            HealthIssue::Restarts,
+            HealthIssue::OomKilled
+        ]
+    );
+
+    // A threshold of 0 disables the restart check
+    assert_eq!(
+        pod_health(&pod, 0)[3].issues,
+        vec![HealthIssue::NoProbes, HealthIssue::OomKilled]
+    );
+}
+
+#[test]
+fn test_snapshot_diff() {
+    let images_of = |pods: Vec<(&str, Vec<Container>)>| {
+        pods.into_iter()
+            .flat_map(|(name, containers)| {
+                process_pod(&create_test_pod(name, "default", containers))
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let before = ImageSnapshot::new(
+        "staging",
+        "default",
+        images_of(vec![
+            (
+                "api",
+                vec![
+                    create_test_container("app", "ghcr.io/acme/api:1.0"),
+                    create_test_container("linkerd-proxy", "cr.l5d.io/linkerd/proxy:edge-25.2.1"),
+                ],
+            ),
+            (
+                "worker",
+                vec![create_test_container("app", "ghcr.io/acme/worker:3.1")],
+            ),
+        ]),
+    );
+    let after = ImageSnapshot::new(
+        "prod",
+        "default",
+        images_of(vec![
+            (
+                "api",
+                vec![
+                    create_test_container("app", "ghcr.io/acme/api:1.0"),
+                    create_test_container("linkerd-proxy", "cr.l5d.io/linkerd/proxy:edge-25.3.3"),
+                ],
+            ),
+            ("cache", vec![create_test_container("redis", "redis:7.2")]),
+        ]),
+    );
+
+    let changes = before.diff(&after);
+    let summary: Vec<(ImageChange, &str, &str)> = changes
+        .iter()
+        .map(|c| (c.change, c.workload.as_str(), c.container_name.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (ImageChange::Changed, "Pod/api", "linkerd-proxy"),
+            (ImageChange::Added, "Pod/cache", "redis"),
+            (ImageChange::Removed, "Pod/worker", "app"),
+        ]
+    );
+    assert_eq!(
+        changes[0].before,
+        vec!["cr.l5d.io/linkerd/proxy:edge-25.2.1"]
+    );
+    assert_eq!(
+        changes[0].after,
+        vec!["cr.l5d.io/linkerd/proxy:edge-25.3.3"]
+    );
+    assert!(changes[1].before.is_empty());
+    assert_eq!(changes[1].after, vec!["docker.io/redis:7.2"]);
+
+    assert!(before.diff(&before).is_empty());
+}
+
+#[test]
+fn test_snapshot_save_and_load() {
+    let pod = create_test_pod(
+        "api",
+        "default",
+        vec![create_test_container("app", "ghcr.io/acme/api:1.0")],
+    );
+    let snapshot = ImageSnapshot::new("staging", "", process_pod(&pod));
+
+    let path = std::env::temp_dir().join(format!("kelper-snapshot-{}.json", std::process::id()));
+    snapshot.save(&path).unwrap();
+    let loaded = ImageSnapshot::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, snapshot);
+    assert_eq!(loaded.cluster, "staging");
+    assert_eq!(loaded.images[0].workload, "Pod/api");
+}
+
+/// Write a kubeconfig whose contexts point at clusters that cannot be reached
+fn write_fleet_kubeconfig(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("kelper-{}-{}.yaml", name, std::process::id()));
+    std::fs::write(
+        &path,
+        r#"apiVersion: v1
+kind: Config
+clusters:
+- name: unreachable
+  cluster:
+    server: https://127.0.0.1:1
+users:
+- name: admin
+  user:
+    token: secret
+contexts:
+- name: prod-eu
+  context:
+    cluster: unreachable
+    user: admin
+- name: prod-us
+  context:
+    cluster: unreachable
+    user: admin
+current-context: prod-eu
+"#,
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn test_kubeconfig_contexts() {
+    let path = write_fleet_kubeconfig("contexts");
+    let contexts = kubeconfig_contexts(Some(&path)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(contexts, vec!["prod-eu", "prod-us"]);
+}
+
+#[tokio::test]
+async fn test_query_fleet_reports_failed_clusters() {
+    let path = write_fleet_kubeconfig("fleet");
+    let options = ClientOptions {
+        kubeconfig: Some(path.clone()),
+        ..Default::default()
+    };
+    let contexts = vec![
+        "prod-eu".to_string(),
+        "missing".to_string(),
+        "prod-us".to_string(),
+    ];
+
+    let fleet = query_fleet(
+        &options,
+        &contexts,
+        &RegistryResolver::default(),
+        2,
+        |client| async move { client.query_pod_images(&ImageQuery::default()).await },
+    )
+    .await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(fleet.records.is_empty());
+    let failed: Vec<&str> = fleet.failures.iter().map(|f| f.cluster.as_str()).collect();
+    assert_eq!(failed, vec!["prod-eu", "missing", "prod-us"]);
+    assert!(fleet.failures[1].error.contains("missing"));
+}
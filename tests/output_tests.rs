@@ -0,0 +1,317 @@
+use kelper::{
+    parse_custom_columns, render_image_events, render_pod_images, render_structured, ClusterRecord,
+    ContainerType, ImageChange, ImageEvent, OutputFormat, PodImage, PodImagePages, ProviderKind,
+    TagSource,
+};
+
+fn create_test_image(pod_name: &str, container_name: &str) -> PodImage {
+    PodImage {
+        pod_name: pod_name.to_string(),
+        node_name: "worker-1".to_string(),
+        namespace: "default".to_string(),
+        workload: "Deployment/api".to_string(),
+        container_name: container_name.to_string(),
+        container_type: ContainerType::Regular,
+        image_name: "linkerd/proxy".to_string(),
+        image_version: "edge-25.3.3".to_string(),
+        tag_source: TagSource::Explicit,
+        pinned_by_digest: false,
+        registry: "cr.l5d.io".to_string(),
+        upstream: "cr.l5d.io".to_string(),
+        provider: ProviderKind::SelfHosted,
+        provider_account: String::new(),
+        provider_region: String::new(),
+        digest: "496429c2a4a4".to_string(),
+    }
+}
+
+#[test]
+fn test_render_pod_images_json_includes_wide_fields() {
+    let images = vec![create_test_image("api-0", "linkerd-proxy")];
+    let rendered = render_pod_images(&images, &OutputFormat::Json).unwrap();
+
+    let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+    let first = &parsed[0];
+    assert_eq!(first["pod_name"], "api-0");
+    assert_eq!(first["namespace"], "default");
+    assert_eq!(first["container_name"], "linkerd-proxy");
+    assert_eq!(first["registry"], "cr.l5d.io");
+    assert_eq!(first["image_name"], "linkerd/proxy");
+    assert_eq!(first["image_version"], "edge-25.3.3");
+    assert_eq!(first["digest"], "496429c2a4a4");
+    assert_eq!(first["node_name"], "worker-1");
+}
+
+#[test]
+fn test_render_pod_images_yaml() {
+    let images = vec![create_test_image("api-0", "linkerd-proxy")];
+    let rendered = render_pod_images(&images, &OutputFormat::Yaml).unwrap();
+
+    assert!(rendered.starts_with("- pod_name: api-0\n"));
+    assert!(rendered.contains("  digest: 496429c2a4a4\n"));
+    assert!(rendered.contains("  node_name: worker-1\n"));
+}
+
+#[test]
+fn test_render_pod_images_csv() {
+    let images = vec![
+        create_test_image("api-0", "linkerd-proxy"),
+        create_test_image("api-1", "linkerd-proxy"),
+    ];
+    let rendered = render_pod_images(&images, &OutputFormat::Csv).unwrap();
+    let lines: Vec<&str> = rendered.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines[0],
+        "pod_name,node_name,namespace,workload,container_name,container_type,image_name,image_version,tag_source,pinned_by_digest,registry,upstream,provider,provider_account,provider_region,digest"
+    );
+    assert_eq!(
+        lines[1],
+        "api-0,worker-1,default,Deployment/api,linkerd-proxy,regular,linkerd/proxy,edge-25.3.3,explicit,false,cr.l5d.io,cr.l5d.io,self-hosted,,,496429c2a4a4"
+    );
+}
+
+#[test]
+fn test_render_empty_pod_images() {
+    let rendered = render_pod_images(&[], &OutputFormat::Json).unwrap();
+    assert_eq!(rendered, "[]\n");
+    let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+    assert_eq!(parsed, serde_json::json!([]));
+
+    assert_eq!(render_pod_images(&[], &OutputFormat::Yaml).unwrap(), "[]\n");
+
+    let rendered = render_pod_images(&[], &OutputFormat::Csv).unwrap();
+    assert_eq!(rendered.lines().count(), 1);
+    assert!(rendered.starts_with("pod_name,node_name,namespace,"));
+    assert!(rendered.ends_with(",digest\n"));
+
+    let rendered = render_pod_images(&[], &OutputFormat::Tsv).unwrap();
+    assert!(rendered.starts_with("pod_name\tnode_name\t"));
+    assert_eq!(rendered.lines().count(), 1);
+}
+
+#[test]
+fn test_render_pod_images_tsv() {
+    let images = vec![create_test_image("api-0", "linkerd-proxy")];
+    let rendered = render_pod_images(&images, &OutputFormat::Tsv).unwrap();
+    let lines: Vec<&str> = rendered.lines().collect();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].split('\t').count(), 16);
+    assert_eq!(lines[1].split('\t').next(), Some("api-0"));
+}
+
+#[test]
+fn test_render_pod_images_normal_table_omits_wide_columns() {
+    let images = vec![create_test_image("api-0", "linkerd-proxy")];
+
+    let normal = render_pod_images(&images, &OutputFormat::Normal).unwrap();
+    assert!(normal.contains("POD"));
+    assert!(!normal.contains("DIGEST"));
+    assert!(!normal.contains("worker-1"));
+
+    let wide = render_pod_images(&images, &OutputFormat::Wide).unwrap();
+    assert!(wide.contains("DIGEST"));
+    assert!(wide.contains("worker-1"));
+}
+
+#[test]
+fn test_render_pod_images_custom_columns() {
+    let images = vec![create_test_image("api-0", "linkerd-proxy")];
+    let format: OutputFormat = "custom-columns=POD:.pod_name,IMG:.image_name,MISSING:.nope"
+        .parse()
+        .unwrap();
+    let rendered = render_pod_images(&images, &format).unwrap();
+    let lines: Vec<Vec<&str>> = rendered
+        .lines()
+        .filter(|line| !line.starts_with('-'))
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+
+    assert_eq!(lines[0], vec!["POD", "IMG", "MISSING"]);
+    assert_eq!(lines[1], vec!["api-0", "linkerd/proxy", "<none>"]);
+}
+
+#[test]
+fn test_render_pod_images_jsonpath_range() {
+    let images = vec![
+        create_test_image("api-0", "linkerd-proxy"),
+        create_test_image("api-1", "server"),
+    ];
+    let format: OutputFormat =
+        r#"jsonpath={range .items[*]}{.pod_name}{"\t"}{.container_name}{"\n"}{end}"#
+            .parse()
+            .unwrap();
+    let rendered = render_pod_images(&images, &format).unwrap();
+
+    assert_eq!(rendered, "api-0\tlinkerd-proxy\napi-1\tserver\n");
+}
+
+#[test]
+fn test_render_pod_images_jsonpath_braces_in_literals() {
+    let images = vec![
+        create_test_image("api-0", "linkerd-proxy"),
+        create_test_image("api-1", "server"),
+    ];
+    let format: OutputFormat = r#"jsonpath={range .items[*]}{"{"}{.pod_name}{"}\n"}{end}"#
+        .parse()
+        .unwrap();
+    let rendered = render_pod_images(&images, &format).unwrap();
+
+    assert_eq!(rendered, "{api-0}\n{api-1}\n");
+
+    let format: OutputFormat = r#"jsonpath={.items[0]['pod_name']}{"\"}"}"#.parse().unwrap();
+    assert_eq!(render_pod_images(&images, &format).unwrap(), "api-0\"}");
+
+    assert!(r#"jsonpath={"}"#.parse::<OutputFormat>().is_err());
+}
+
+#[test]
+fn test_render_pod_images_jsonpath_wildcard_and_index() {
+    let images = vec![
+        create_test_image("api-0", "linkerd-proxy"),
+        create_test_image("api-1", "server"),
+    ];
+
+    let format: OutputFormat = "jsonpath={.items[*].pod_name}".parse().unwrap();
+    assert_eq!(render_pod_images(&images, &format).unwrap(), "api-0 api-1");
+
+    let format: OutputFormat = "jsonpath=last={.items[-1].container_name}".parse().unwrap();
+    assert_eq!(render_pod_images(&images, &format).unwrap(), "last=server");
+}
+
+#[test]
+fn test_parse_custom_columns_with_commas_in_paths() {
+    let columns =
+        parse_custom_columns(r#"POD:.pod_name,KEY:.labels['a,b'],Q:.labels["c,d"]"#).unwrap();
+    let headers: Vec<&str> = columns.iter().map(|c| c.header.as_str()).collect();
+    assert_eq!(headers, vec!["POD", "KEY", "Q"]);
+
+    let row = serde_json::json!({"pod_name": "api-0", "labels": {"a,b": "x", "c,d": "y"}});
+    let cells: Vec<String> = columns.iter().map(|c| c.cell(&row)).collect();
+    assert_eq!(cells, vec!["api-0", "x", "y"]);
+}
+
+#[test]
+fn test_parse_invalid_jsonpath_templates() {
+    assert!("jsonpath={.items[*].pod_name"
+        .parse::<OutputFormat>()
+        .is_err());
+    assert!("jsonpath={range .items[*]}{.pod_name}"
+        .parse::<OutputFormat>()
+        .is_err());
+    assert!("jsonpath={end}".parse::<OutputFormat>().is_err());
+    assert!("custom-columns=".parse::<OutputFormat>().is_err());
+    assert!("custom-columns=POD".parse::<OutputFormat>().is_err());
+}
+
+#[test]
+fn test_render_image_events_streams_without_repeating_headers() {
+    let events = vec![ImageEvent {
+        change: ImageChange::Changed,
+        image: create_test_image("api-0", "linkerd-proxy"),
+        previous: "cr.l5d.io/linkerd/proxy:edge-25.2.1".to_string(),
+    }];
+
+    let first = render_image_events(&events, &OutputFormat::Normal, true).unwrap();
+    assert!(first.contains("CHANGE"));
+    assert!(first.contains("PREVIOUS"));
+    assert!(first.contains("cr.l5d.io/linkerd/proxy:edge-25.2.1"));
+
+    let next = render_image_events(&events, &OutputFormat::Normal, false).unwrap();
+    assert!(!next.contains("CHANGE"));
+    assert!(next.contains("changed"));
+
+    let csv = render_image_events(&events, &OutputFormat::Csv, false).unwrap();
+    assert_eq!(csv.lines().count(), 1);
+    assert!(csv.starts_with("changed,api-0,"));
+
+    let json = render_image_events(&events, &OutputFormat::Json, true).unwrap();
+    assert_eq!(json.lines().count(), 1);
+    let parsed: serde_json::Value = serde_json::from_str(json.trim()).unwrap();
+    assert_eq!(parsed["change"], "changed");
+    assert_eq!(parsed["pod_name"], "api-0");
+    assert_eq!(parsed["previous"], "cr.l5d.io/linkerd/proxy:edge-25.2.1");
+}
+
+#[test]
+fn test_render_cluster_records_csv() {
+    let images = vec![ClusterRecord {
+        cluster: "prod-eu".to_string(),
+        record: create_test_image("api-0", "linkerd-proxy"),
+    }];
+    let rendered = render_structured(&images, &OutputFormat::Csv).unwrap();
+    let lines: Vec<&str> = rendered.lines().collect();
+
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("cluster,pod_name,node_name,namespace,"));
+    assert!(lines[1].starts_with("prod-eu,api-0,worker-1,default,"));
+}
+
+/// Render images split into pages, as `get images` prints them while listing
+fn render_pages(pages: &[Vec<PodImage>], format: &OutputFormat) -> Vec<String> {
+    let mut printer = PodImagePages::new(format);
+    let mut rendered: Vec<String> = pages
+        .iter()
+        .map(|page| printer.render_page(page).unwrap())
+        .collect();
+    rendered.push(printer.render_end().unwrap());
+    rendered
+}
+
+#[test]
+fn test_pod_image_pages_match_the_whole_document() {
+    let images: Vec<PodImage> = (0..5)
+        .map(|i| create_test_image(&format!("api-{}", i), "linkerd-proxy"))
+        .collect();
+    let pages = vec![images[..2].to_vec(), Vec::new(), images[2..].to_vec()];
+
+    for format in [OutputFormat::Json, OutputFormat::Yaml, OutputFormat::Csv] {
+        let rendered = render_pages(&pages, &format);
+        assert_eq!(
+            rendered.concat(),
+            render_pod_images(&images, &format).unwrap(),
+            "{}",
+            format
+        );
+    }
+
+    // The JSON array is opened by the first page and only closed at the end
+    let rendered = render_pages(&pages, &OutputFormat::Json);
+    assert!(rendered[0].starts_with("[\n  {"));
+    assert!(rendered[2].starts_with(",\n  {"));
+    assert_eq!(rendered[3], "\n]\n");
+
+    for format in [OutputFormat::Json, OutputFormat::Yaml, OutputFormat::Csv] {
+        assert_eq!(
+            render_pages(&[Vec::new()], &format).concat(),
+            render_pod_images(&[], &format).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_pod_image_pages_keep_table_column_widths() {
+    let first = vec![create_test_image("api-server-0", "linkerd-proxy")];
+    let second = vec![create_test_image("db-0", "pg")];
+    let rendered = render_pages(&[first, second], &OutputFormat::Normal);
+
+    let header = rendered[0].lines().next().unwrap();
+    assert!(header.starts_with("POD "));
+    assert!(!rendered[1].contains("POD"));
+
+    // Later rows line up with the first page although their values are shorter
+    let column = |line: &str, header: &str| {
+        let offset = rendered[0].lines().next().unwrap().find(header).unwrap();
+        line[offset..]
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .to_string()
+    };
+    let row = rendered[1].lines().next().unwrap();
+    assert_eq!(column(row, "NAMESPACE"), "default");
+    assert_eq!(column(row, "IMAGE"), "linkerd/proxy");
+    assert_eq!(rendered[2], "");
+}
@@ -0,0 +1,272 @@
+mod common;
+
+use common::{create_pods, FakeApiServer};
+use kelper::{
+    query_fleet, ImageQuery, LabelRequirement, LabelTarget, RegistryResolver, RegistrySource,
+    DEFAULT_RESTART_THRESHOLD,
+};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn test_stream_pod_images_pages_through_pods() {
+    let server = FakeApiServer::start(create_pods(1200)).await;
+    let client = server.client(500).await;
+
+    let mut pages = Vec::new();
+    let total = client
+        .stream_pod_images(&ImageQuery::default(), |images| {
+            pages.push(images.len());
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(total, 1200);
+    assert_eq!(pages, vec![500, 500, 200]);
+
+    let requests = server.pod_list_requests();
+    let pages: Vec<&String> = requests
+        .iter()
+        .filter(|request| request.contains("limit=500"))
+        .collect();
+    assert_eq!(pages.len(), 3);
+    assert!(!pages[0].contains("continue="));
+    assert!(pages[1].contains("continue=500"));
+    assert!(pages[2].contains("continue=1000"));
+}
+
+#[tokio::test]
+async fn test_query_pod_images_without_chunking() {
+    let server = FakeApiServer::start(create_pods(1200)).await;
+    let client = server.client(0).await;
+
+    let images = client
+        .query_pod_images(&ImageQuery {
+            all_namespaces: true,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(images.len(), 1200);
+    assert_eq!(images[1199].pod_name, "pod-1199");
+    assert!(server
+        .pod_list_requests()
+        .iter()
+        .all(|request| !request.contains("limit=")));
+}
+
+#[tokio::test]
+async fn test_stream_pod_images_stops_on_callback_error() {
+    let server = FakeApiServer::start(create_pods(1200)).await;
+    let client = server.client(500).await;
+
+    let mut pages = 0;
+    let result = client
+        .stream_pod_images(&ImageQuery::default(), |_| {
+            pages += 1;
+            anyhow::bail!("stdout closed")
+        })
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(pages, 1);
+    assert_eq!(
+        server
+            .pod_list_requests()
+            .iter()
+            .filter(|request| request.contains("limit=500"))
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_health_drift_and_label_audit_page_through_pods() {
+    let server = FakeApiServer::start(create_pods(1200)).await;
+    let client = server.client(500).await;
+
+    let health = client
+        .get_container_health(&ImageQuery::default(), DEFAULT_RESTART_THRESHOLD)
+        .await
+        .unwrap();
+    assert_eq!(health.len(), 1200);
+
+    let drift = client
+        .get_image_drift(&ImageQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(drift.len(), 1200);
+
+    let requirements = [LabelRequirement::label("team").unwrap()];
+    let audit = client
+        .audit_labels("default", false, None, &[LabelTarget::Pods], &requirements)
+        .await
+        .unwrap();
+    assert_eq!(audit.objects, 1200);
+
+    // Three pages of 500 pods for each of the three commands
+    let pages = server
+        .pod_list_requests()
+        .iter()
+        .filter(|request| request.contains("limit=500"))
+        .count();
+    assert_eq!(pages, 9);
+}
+
+#[tokio::test]
+async fn test_empty_namespace_is_an_empty_result() {
+    let server = FakeApiServer::start(Vec::new()).await;
+    let client = server.client(500).await;
+    let query = ImageQuery::default();
+
+    assert!(client.query_pod_images(&query).await.unwrap().is_empty());
+    assert!(client
+        .get_container_health(&query, DEFAULT_RESTART_THRESHOLD)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(client.get_image_drift(&query).await.unwrap().is_empty());
+
+    // The legacy lookup still reports that nothing matched
+    let error = client
+        .get_pod_images("default", None, None, None, false)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("pods in namespace default"));
+}
+
+#[tokio::test]
+async fn test_fleet_counts_empty_clusters_as_answered() {
+    let server = FakeApiServer::start(Vec::new()).await;
+    let options = server.client_options(500);
+    let contexts = vec!["fake".to_string()];
+    let resolver = RegistryResolver::default();
+
+    let fleet = query_fleet(&options, &contexts, &resolver, 1, |client| async move {
+        client.query_pod_images(&ImageQuery::default()).await
+    })
+    .await;
+    assert!(fleet.records.is_empty());
+    assert!(fleet.failures.is_empty());
+
+    let fleet = query_fleet(&options, &contexts, &resolver, 1, |client| async move {
+        client
+            .get_registry_usage("default", false, RegistrySource::All)
+            .await
+    })
+    .await;
+    assert!(fleet.records.is_empty());
+    assert!(fleet.failures.is_empty());
+}
+
+#[tokio::test]
+async fn test_registry_usage_pages_through_every_kind() {
+    let deployments = (0..5)
+        .map(|i| {
+            json!({
+                "apiVersion": "apps/v1",
+                "kind": "Deployment",
+                "metadata": {"name": format!("api-{}", i), "namespace": "default"},
+                "spec": {"template": {"spec": {"containers": [
+                    {"name": "api", "image": format!("ghcr.io/acme/api-{}:1.0", i)}
+                ]}}}
+            })
+        })
+        .collect();
+    let pods = create_pods(3)
+        .iter()
+        .map(|pod| serde_json::to_value(pod).unwrap())
+        .collect();
+    let server =
+        FakeApiServer::start_with(vec![("deployments", deployments), ("pods", pods)]).await;
+    let client = server.client(2).await;
+
+    let usage = client
+        .get_registry_usage("default", false, RegistrySource::Workloads)
+        .await
+        .unwrap();
+
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].registry, "ghcr.io");
+    assert_eq!(usage[0].workloads, 8);
+    assert_eq!(usage[0].kinds, vec!["Deployment", "Pod"]);
+
+    let requests = server.requests.lock().unwrap().clone();
+    let count = |plural: &str| {
+        requests
+            .iter()
+            .filter(|request| {
+                request.contains(&format!("/{}?", plural)) && request.contains("limit=2")
+            })
+            .count()
+    };
+    assert_eq!(count("deployments"), 3);
+    assert_eq!(count("pods"), 2);
+    assert_eq!(count("statefulsets"), 1);
+}
+
+#[tokio::test]
+async fn test_registry_usage_lists_metadata_for_owner_checks() {
+    let owned_by = |kind: &str, name: &str| json!([{"apiVersion": "apps/v1", "kind": kind, "name": name, "uid": name, "controller": true}]);
+    let replica_set = |name: &str, owners: Value| {
+        json!({
+            "apiVersion": "apps/v1",
+            "kind": "ReplicaSet",
+            "metadata": {"name": name, "namespace": "default", "ownerReferences": owners},
+            "spec": {"template": {"spec": {"containers": [
+                {"name": "app", "image": format!("quay.io/acme/{}:1.0", name)}
+            ]}}}
+        })
+    };
+    let pod = |name: &str, owners: Value| {
+        json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {"name": name, "namespace": "default", "ownerReferences": owners},
+            "spec": {"containers": [{"name": "app", "image": format!("ghcr.io/acme/{}:1.0", name)}]}
+        })
+    };
+    let server = FakeApiServer::start_with(vec![
+        (
+            "replicasets",
+            vec![
+                replica_set("web-1", owned_by("Deployment", "web")),
+                replica_set("legacy", json!([])),
+            ],
+        ),
+        (
+            "pods",
+            vec![
+                pod("web-1-abc", owned_by("ReplicaSet", "web-1")),
+                pod("debug", json!([])),
+            ],
+        ),
+    ])
+    .await;
+    let client = server.client(500).await;
+
+    let usage = client
+        .get_registry_usage("default", false, RegistrySource::Workloads)
+        .await
+        .unwrap();
+
+    let registries: Vec<(&str, usize)> = usage
+        .iter()
+        .map(|usage| (usage.registry.as_str(), usage.workloads))
+        .collect();
+    assert_eq!(registries, vec![("ghcr.io", 1), ("quay.io", 1)]);
+
+    // Owners are read from metadata lists, and only unowned objects are fetched in full
+    let metadata = server.metadata_requests.lock().unwrap().clone();
+    assert!(metadata
+        .iter()
+        .any(|request| request.ends_with("/replicasets?&limit=500")));
+    assert!(metadata
+        .iter()
+        .any(|request| request.ends_with("/pods?&limit=500")));
+    let requests = server.requests.lock().unwrap().clone();
+    assert!(requests.contains(&"/apis/apps/v1/namespaces/default/replicasets/legacy".to_string()));
+    assert!(requests.contains(&"/api/v1/namespaces/default/pods/debug".to_string()));
+    assert!(!requests.iter().any(|request| request.contains("web-1")));
+}
@@ -0,0 +1,384 @@
+mod common;
+
+use common::{create_realistic_pods, FakeApiServer};
+use kelper::{
+    ContainerType, ImageQuery, LabelIssue, LabelRequirement, LabelTarget, MetadataField,
+    MetadataResource, Pattern, RegistrySource, DEFAULT_RESTART_THRESHOLD,
+};
+use serde_json::json;
+use std::sync::atomic::Ordering;
+
+#[tokio::test]
+async fn test_query_pod_images_reads_projected_pods() {
+    let server = FakeApiServer::start_with(vec![("pods", create_realistic_pods(120))]).await;
+    let client = server.client(50).await;
+
+    let images = client
+        .query_pod_images(&ImageQuery::default())
+        .await
+        .unwrap();
+
+    // An init container, the app and a sidecar per pod
+    assert_eq!(images.len(), 360);
+    let init = &images[0];
+    assert_eq!(init.container_type, ContainerType::Init);
+    assert_eq!(init.container_name, "migrate");
+    let app = &images[1];
+    assert_eq!(app.container_name, "app");
+    assert_eq!(app.namespace, "default");
+    assert_eq!(app.node_name, "node-0");
+    assert_eq!(app.workload, "Deployment/app-0");
+    assert_eq!(app.registry, "123456789012.dkr.ecr.eu-west-1.amazonaws.com");
+    assert_eq!(app.image_version, "1.0");
+    assert_eq!(app.digest, format!("{:064x}", 0));
+    assert_eq!(images[2].container_name, "linkerd-proxy");
+    assert_eq!(images[2].digest, "ab".repeat(32));
+}
+
+#[tokio::test]
+async fn test_pod_lists_are_gzip_compressed() {
+    let server = FakeApiServer::start_with(vec![("pods", create_realistic_pods(100))]).await;
+    let client = server.client(0).await;
+
+    client
+        .query_pod_images(&ImageQuery::default())
+        .await
+        .unwrap();
+    let compressed = server.bytes_sent.swap(0, Ordering::Relaxed);
+    assert!(server.gzipped.load(Ordering::Relaxed) > 0);
+
+    server.gzip.store(false, Ordering::Relaxed);
+    client
+        .query_pod_images(&ImageQuery::default())
+        .await
+        .unwrap();
+    let uncompressed = server.bytes_sent.load(Ordering::Relaxed);
+    assert!(compressed * 5 < uncompressed);
+}
+
+#[tokio::test]
+async fn test_registry_usage_reads_projected_workloads() {
+    let template = |image: &str| {
+        json!({
+            "metadata": {"labels": {"app": "api"}},
+            "spec": {
+                "containers": [{"name": "api", "image": image, "env": [{"name": "A", "value": "1"}]}],
+                "volumes": [{"name": "tmp", "emptyDir": {}}]
+            }
+        })
+    };
+    let deployment = json!({
+        "apiVersion": "apps/v1",
+        "kind": "Deployment",
+        "metadata": {
+            "name": "api",
+            "namespace": "default",
+            "uid": "deployment-uid",
+            "managedFields": [{"manager": "kubectl", "operation": "Apply", "fieldsV1": {}}]
+        },
+        "spec": {"replicas": 2, "selector": {"matchLabels": {"app": "api"}}, "template": template("ghcr.io/acme/api:1.0")}
+    });
+    let replica_set = json!({
+        "apiVersion": "apps/v1",
+        "kind": "ReplicaSet",
+        "metadata": {
+            "name": "api-5d8f",
+            "namespace": "default",
+            "ownerReferences": [{"apiVersion": "apps/v1", "kind": "Deployment", "name": "api", "uid": "deployment-uid", "controller": true}]
+        },
+        "spec": {"selector": {"matchLabels": {"app": "api"}}, "template": template("ghcr.io/acme/api:1.0")}
+    });
+    let cron_job = json!({
+        "apiVersion": "batch/v1",
+        "kind": "CronJob",
+        "metadata": {"name": "backup", "namespace": "default", "uid": "cronjob-uid"},
+        "spec": {
+            "schedule": "0 * * * *",
+            "jobTemplate": {"spec": {"template": {"spec": {
+                "restartPolicy": "Never",
+                "containers": [{"name": "backup", "image": "quay.io/acme/backup:2.0"}]
+            }}}}
+        }
+    });
+    let server = FakeApiServer::start_with(vec![
+        ("deployments", vec![deployment]),
+        ("replicasets", vec![replica_set]),
+        ("cronjobs", vec![cron_job]),
+    ])
+    .await;
+    let client = server.client(500).await;
+
+    let usage = client
+        .get_registry_usage("default", false, RegistrySource::Workloads)
+        .await
+        .unwrap();
+
+    assert_eq!(usage.len(), 2);
+    assert_eq!(usage[0].registry, "ghcr.io");
+    assert_eq!(usage[0].workloads, 1);
+    assert_eq!(usage[0].kinds, vec!["Deployment"]);
+    assert_eq!(usage[1].registry, "quay.io");
+    assert_eq!(usage[1].kinds, vec!["CronJob"]);
+}
+
+#[tokio::test]
+async fn test_registry_usage_counts_pods_without_a_template_owner() {
+    let pod = |name: &str, image: &str, owner: Option<(&str, &str)>| {
+        let owners: Vec<_> = owner
+            .into_iter()
+            .map(|(kind, owner)| {
+                json!({"apiVersion": "v1", "kind": kind, "name": owner, "uid": format!("{}-uid", owner), "controller": true})
+            })
+            .collect();
+        json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {"name": name, "namespace": "default", "ownerReferences": owners},
+            "spec": {"containers": [{"name": "main", "image": image}]}
+        })
+    };
+    let replication_controller = json!({
+        "apiVersion": "v1",
+        "kind": "ReplicationController",
+        "metadata": {"name": "legacy", "namespace": "default"},
+        "spec": {"template": {"spec": {"containers": [{"name": "main", "image": "gcr.io/acme/legacy:1.0"}]}}}
+    });
+    let server = FakeApiServer::start_with(vec![
+        (
+            "pods",
+            vec![
+                // Static pod mirrored by the kubelet
+                pod(
+                    "kube-apiserver-node-0",
+                    "registry.k8s.io/kube-apiserver:v1.33.0",
+                    Some(("Node", "node-0")),
+                ),
+                // Pod of an operator's custom resource
+                pod(
+                    "db-0",
+                    "quay.io/acme/postgres:16",
+                    Some(("PostgresCluster", "db")),
+                ),
+                pod("debug", "docker.io/library/busybox:1.36", None),
+                // Covered by the template of its ReplicationController
+                pod(
+                    "legacy-x7k2p",
+                    "gcr.io/acme/legacy:1.0",
+                    Some(("ReplicationController", "legacy")),
+                ),
+                // Its ReplicaSet is not listed, so the pod is not counted either
+                pod(
+                    "api-5d8f-abcde",
+                    "ghcr.io/acme/api:1.0",
+                    Some(("ReplicaSet", "api-5d8f")),
+                ),
+            ],
+        ),
+        ("replicationcontrollers", vec![replication_controller]),
+    ])
+    .await;
+    let client = server.client(500).await;
+
+    let usage = client
+        .get_registry_usage("default", false, RegistrySource::Workloads)
+        .await
+        .unwrap();
+
+    let registries: Vec<(&str, &[String])> = usage
+        .iter()
+        .map(|usage| (usage.registry.as_str(), usage.kinds.as_slice()))
+        .collect();
+    assert_eq!(
+        registries,
+        vec![
+            ("docker.io", &["Pod".to_string()][..]),
+            ("gcr.io", &["ReplicationController".to_string()][..]),
+            ("quay.io", &["Pod".to_string()][..]),
+            ("registry.k8s.io", &["Pod".to_string()][..]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_get_metadata_lists_only_metadata() {
+    let node = json!({
+        "apiVersion": "v1",
+        "kind": "Node",
+        "metadata": {
+            "name": "node-0",
+            "labels": {"kubernetes.io/hostname": "node-0", "topology.kubernetes.io/zone": "eu-west-1a"},
+            "annotations": {"node.alpha.kubernetes.io/ttl": "0"}
+        },
+        "status": {"images": [{"names": ["ghcr.io/acme/app:1.0"], "sizeBytes": 1}]}
+    });
+    let server = FakeApiServer::start_with(vec![
+        ("pods", create_realistic_pods(3)),
+        ("nodes", vec![node]),
+    ])
+    .await;
+    let client = server.client(500).await;
+
+    let annotations = client
+        .get_metadata(
+            MetadataResource::Pods,
+            MetadataField::Annotations,
+            &ImageQuery::default(),
+            &["prometheus.io/*".parse::<Pattern>().unwrap()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(annotations.len(), 6);
+    assert!(annotations.iter().all(|entry| entry.kind == "Pod"));
+    assert_eq!(annotations[0].key, "prometheus.io/port");
+    assert_eq!(annotations[0].value, "9090");
+
+    let labels = client
+        .get_metadata(
+            MetadataResource::Nodes,
+            MetadataField::Labels,
+            &ImageQuery::default(),
+            &["topology.kubernetes.io/*".parse::<Pattern>().unwrap()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0].kind, "Node");
+    assert_eq!(labels[0].namespace, "");
+    assert_eq!(labels[0].name, "node-0");
+    assert_eq!(labels[0].value, "eu-west-1a");
+
+    // Every list asked the API server for metadata only
+    let lists: Vec<String> = server
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|request| request.contains("limit="))
+        .cloned()
+        .collect();
+    assert_eq!(lists.len(), 2);
+    assert_eq!(*server.metadata_requests.lock().unwrap(), lists);
+}
+
+#[tokio::test]
+async fn test_audit_labels_lists_only_metadata() {
+    let deployment = |name: &str, labels: serde_json::Value| {
+        json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": {"name": name, "namespace": "default", "labels": labels},
+            "spec": {"template": {"spec": {"containers": [{"name": "app", "image": "nginx"}]}}}
+        })
+    };
+    let namespace = json!({
+        "apiVersion": "v1",
+        "kind": "Namespace",
+        "metadata": {"name": "default", "labels": {"team": "platform"}}
+    });
+    let server = FakeApiServer::start_with(vec![
+        ("pods", create_realistic_pods(2)),
+        (
+            "deployments",
+            vec![
+                deployment("api", json!({"team": "payments"})),
+                deployment("worker", json!({"team": "Payments"})),
+                deployment("cron", json!({})),
+            ],
+        ),
+        ("namespaces", vec![namespace]),
+    ])
+    .await;
+    let client = server.client(500).await;
+    let requirements = [LabelRequirement::label("team=^[a-z]+$").unwrap()];
+
+    let audit = client
+        .audit_labels(
+            "default",
+            false,
+            None,
+            &[
+                LabelTarget::Pods,
+                LabelTarget::Workloads,
+                LabelTarget::Namespaces,
+            ],
+            &requirements,
+        )
+        .await
+        .unwrap();
+
+    // 3 deployments, 2 pods and the namespace
+    assert_eq!(audit.objects, 6);
+    let violations: Vec<(&str, &str, LabelIssue)> = audit
+        .violations
+        .iter()
+        .map(|v| (v.kind.as_str(), v.name.as_str(), v.issue))
+        .collect();
+    assert_eq!(
+        violations,
+        vec![
+            ("Deployment", "worker", LabelIssue::Mismatch),
+            ("Deployment", "cron", LabelIssue::Missing),
+            ("Pod", "app-0-7d9f8b00-00000", LabelIssue::Missing),
+            ("Pod", "app-1-7d9f8b01-00001", LabelIssue::Missing),
+        ]
+    );
+
+    let audit = client
+        .audit_labels(
+            "default",
+            false,
+            None,
+            &[LabelTarget::Namespaces],
+            &requirements,
+        )
+        .await
+        .unwrap();
+    assert_eq!(audit.objects, 1);
+    assert!(audit.violations.is_empty());
+
+    // Six workload kinds, pods and namespaces, then namespaces again, all as metadata
+    let lists: Vec<String> = server
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|request| request.contains("limit="))
+        .cloned()
+        .collect();
+    assert_eq!(lists.len(), 9);
+    assert_eq!(*server.metadata_requests.lock().unwrap(), lists);
+}
+
+#[tokio::test]
+async fn test_container_health_reads_projected_probes_and_statuses() {
+    let server = FakeApiServer::start_with(vec![("pods", create_realistic_pods(2))]).await;
+    let client = server.client(500).await;
+
+    let rows = client
+        .get_container_health(&ImageQuery::default(), DEFAULT_RESTART_THRESHOLD)
+        .await
+        .unwrap();
+
+    assert_eq!(rows.len(), 6);
+    let app = &rows[1];
+    assert_eq!(app.container_name, "app");
+    assert_eq!(app.liveness, "http-get http://:8080/healthz");
+    assert_eq!(app.readiness, "http-get http://:8080/ready");
+    assert_eq!(app.startup, "");
+    assert!(app.ready);
+    assert_eq!(app.state, "Running");
+    assert!(rows.iter().all(|row| !row.is_unhealthy()));
+
+    let rows = client
+        .get_container_health(
+            &ImageQuery {
+                image: Some("linkerd/*".parse::<Pattern>().unwrap()),
+                ..Default::default()
+            },
+            DEFAULT_RESTART_THRESHOLD,
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|row| row.container_name == "linkerd-proxy"));
+}